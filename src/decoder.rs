@@ -6,7 +6,7 @@ use std::{
     convert::TryInto,
     ffi::{CStr, CString},
     mem,
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddrV4},
     str,
 };
 
@@ -26,12 +26,36 @@ pub trait Decodable: Sized {
 #[derive(Debug)]
 pub struct Decoder<'a> {
     buffer: &'a [u8],
+    /// length of the buffer the decoder was created with
+    len: usize,
 }
 
 impl<'a> Decoder<'a> {
     /// Create a new Decoder
     pub fn new(buffer: &'a [u8]) -> Self {
-        Decoder { buffer }
+        Decoder {
+            buffer,
+            len: buffer.len(),
+        }
+    }
+
+    /// number of bytes read since the decoder was created
+    /// ```
+    /// # use dhcproto::Decoder;
+    /// let mut d = Decoder::new(&[1, 2, 3]);
+    /// d.read_u16()?;
+    /// assert_eq!(d.offset(), 2);
+    /// # Ok::<(), dhcproto::error::DecodeError>(())
+    /// ```
+    pub fn offset(&self) -> usize {
+        self.len - self.buffer.len()
+    }
+
+    /// move back to `rest`, a suffix of the buffer previously returned by
+    /// [`remaining`](Self::remaining)
+    pub(crate) fn rewind(&mut self, rest: &'a [u8]) {
+        debug_assert!(rest.len() >= self.buffer.len() && rest.len() <= self.len);
+        self.buffer = rest;
     }
 
     /// peek at the next byte without advancing the internal pointer
@@ -72,6 +96,28 @@ impl<'a> Decoder<'a> {
         ))
     }
 
+    /// read a 3 byte, 24-bit, value into a u32
+    pub fn read_u24(&mut self) -> DecodeResult<u32> {
+        let [a, b, c] = self.read::<3>()?;
+        Ok(u32::from_be_bytes([0, a, b, c]))
+    }
+
+    /// read a little-endian u16. DHCP is big-endian, this is only for vendor
+    /// options that embed little-endian integers
+    pub fn read_u16_le(&mut self) -> DecodeResult<u16> {
+        Ok(u16::from_le_bytes(
+            self.read::<{ mem::size_of::<u16>() }>()?,
+        ))
+    }
+
+    /// read a little-endian u32. DHCP is big-endian, this is only for vendor
+    /// options that embed little-endian integers
+    pub fn read_u32_le(&mut self) -> DecodeResult<u32> {
+        Ok(u32::from_le_bytes(
+            self.read::<{ mem::size_of::<u32>() }>()?,
+        ))
+    }
+
     /// read a `N` bytes into slice
     pub fn read<const N: usize>(&mut self) -> DecodeResult<[u8; N]> {
         if N > self.buffer.len() {
@@ -133,6 +179,41 @@ impl<'a> Decoder<'a> {
         Ok(slice)
     }
 
+    /// fill `buf` with the next `buf.len()` bytes, for when the length is only
+    /// known at runtime & the caller already has storage. Doesn't advance if
+    /// there aren't enough bytes
+    /// ```
+    /// # use dhcproto::Decoder;
+    /// let mut chaddr = [0; 16];
+    /// let mut d = Decoder::new(&[1, 2, 3, 4, 5, 6, 7]);
+    /// d.read_exact_into(&mut chaddr[..6])?;
+    /// assert_eq!(chaddr[..6], [1, 2, 3, 4, 5, 6]);
+    /// assert!(d.read_exact_into(&mut chaddr[..2]).is_err());
+    /// assert_eq!(d.read_u8()?, 7);
+    /// # Ok::<(), dhcproto::error::DecodeError>(())
+    /// ```
+    pub fn read_exact_into(&mut self, buf: &mut [u8]) -> DecodeResult<()> {
+        buf.copy_from_slice(self.read_slice(buf.len())?);
+        Ok(())
+    }
+
+    /// return a decoder limited to the next `len` bytes, advancing past them.
+    /// Nested parsers (e.g. sub-options) can't read beyond their region
+    /// ```
+    /// # use dhcproto::Decoder;
+    /// let buf = [2, 0xAA, 0xBB, 0xCC];
+    /// let mut d = Decoder::new(&buf);
+    /// let len = d.read_u8()? as usize;
+    /// let mut sub = d.sub_decoder(len)?;
+    /// assert_eq!(sub.read_u16()?, 0xAABB);
+    /// assert!(sub.read_u8().is_err());
+    /// assert_eq!(d.read_u8()?, 0xCC);
+    /// # Ok::<(), dhcproto::error::DecodeError>(())
+    /// ```
+    pub fn sub_decoder(&mut self, len: usize) -> DecodeResult<Decoder<'a>> {
+        Ok(Decoder::new(self.read_slice(len)?))
+    }
+
     /// Read a utf-8 encoded String
     pub fn read_string(&mut self, len: usize) -> DecodeResult<String> {
         Ok(self.read_str(len)?.to_owned())
@@ -152,6 +233,12 @@ impl<'a> Decoder<'a> {
         Ok(bytes.into())
     }
 
+    /// Read an ipv4 addr followed by a u16 port
+    pub fn read_socketaddr_v4(&mut self) -> DecodeResult<SocketAddrV4> {
+        let ip = self.read_ipv4(4)?;
+        Ok(SocketAddrV4::new(ip, self.read_u16()?))
+    }
+
     /// Read a list of ipv4 addrs
     pub fn read_ipv4s(&mut self, length: usize) -> DecodeResult<Vec<Ipv4Addr>> {
         // must be multiple of 4
@@ -179,6 +266,19 @@ impl<'a> Decoder<'a> {
             .collect::<Result<Vec<Ipv6Addr>, _>>()?)
     }
 
+    /// Read a list of u16s
+    pub fn read_u16s(&mut self, length: usize) -> DecodeResult<Vec<u16>> {
+        // must be multiple of 2
+        if length % 2 != 0 {
+            return Err(DecodeError::NotEnoughBytes);
+        }
+        let bytes = self.read_slice(length)?;
+        Ok(bytes
+            .chunks(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect())
+    }
+
     /// Read a list of ipv4 pairs
     pub fn read_pair_ipv4s(&mut self, length: usize) -> DecodeResult<Vec<(Ipv4Addr, Ipv4Addr)>> {
         // must be multiple of 8
@@ -206,4 +306,64 @@ impl<'a> Decoder<'a> {
     pub fn buffer(&self) -> &[u8] {
         self.buffer
     }
+
+    /// unread data, borrowed for the lifetime of the underlying buffer
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_endianness() -> DecodeResult<()> {
+        let buf = [0x12, 0x34, 0x56, 0x78];
+        assert_eq!(Decoder::new(&buf).read_u16()?, 0x1234);
+        assert_eq!(Decoder::new(&buf).read_u16_le()?, 0x3412);
+        assert_eq!(Decoder::new(&buf).read_u32()?, 0x12345678);
+        assert_eq!(Decoder::new(&buf).read_u32_le()?, 0x78563412);
+
+        let mut dec = Decoder::new(&buf[..3]);
+        assert_eq!(dec.read_u16_le()?, 0x3412);
+        assert!(dec.read_u16_le().is_err());
+        assert!(Decoder::new(&buf[..3]).read_u32_le().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn decode_u24() -> DecodeResult<()> {
+        let mut dec = Decoder::new(&[0x12, 0x34, 0x56, 0xFF, 0xFF]);
+        assert_eq!(dec.read_u24()?, 0x123456);
+        assert!(dec.read_u24().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_exact_into() -> DecodeResult<()> {
+        let mut dec = Decoder::new(&[1, 2, 3]);
+        let mut buf = [0; 2];
+        dec.read_exact_into(&mut buf)?;
+        assert_eq!(buf, [1, 2]);
+        dec.read_exact_into(&mut [])?;
+        assert!(dec.read_exact_into(&mut buf).is_err());
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(dec.buffer(), &[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sub_decoder() -> DecodeResult<()> {
+        let buf = [1, 2, 3, 4, 5];
+        let mut dec = Decoder::new(&buf);
+        let mut sub = dec.sub_decoder(3)?;
+        assert_eq!(sub.buffer(), &[1, 2, 3]);
+        assert_eq!(dec.buffer(), &[4, 5]);
+        assert!(sub.read::<4>().is_err());
+        assert!(dec.sub_decoder(3).is_err());
+        // failed call doesn't advance
+        assert_eq!(dec.buffer(), &[4, 5]);
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
 //! Encodable trait & Encoder
 use crate::error::{EncodeError, EncodeResult};
 
+use std::net::SocketAddrV4;
+
 /// A trait for types which are deserializable to DHCP binary formats
 pub trait Encodable {
     /// Read the type from the stream
@@ -14,6 +16,27 @@ pub trait Encodable {
         Ok(buffer)
     }
 }
+/// Default maximum length of a buffer written by [`Encoder`], the largest
+/// UDP payload of an IPv4 datagram (65535 less the 20 byte IP & 8 byte UDP
+/// headers). The limit covers the whole buffer, including any bytes before
+/// the offset given to [`Encoder::with_offset`]
+pub const DEFAULT_MAX_LEN: usize = u16::MAX as usize - 20 - 8;
+
+/// A position in an [`Encoder`]'s buffer & the bytes the buffer held past
+/// it, see [`Encoder::checkpoint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    offset: usize,
+    tail: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// offset of the next byte written at the checkpoint
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
 /// Encoder type, holds a mut ref to a buffer
 /// that it will write data to and an offset
 /// of the next position to write
@@ -21,12 +44,59 @@ pub trait Encodable {
 pub struct Encoder<'a> {
     buffer: &'a mut Vec<u8>,
     offset: usize,
+    max_len: usize,
 }
 
 impl<'a> Encoder<'a> {
     /// Create a new Encoder from a mutable buffer
     pub fn new(buffer: &'a mut Vec<u8>) -> Self {
-        Self { buffer, offset: 0 }
+        Self::with_offset(buffer, 0)
+    }
+
+    /// Create a new Encoder that starts writing at `offset` within `buffer`.
+    /// Bytes before `offset` are left untouched. If the buffer is shorter
+    /// than `offset` it is extended with zeros.
+    ///
+    /// Useful for leaving room for headers in a preallocated frame:
+    /// ```
+    /// # use dhcproto::Encoder;
+    /// let mut frame = vec![0xAA; 4];
+    /// let mut e = Encoder::with_offset(&mut frame, 4);
+    /// e.write_u16(42)?;
+    /// assert_eq!(e.offset(), 6);
+    /// assert_eq!(frame, [0xAA, 0xAA, 0xAA, 0xAA, 0, 42]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_offset(buffer: &'a mut Vec<u8>, offset: usize) -> Self {
+        if buffer.len() < offset {
+            buffer.resize(offset, 0);
+        }
+        Self {
+            buffer,
+            offset,
+            max_len: DEFAULT_MAX_LEN,
+        }
+    }
+
+    /// Set the maximum length of the buffer, writes that would extend the
+    /// buffer past `max_len` return [`EncodeError::MaxLenExceeded`] instead.
+    /// Defaults to [`DEFAULT_MAX_LEN`]
+    /// ```
+    /// # use dhcproto::Encoder;
+    /// let mut buf = Vec::new();
+    /// let mut e = Encoder::new(&mut buf);
+    /// e.set_max_len(4);
+    /// assert!(e.write_u32(1).is_ok());
+    /// assert!(e.write_u8(1).is_err());
+    /// ```
+    pub fn set_max_len(&mut self, max_len: usize) -> &mut Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Get the maximum length of the buffer
+    pub fn max_len(&self) -> usize {
+        self.max_len
     }
 
     /// Get a reference to the underlying buffer
@@ -34,57 +104,90 @@ impl<'a> Encoder<'a> {
         self.buffer
     }
 
+    /// Position in the buffer where the next byte will be written
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Save the current position & any bytes the buffer already holds past
+    /// it, to [`rollback`](Encoder::rollback) to if a speculative write
+    /// doesn't fit. Only allocates if the buffer extends past the position,
+    /// e.g. a preallocated frame given to [`with_offset`](Encoder::with_offset)
+    /// ```
+    /// # use dhcproto::Encoder;
+    /// let mut buf = Vec::new();
+    /// let mut e = Encoder::new(&mut buf);
+    /// e.set_max_len(6);
+    /// e.write_u16(1)?;
+    /// let checkpoint = e.checkpoint();
+    /// e.write_u16(2)?;
+    /// if e.write_u32(3).is_err() {
+    ///     e.rollback(checkpoint);
+    /// }
+    /// e.write_u8(4)?;
+    /// assert_eq!(e.buffer(), [0, 1, 4]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            offset: self.offset,
+            tail: self.buffer.get(self.offset..).unwrap_or_default().to_vec(),
+        }
+    }
+
+    /// Return to a position saved with [`checkpoint`](Encoder::checkpoint),
+    /// the buffer past it is restored to what it held at the checkpoint:
+    /// bytes appended since are discarded & bytes overwritten since, e.g.
+    /// past the offset given to [`with_offset`](Encoder::with_offset), are
+    /// put back. A checkpoint past the current position is ignored
+    /// ```
+    /// # use dhcproto::Encoder;
+    /// let mut frame = vec![0xAA; 4];
+    /// let mut e = Encoder::with_offset(&mut frame, 2);
+    /// let checkpoint = e.checkpoint();
+    /// e.write_slice(&[1, 2, 3])?;
+    /// e.rollback(checkpoint);
+    /// assert_eq!(e.offset(), 2);
+    /// assert_eq!(e.buffer(), [0xAA; 4]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        if checkpoint.offset <= self.offset {
+            self.buffer.resize(checkpoint.offset, 0);
+            self.buffer.extend_from_slice(&checkpoint.tail);
+            self.offset = checkpoint.offset;
+        }
+    }
+
     /// write bytes to buffer
     /// Return:
     ///     number of bytes written
     pub fn write_slice(&mut self, bytes: &[u8]) -> EncodeResult<()> {
         let additional = bytes.len();
-        // space already reserved, we may not need this
-        if self.offset + additional <= self.buffer.len() {
-            // if self.offset == self.buffer.len() indexing can panic
-            for (byte, b) in self.buffer[self.offset..].iter_mut().zip(bytes.iter()) {
-                *byte = *b;
-            }
-        } else {
-            let expected_len = self.buffer.len() + additional;
-            self.buffer.reserve(additional);
-            self.buffer.extend_from_slice(bytes);
-
-            debug_assert!(self.buffer.len() == expected_len);
-        }
-
         let index = self
             .offset
             .checked_add(additional)
             .ok_or(EncodeError::AddOverflow)?;
+        if index > self.max_len {
+            return Err(EncodeError::MaxLenExceeded { max: self.max_len });
+        }
+        // overwrite any space already reserved, then append the remainder
+        let overlap = self
+            .buffer
+            .len()
+            .saturating_sub(self.offset)
+            .min(additional);
+        self.buffer[self.offset..self.offset + overlap].copy_from_slice(&bytes[..overlap]);
+        self.buffer.extend_from_slice(&bytes[overlap..]);
+        debug_assert!(self.buffer.len() >= index);
+
         self.offset = index;
         Ok(())
     }
 
     /// Write const number of bytes to buffer
     pub fn write<const N: usize>(&mut self, bytes: [u8; N]) -> EncodeResult<()> {
-        // TODO: refactor this and above method?
-        // only difference is zip & extend
-        let additional = bytes.len();
-        // space already reserved, we may not need this
-        if self.offset + additional <= self.buffer.len() {
-            // if self.offset == self.buffer.len() indexing can panic
-            for (byte, b) in self.buffer[self.offset..].iter_mut().zip(bytes) {
-                *byte = b;
-            }
-        } else {
-            let expected_len = self.buffer.len() + additional;
-            self.buffer.reserve(additional);
-            self.buffer.extend(bytes);
-            debug_assert!(self.buffer.len() == expected_len);
-        }
-
-        let index = self
-            .offset
-            .checked_add(additional)
-            .ok_or(EncodeError::AddOverflow)?;
-        self.offset = index;
-        Ok(())
+        self.write_slice(&bytes)
     }
 
     /// write a u8
@@ -99,6 +202,28 @@ impl<'a> Encoder<'a> {
     pub fn write_u32(&mut self, data: u32) -> EncodeResult<()> {
         self.write(data.to_be_bytes())
     }
+    /// write the low 3 bytes of a u32, for 24-bit fields. Returns
+    /// [`EncodeError::IntTooWide`] if `data` doesn't fit in 24 bits
+    pub fn write_u24(&mut self, data: u32) -> EncodeResult<()> {
+        if data > 0x00FF_FFFF {
+            return Err(EncodeError::IntTooWide {
+                value: data.into(),
+                bits: 24,
+            });
+        }
+        let [_, bytes @ ..] = data.to_be_bytes();
+        self.write(bytes)
+    }
+    /// write a little-endian u16. DHCP is big-endian, this is only for vendor
+    /// options that embed little-endian integers
+    pub fn write_u16_le(&mut self, data: u16) -> EncodeResult<()> {
+        self.write(data.to_le_bytes())
+    }
+    /// write a little-endian u32. DHCP is big-endian, this is only for vendor
+    /// options that embed little-endian integers
+    pub fn write_u32_le(&mut self, data: u32) -> EncodeResult<()> {
+        self.write(data.to_le_bytes())
+    }
     /// write a u128
     pub fn write_u128(&mut self, data: u128) -> EncodeResult<()> {
         self.write(data.to_be_bytes())
@@ -111,7 +236,14 @@ impl<'a> Encoder<'a> {
     pub fn write_i32(&mut self, data: i32) -> EncodeResult<()> {
         self.write(data.to_be_bytes())
     }
-    /// Writes bytes to buffer and pads with 0 bytes up to some fill_len
+    /// write an ipv4 addr followed by a u16 port
+    pub fn write_socketaddr_v4(&mut self, addr: SocketAddrV4) -> EncodeResult<()> {
+        self.write_u32((*addr.ip()).into())?;
+        self.write_u16(addr.port())
+    }
+    /// Writes bytes to buffer and pads with 0 bytes up to some fill_len.
+    /// Values are never truncated, if bytes fills fill_len exactly no NUL
+    /// terminator is written
     ///
     /// Returns
     ///    Err - if bytes.len() is greater then fill_len
@@ -127,7 +259,8 @@ impl<'a> Encoder<'a> {
         Ok(())
     }
     /// Writes value to buffer and pads with 0 bytes up to some fill_len
-    /// if String is None then write fill_len 0 bytes
+    /// if String is None then write fill_len 0 bytes. Like
+    /// [`write_fill_bytes`](Self::write_fill_bytes) values are never truncated
     ///
     /// Returns
     ///    Err - if bytes.len() is greater then fill_len
@@ -180,4 +313,123 @@ mod tests {
         assert_eq!(enc.offset, 4);
         Ok(())
     }
+
+    #[test]
+    fn checkpoint_rollback() -> EncodeResult<()> {
+        let mut buf = vec![0xAA; 2];
+        let mut enc = Encoder::with_offset(&mut buf, 2);
+        let checkpoint = enc.checkpoint();
+        enc.write_slice(&[1, 2, 3])?;
+        let later = enc.checkpoint();
+        enc.rollback(checkpoint);
+        assert_eq!(enc.offset(), 2);
+        assert_eq!(enc.buffer(), [0xAA, 0xAA]);
+        // past the current position
+        enc.rollback(later);
+        assert_eq!(enc.offset(), 2);
+        enc.write_u8(4)?;
+        assert_eq!(enc.buffer(), [0xAA, 0xAA, 4]);
+
+        // overwritten preallocated bytes are restored
+        let mut buf = vec![0xAA, 0xBB, 0xCC, 0xDD];
+        let mut enc = Encoder::with_offset(&mut buf, 1);
+        let checkpoint = enc.checkpoint();
+        assert_eq!(checkpoint.offset(), 1);
+        enc.write_slice(&[1, 2, 3, 4, 5])?;
+        enc.rollback(checkpoint);
+        assert_eq!(enc.buffer(), [0xAA, 0xBB, 0xCC, 0xDD]);
+        enc.write_u8(1)?;
+        let checkpoint = enc.checkpoint();
+        enc.write_u8(2)?;
+        enc.rollback(checkpoint);
+        assert_eq!(enc.buffer(), [0xAA, 1, 0xCC, 0xDD]);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_partial_overlap() -> EncodeResult<()> {
+        let mut buf = vec![0, 1, 2, 3];
+        let mut enc = Encoder::with_offset(&mut buf, 3);
+        enc.write_slice(&[9, 8, 7])?;
+        assert_eq!(enc.buffer, &mut vec![0, 1, 2, 9, 8, 7]);
+        assert_eq!(enc.offset(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_max_len() -> EncodeResult<()> {
+        let mut buf = vec![];
+        let mut enc = Encoder::new(&mut buf);
+        enc.write_slice(&[0; DEFAULT_MAX_LEN])?;
+        assert!(matches!(
+            enc.write_u8(0),
+            Err(EncodeError::MaxLenExceeded {
+                max: DEFAULT_MAX_LEN
+            })
+        ));
+        // nothing written on error
+        assert_eq!(enc.offset(), DEFAULT_MAX_LEN);
+        assert_eq!(buf.len(), DEFAULT_MAX_LEN);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_socketaddr_v4() -> Result<(), Box<dyn std::error::Error>> {
+        let addr = "192.168.0.1:67".parse::<SocketAddrV4>()?;
+        let mut buf = vec![];
+        Encoder::new(&mut buf).write_socketaddr_v4(addr)?;
+        assert_eq!(buf, vec![192, 168, 0, 1, 0, 67]);
+        let mut dec = crate::Decoder::new(&buf);
+        assert_eq!(dec.read_socketaddr_v4()?, addr);
+        assert!(dec.read_socketaddr_v4().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn encode_endianness() -> EncodeResult<()> {
+        let mut buf = vec![];
+        let mut enc = Encoder::new(&mut buf);
+        enc.write_u16(0x1234)?;
+        enc.write_u16_le(0x1234)?;
+        enc.write_u32(0x12345678)?;
+        enc.write_u32_le(0x12345678)?;
+        assert_eq!(
+            buf,
+            vec![0x12, 0x34, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78, 0x78, 0x56, 0x34, 0x12]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn encode_u24() -> EncodeResult<()> {
+        let mut buf = vec![];
+        let mut enc = Encoder::new(&mut buf);
+        enc.write_u24(0x123456)?;
+        enc.write_u24(0xFFFFFF)?;
+        assert_eq!(buf, vec![0x12, 0x34, 0x56, 0xFF, 0xFF, 0xFF]);
+        Ok(())
+    }
+
+    #[test]
+    fn encode_u24_overflow() {
+        let mut buf = vec![];
+        assert!(matches!(
+            Encoder::new(&mut buf).write_u24(0x0100_0000),
+            Err(EncodeError::IntTooWide {
+                value: 0x0100_0000,
+                bits: 24
+            })
+        ));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn encode_with_offset() -> EncodeResult<()> {
+        // offset past the end of the buffer is zero filled
+        let mut buf = vec![1];
+        let mut enc = Encoder::with_offset(&mut buf, 3);
+        enc.write_u16(0xABCD)?;
+        assert_eq!(buf, vec![1, 0, 0, 0xAB, 0xCD]);
+        Ok(())
+    }
 }
@@ -44,11 +44,111 @@ pub enum DecodeError {
     #[error("url parse error")]
     UrlParseError(#[from] url::ParseError),
 
+    /// domain name parse error from trust-dns
+    #[error("domain parse error {0}")]
+    DomainParseError(#[from] trust_dns_proto::error::ProtoError),
+
+    /// magic cookie wasn't found when scanning for it
+    #[error("magic cookie not found within {window} bytes of the file field")]
+    MagicCookieNotFound {
+        /// number of bytes searched
+        window: usize,
+    },
+
+    /// DHCP message without a Message Type option (53), see
+    /// [`v4::OptionsMode::Dhcp`](crate::v4::OptionsMode::Dhcp)
+    #[error("DHCP message is missing the message type option (53)")]
+    MissingMessageType,
+
+    /// magic cookie repeated straight after the cookie, in strict decoding
+    #[error("magic cookie repeated at byte {offset}")]
+    DuplicateMagicCookie {
+        /// offset of the repeated cookie within the decoder's buffer
+        offset: usize,
+    },
+
+    /// invalid prefix length of a network
+    #[error("prefix length error {0}")]
+    PrefixLenError(#[from] ipnet::PrefixLenError),
+
+    /// singleton option appeared more than once, in strict decoding
+    #[error("option {code:?} must appear at most once")]
+    DuplicateOption {
+        /// code of the duplicated option
+        code: crate::v4::OptionCode,
+    },
+
+    /// error decoding a header field
+    #[error("failed to decode {field} at byte {offset}: {source}")]
+    InField {
+        /// name of the field
+        field: &'static str,
+        /// offset of the field within the decoder's buffer
+        offset: usize,
+        /// the underlying error
+        #[source]
+        source: Box<DecodeError>,
+    },
+
+    /// error decoding an option
+    #[error("failed to decode option {code} at byte {offset}: {source}")]
+    InOption {
+        /// option code
+        code: u8,
+        /// offset of the option's code within the decoder's buffer
+        offset: usize,
+        /// the underlying error
+        #[source]
+        source: Box<DecodeError>,
+    },
+
     /// Unknown decode error
     #[error("unknown error")]
     Unknown(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
 
+impl DecodeError {
+    /// the underlying error, with any field or option context removed
+    /// ```
+    /// # use dhcproto::{Decodable, error::DecodeError, v4::Message};
+    /// let err = Message::from_bytes(&[2, 1, 6]).unwrap_err();
+    /// assert!(matches!(err, DecodeError::InField { field: "hops", offset: 3, .. }));
+    /// assert!(matches!(err.kind(), DecodeError::NotEnoughBytes));
+    /// ```
+    pub fn kind(&self) -> &DecodeError {
+        match self {
+            DecodeError::InField { source, .. } | DecodeError::InOption { source, .. } => {
+                source.kind()
+            }
+            err => err,
+        }
+    }
+    /// offset within the decoder's buffer where decoding failed, if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DecodeError::InField { offset, .. } | DecodeError::InOption { offset, .. } => {
+                Some(*offset)
+            }
+            DecodeError::EndOfBuffer { index } => Some(*index),
+            _ => None,
+        }
+    }
+    pub(crate) fn in_field(self, field: &'static str, offset: usize) -> Self {
+        DecodeError::InField {
+            field,
+            offset,
+            source: Box::new(self),
+        }
+    }
+    pub(crate) fn in_option(self, code: u8, offset: usize) -> Self {
+        DecodeError::InOption {
+            code,
+            offset,
+            source: Box::new(self),
+        }
+    }
+}
+
 /// Returned from types that encode
 #[derive(Error, Debug)]
 pub enum EncodeError {
@@ -65,6 +165,49 @@ pub enum EncodeError {
         len: usize,
     },
 
+    /// option value doesn't fit in a single length byte & can't be split
+    #[error("value of option {code} is {len} bytes, exceeding the max of 255")]
+    OptionValueTooLong {
+        /// option (or sub-option) code
+        code: u8,
+        /// length of the value
+        len: usize,
+    },
+
+    /// option value is empty, but must be at least 1 byte
+    #[error("value of option {code} is empty")]
+    EmptyOptionValue {
+        /// option (or sub-option) code
+        code: u8,
+    },
+
+    /// value doesn't fit in a fixed size header field
+    #[error("{field} is {len} bytes, exceeding the max of {max}")]
+    FieldTooLong {
+        /// name of the field
+        field: &'static str,
+        /// length of the value
+        len: usize,
+        /// max length of the value, excluding the NUL terminator
+        max: usize,
+    },
+
+    /// integer doesn't fit in the width of the field it's written to
+    #[error("{value:#x} doesn't fit in {bits} bits")]
+    IntTooWide {
+        /// the integer
+        value: u64,
+        /// width of the field
+        bits: u8,
+    },
+
+    /// buffer would exceed the encoder's max length
+    #[error("encoded message would exceed the max length of {max} bytes")]
+    MaxLenExceeded {
+        /// max length of the buffer
+        max: usize,
+    },
+
     /// io error
     #[error("io error {0}")]
     IoError(#[from] io::Error),
@@ -72,6 +215,102 @@ pub enum EncodeError {
     /// DNS encoding error from trust-dns
     #[error("domain encoding error {0}")]
     DomainEncodeError(#[from] trust_dns_proto::error::ProtoError),
+
+    /// error encoding a header field
+    #[error("failed to encode {field}: {source}")]
+    InField {
+        /// name of the field
+        field: &'static str,
+        /// the underlying error
+        #[source]
+        source: Box<EncodeError>,
+    },
+
+    /// error encoding an option
+    #[error("failed to encode option {code}: {source}")]
+    InOption {
+        /// option code
+        code: u8,
+        /// the underlying error
+        #[source]
+        source: Box<EncodeError>,
+    },
+}
+
+impl EncodeError {
+    /// the underlying error, with any field or option context removed
+    pub fn kind(&self) -> &EncodeError {
+        match self {
+            EncodeError::InField { source, .. } | EncodeError::InOption { source, .. } => {
+                source.kind()
+            }
+            err => err,
+        }
+    }
+    pub(crate) fn in_field(self, field: &'static str) -> Self {
+        EncodeError::InField {
+            field,
+            source: Box::new(self),
+        }
+    }
+    pub(crate) fn in_option(self, code: u8) -> Self {
+        EncodeError::InOption {
+            code,
+            source: Box::new(self),
+        }
+    }
+}
+
+/// Returned from [`v4::Message::validate`](crate::v4::Message::validate)
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// DHCP message without a Message Type option (53)
+    #[error("DHCP message is missing the message type option (53)")]
+    MissingMessageType,
+
+    /// opcode doesn't match the direction of the message type
+    #[error("opcode {opcode:?} doesn't match message type {msg_type:?}")]
+    OpcodeMismatch {
+        /// opcode of the message
+        opcode: crate::v4::Opcode,
+        /// message type (option 53)
+        msg_type: crate::v4::MessageType,
+    },
+
+    /// hlen doesn't match the address length of the hardware type
+    #[error("hlen {hlen} doesn't match the expected {expected} of htype {htype}")]
+    HlenMismatch {
+        /// hardware type of the message
+        htype: crate::v4::HType,
+        /// hlen of the message
+        hlen: u8,
+        /// hlen expected for `htype`, see [`HType::expected_hlen`](crate::v4::HType::expected_hlen)
+        expected: u8,
+    },
+}
+
+/// Returned from [`v4::Message::relay_forward`](crate::v4::Message::relay_forward)
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RelayError {
+    /// forwarding would take the message's hops past the limit
+    #[error("hops {hops} would exceed the limit of {max}")]
+    HopsExceeded {
+        /// hops of the message before forwarding
+        hops: u8,
+        /// max hops allowed
+        max: u8,
+    },
+}
+
+/// Returned from the `FromStr` impls of [`v4::MessageType`](crate::v4::MessageType),
+/// [`v4::Opcode`](crate::v4::Opcode) & [`v4::HType`](crate::v4::HType)
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unrecognized {kind} {name:?}")]
+pub struct ParseNameError {
+    /// the type being parsed
+    pub kind: &'static str,
+    /// the name that failed to parse
+    pub name: String,
 }
 
 /// Convenience type for encode errors
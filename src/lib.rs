@@ -95,7 +95,7 @@ pub mod error;
 pub mod v4;
 pub mod v6;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Domain(Name);
 
 impl AsRef<Name> for Domain {
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 /// Lease query data source flags
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Default, Clone, PartialEq, Eq)]
+#[derive(Copy, Default, Clone, PartialEq, Eq, Hash)]
 pub struct DataSourceFlags(u8);
 
 impl fmt::Debug for DataSourceFlags {
@@ -0,0 +1,124 @@
+//! Classless Static Route option (121) - <https://www.rfc-editor.org/rfc/rfc3442.html>
+use std::net::Ipv4Addr;
+
+pub use ipnet::Ipv4Net;
+
+use crate::{
+    decoder::{Decodable, Decoder},
+    encoder::{Encodable, Encoder},
+    error::{DecodeResult, EncodeResult},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A route from the Classless Static Route option. On the wire the
+/// destination is encoded as its prefix length followed by only the
+/// significant octets of the network address.
+///
+/// ```text
+/// +--------+------------------------+---------------+
+/// | prefix | significant octets     | router (4)    |
+/// +--------+------------------------+---------------+
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ClasslessRoute {
+    destination: Ipv4Net,
+    router: Ipv4Addr,
+}
+
+impl ClasslessRoute {
+    /// Create a new route to `destination` via `router`. A `router` of
+    /// `0.0.0.0` means the destination is directly reachable on the link
+    pub fn new(destination: Ipv4Net, router: Ipv4Addr) -> Self {
+        Self {
+            destination,
+            router,
+        }
+    }
+    /// get the destination network
+    pub fn destination(&self) -> Ipv4Net {
+        self.destination
+    }
+    /// get the router
+    pub fn router(&self) -> Ipv4Addr {
+        self.router
+    }
+    /// returns the route as a `(destination, gateway)` pair. Host bits of the
+    /// destination are cleared, only the significant octets are sent so they
+    /// don't survive encoding anyway
+    /// ```
+    /// # use dhcproto::v4::classless::ClasslessRoute;
+    /// let route = ClasslessRoute::new("10.1.2.3/16".parse()?, [10, 0, 0, 1].into());
+    /// assert_eq!(
+    ///     route.to_route_entry(),
+    ///     ("10.1.0.0/16".parse()?, [10, 0, 0, 1].into())
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_route_entry(&self) -> (Ipv4Net, Ipv4Addr) {
+        (self.destination.trunc(), self.router)
+    }
+    /// number of significant octets of the destination
+    fn octets_len(&self) -> usize {
+        (self.destination.prefix_len() as usize + 7) / 8
+    }
+    /// number of bytes the route encodes to
+    pub(crate) fn encoded_len(&self) -> usize {
+        1 + self.octets_len() + 4
+    }
+}
+
+impl Decodable for ClasslessRoute {
+    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        let prefix_len = decoder.read_u8()?;
+        let mut octets = [0; 4];
+        let len = (prefix_len as usize + 7) / 8;
+        // prefix len validated below, don't read past the octets
+        let significant = decoder.read_slice(len.min(4))?;
+        octets[..significant.len()].copy_from_slice(significant);
+        Ok(Self {
+            destination: Ipv4Net::new(octets.into(), prefix_len)?,
+            router: decoder.read_ipv4(4)?,
+        })
+    }
+}
+
+impl Encodable for ClasslessRoute {
+    fn encode(&self, e: &mut Encoder<'_>) -> EncodeResult<()> {
+        let (destination, router) = self.to_route_entry();
+        e.write_u8(destination.prefix_len())?;
+        e.write_slice(&destination.addr().octets()[..self.octets_len()])?;
+        e.write_u32(router.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() -> Result<(), Box<dyn std::error::Error>> {
+        // examples from RFC 3442
+        let routes = [
+            ("0.0.0.0/0", vec![0]),
+            ("10.0.0.0/8", vec![8, 10]),
+            ("10.17.0.0/16", vec![16, 10, 17]),
+            ("10.229.0.128/25", vec![25, 10, 229, 0, 128]),
+            ("10.198.122.47/32", vec![32, 10, 198, 122, 47]),
+        ];
+        for (dest, mut buf) in routes {
+            buf.extend([192, 168, 0, 1]);
+            let route = ClasslessRoute::new(dest.parse()?, [192, 168, 0, 1].into());
+            assert_eq!(route.to_vec()?, buf);
+            assert_eq!(route.encoded_len(), buf.len());
+            assert_eq!(ClasslessRoute::decode(&mut Decoder::new(&buf))?, route);
+        }
+        // prefix too long
+        assert!(
+            ClasslessRoute::decode(&mut Decoder::new(&[33, 1, 2, 3, 4, 5, 0, 0, 0, 0])).is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! DHCP client fingerprint, as used for passive OS identification
+//!
+//! Clients are identified by the Parameter Request List (55), in the order
+//! the client sent it, along with the Vendor Class Identifier (60).
+//! ```rust
+//! use dhcproto::v4::{DhcpOption, Message, OptionCode};
+//!
+//! let mut msg = Message::default();
+//! msg.opts_mut().insert(DhcpOption::ParameterRequestList(vec![
+//!     OptionCode::SubnetMask,
+//!     OptionCode::Router,
+//!     OptionCode::DomainNameServer,
+//!     OptionCode::DomainName,
+//! ]));
+//! msg.opts_mut()
+//!     .insert(DhcpOption::ClassIdentifier(b"MSFT 5.0".to_vec()));
+//!
+//! let fp = msg.fingerprint();
+//! assert_eq!(fp.params(), &[1, 3, 6, 15]);
+//! assert_eq!(fp.vendor_class(), Some(&b"MSFT 5.0"[..]));
+//! assert_eq!(fp.to_string(), "1,3,6,15");
+//! ```
+use std::fmt;
+
+use crate::v4::{DhcpOption, DhcpOptions, OptionCode};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Ordered Parameter Request List codes & Vendor Class Identifier of a message.
+/// Two fingerprints are equal only if the codes were requested in the same order
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint {
+    params: Vec<u8>,
+    vendor_class: Option<Vec<u8>>,
+}
+
+impl Fingerprint {
+    /// Create a fingerprint from option codes & a vendor class
+    pub fn new(params: Vec<u8>, vendor_class: Option<Vec<u8>>) -> Self {
+        Self {
+            params,
+            vendor_class,
+        }
+    }
+    /// the Parameter Request List codes, in the order they were sent
+    pub fn params(&self) -> &[u8] {
+        &self.params
+    }
+    /// the Vendor Class Identifier, if sent
+    pub fn vendor_class(&self) -> Option<&[u8]> {
+        self.vendor_class.as_deref()
+    }
+    /// true if the message had neither a Parameter Request List nor a Vendor Class
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty() && self.vendor_class.is_none()
+    }
+}
+
+impl From<&DhcpOptions> for Fingerprint {
+    fn from(opts: &DhcpOptions) -> Self {
+        let params = match opts.get(OptionCode::ParameterRequestList) {
+            Some(DhcpOption::ParameterRequestList(codes)) => {
+                codes.iter().map(|&code| code.into()).collect()
+            }
+            _ => Vec::new(),
+        };
+        let vendor_class = match opts.get(OptionCode::ClassIdentifier) {
+            Some(DhcpOption::ClassIdentifier(class)) => Some(class.clone()),
+            _ => None,
+        };
+        Self::new(params, vendor_class)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    /// writes the Parameter Request List as comma separated codes, the usual
+    /// format of fingerprint databases
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, code) in self.params.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", code)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decoder::Decodable, v4::Message};
+
+    #[test]
+    fn test_fingerprint_order() {
+        let codes = [OptionCode::DomainNameServer, OptionCode::Router];
+        let mut msg = Message::default();
+        msg.opts_mut()
+            .insert(DhcpOption::ParameterRequestList(codes.to_vec()));
+        let mut rev = Message::default();
+        rev.opts_mut().insert(DhcpOption::ParameterRequestList(
+            codes.iter().rev().copied().collect(),
+        ));
+        assert_eq!(msg.fingerprint().params(), &[6, 3]);
+        assert_ne!(msg.fingerprint(), rev.fingerprint());
+        assert!(Message::default().fingerprint().is_empty());
+
+        // order & unknown codes survive decoding
+        let mut buf = crate::v4::test_fixtures::bootreq();
+        buf.truncate(236);
+        buf.extend([99, 130, 83, 99, 55, 4, 121, 1, 250, 3, 255]);
+        let msg = Message::from_bytes(&buf).unwrap();
+        assert_eq!(msg.fingerprint().params(), &[121, 1, 250, 3]);
+        assert_eq!(msg.fingerprint().to_string(), "121,1,250,3");
+        assert_eq!(msg.fingerprint().vendor_class(), None);
+    }
+}
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 /// Represents available flags on message
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Default, Clone, PartialEq, Eq)]
+#[derive(Copy, Default, Clone, PartialEq, Eq, Hash)]
 pub struct Flags(u16);
 
 impl fmt::Debug for Flags {
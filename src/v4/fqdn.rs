@@ -0,0 +1,184 @@
+//! Client FQDN option (81) - <https://datatracker.ietf.org/doc/html/rfc4702>
+use std::fmt;
+
+use crate::Domain;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Client FQDN flags
+///
+/// ```text
+///  0 1 2 3 4 5 6 7
+/// +-+-+-+-+-+-+-+-+
+/// |  MBZ  |N|E|O|S|
+/// +-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Default, Clone, PartialEq, Eq, Hash)]
+pub struct FqdnFlags(u8);
+
+impl fmt::Debug for FqdnFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FqdnFlags")
+            .field("N", &self.n())
+            .field("E", &self.e())
+            .field("O", &self.o())
+            .field("S", &self.s())
+            .finish()
+    }
+}
+
+impl fmt::Display for FqdnFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FqdnFlags {
+    /// Create new FqdnFlags from u8
+    pub fn new(n: u8) -> Self {
+        Self(n)
+    }
+    /// get the status of the S flag, whether the server should perform the
+    /// A RR (FQDN-to-address) DNS update
+    pub fn s(&self) -> bool {
+        (self.0 & 0x01) != 0
+    }
+    /// set the S bit, returns a new FqdnFlags
+    pub fn set_s(mut self, bit: bool) -> Self {
+        self.set_bit(0x01, bit);
+        self
+    }
+    /// get the status of the O flag, set by the server if it has overridden
+    /// the client's preference for the S bit
+    pub fn o(&self) -> bool {
+        (self.0 & 0x02) != 0
+    }
+    /// set the O bit, returns a new FqdnFlags
+    pub fn set_o(mut self, bit: bool) -> Self {
+        self.set_bit(0x02, bit);
+        self
+    }
+    /// get the status of the E flag, whether the domain name is in canonical
+    /// wire format rather than the deprecated ASCII encoding
+    pub fn e(&self) -> bool {
+        (self.0 & 0x04) != 0
+    }
+    /// set the E bit, returns a new FqdnFlags
+    pub fn set_e(mut self, bit: bool) -> Self {
+        self.set_bit(0x04, bit);
+        self
+    }
+    /// get the status of the N flag, whether the server should not perform
+    /// any DNS updates
+    pub fn n(&self) -> bool {
+        (self.0 & 0x08) != 0
+    }
+    /// set the N bit, returns a new FqdnFlags
+    pub fn set_n(mut self, bit: bool) -> Self {
+        self.set_bit(0x08, bit);
+        self
+    }
+    fn set_bit(&mut self, mask: u8, bit: bool) {
+        if bit {
+            self.0 |= mask;
+        } else {
+            self.0 &= !mask;
+        }
+    }
+}
+
+impl From<u8> for FqdnFlags {
+    fn from(n: u8) -> Self {
+        Self(n)
+    }
+}
+impl From<FqdnFlags> for u8 {
+    fn from(f: FqdnFlags) -> Self {
+        f.0
+    }
+}
+
+/// Client FQDN option data
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientFQDN {
+    flags: FqdnFlags,
+    r1: u8,
+    r2: u8,
+    domain: Domain,
+}
+
+impl ClientFQDN {
+    /// Create a new ClientFQDN. The RCODE fields are deprecated, clients set
+    /// them to 0 & servers to 255
+    pub fn new(flags: FqdnFlags, domain: Domain) -> Self {
+        Self {
+            flags,
+            r1: 0,
+            r2: 0,
+            domain,
+        }
+    }
+    /// get the flags
+    pub fn flags(&self) -> FqdnFlags {
+        self.flags
+    }
+    /// set the flags
+    pub fn set_flags(&mut self, flags: FqdnFlags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+    /// get the deprecated RCODE1 field
+    pub fn r1(&self) -> u8 {
+        self.r1
+    }
+    /// set the deprecated RCODE1 field
+    pub fn set_r1(&mut self, r1: u8) -> &mut Self {
+        self.r1 = r1;
+        self
+    }
+    /// get the deprecated RCODE2 field
+    pub fn r2(&self) -> u8 {
+        self.r2
+    }
+    /// set the deprecated RCODE2 field
+    pub fn set_r2(&mut self, r2: u8) -> &mut Self {
+        self.r2 = r2;
+        self
+    }
+    /// get the domain name
+    pub fn domain(&self) -> &Domain {
+        &self.domain
+    }
+    /// set the domain name
+    pub fn set_domain(&mut self, domain: Domain) -> &mut Self {
+        self.domain = domain;
+        self
+    }
+}
+
+/// The name a client wants to be known by, see
+/// [`Message::preferred_name`](crate::v4::Message::preferred_name)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientName<'a> {
+    /// name from the Client FQDN option (81)
+    Fqdn(&'a Domain),
+    /// name from the Host Name option (12)
+    Hostname(&'a str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        let flags = FqdnFlags::default().set_s(true).set_e(true);
+        assert_eq!(u8::from(flags), 0x05);
+        assert!(flags.s() && flags.e() && !flags.o() && !flags.n());
+        let flags = flags.set_s(false).set_n(true).set_o(true);
+        assert_eq!(u8::from(flags), 0x0E);
+    }
+}
@@ -0,0 +1,114 @@
+//! Dispatching messages to server handlers by message type
+use crate::v4::{Message, MessageType};
+
+/// Handles messages received by a DHCP server, see [`dispatch`]. Each method
+/// receives the client's message & returns an optional reply.
+///
+/// All methods default to calling [`on_other`](DhcpHandler::on_other), so only
+/// the message types a server cares about need to be implemented
+pub trait DhcpHandler {
+    /// handle a DHCPDISCOVER
+    fn on_discover(&mut self, msg: &Message) -> Option<Message> {
+        self.on_other(msg)
+    }
+    /// handle a DHCPREQUEST
+    fn on_request(&mut self, msg: &Message) -> Option<Message> {
+        self.on_other(msg)
+    }
+    /// handle a DHCPDECLINE
+    fn on_decline(&mut self, msg: &Message) -> Option<Message> {
+        self.on_other(msg)
+    }
+    /// handle a DHCPRELEASE
+    fn on_release(&mut self, msg: &Message) -> Option<Message> {
+        self.on_other(msg)
+    }
+    /// handle a DHCPINFORM
+    fn on_inform(&mut self, msg: &Message) -> Option<Message> {
+        self.on_other(msg)
+    }
+    /// handle any other message, including those with an unknown or missing
+    /// message type (option 53). Returns `None` by default
+    fn on_other(&mut self, _msg: &Message) -> Option<Message> {
+        None
+    }
+}
+
+/// Call the [`DhcpHandler`] method matching the message type (option 53) of
+/// `msg`, returning the handler's reply
+/// ```
+/// # use dhcproto::v4::{handler::{dispatch, DhcpHandler}, DhcpOption, Message, MessageType};
+/// struct Server;
+///
+/// impl DhcpHandler for Server {
+///     fn on_discover(&mut self, msg: &Message) -> Option<Message> {
+///         let mut offer = msg.clone();
+///         offer.opts_mut().insert(DhcpOption::MessageType(MessageType::Offer));
+///         Some(offer)
+///     }
+/// }
+///
+/// let mut discover = Message::default();
+/// discover.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+/// let reply = dispatch(&mut Server, &discover).unwrap();
+/// assert_eq!(reply.opts().msg_type(), Some(MessageType::Offer));
+/// // unhandled message types go to `on_other`
+/// assert!(dispatch(&mut Server, &Message::default()).is_none());
+/// ```
+pub fn dispatch<H: DhcpHandler + ?Sized>(handler: &mut H, msg: &Message) -> Option<Message> {
+    match msg.opts().msg_type() {
+        Some(MessageType::Discover) => handler.on_discover(msg),
+        Some(MessageType::Request) => handler.on_request(msg),
+        Some(MessageType::Decline) => handler.on_decline(msg),
+        Some(MessageType::Release) => handler.on_release(msg),
+        Some(MessageType::Inform) => handler.on_inform(msg),
+        _ => handler.on_other(msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v4::DhcpOption;
+
+    #[derive(Default)]
+    struct Counter {
+        calls: Vec<&'static str>,
+    }
+
+    impl DhcpHandler for Counter {
+        fn on_discover(&mut self, _msg: &Message) -> Option<Message> {
+            self.calls.push("discover");
+            None
+        }
+        fn on_release(&mut self, _msg: &Message) -> Option<Message> {
+            self.calls.push("release");
+            None
+        }
+        fn on_other(&mut self, _msg: &Message) -> Option<Message> {
+            self.calls.push("other");
+            None
+        }
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut handler = Counter::default();
+        for ty in [
+            MessageType::Discover,
+            MessageType::Release,
+            MessageType::Request,
+            MessageType::Unknown(200),
+        ] {
+            let mut msg = Message::default();
+            msg.opts_mut().insert(DhcpOption::MessageType(ty));
+            dispatch(&mut handler, &msg);
+        }
+        // no message type
+        dispatch(&mut handler, &Message::default());
+        assert_eq!(
+            handler.calls,
+            ["discover", "release", "other", "other", "other"]
+        );
+    }
+}
@@ -1,7 +1,9 @@
+use std::{fmt, str::FromStr};
+
 use crate::{
     decoder::{Decodable, Decoder},
     encoder::{Encodable, Encoder},
-    error::{DecodeResult, EncodeResult},
+    error::{DecodeResult, EncodeResult, ParseNameError},
 };
 
 #[cfg(feature = "serde")]
@@ -141,6 +143,89 @@ impl From<HType> for u8 {
     }
 }
 
+impl HType {
+    /// canonical name of the hardware type
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        use HType::*;
+        Some(match self {
+            Eth => "ETHERNET",
+            ExperimentalEth => "EXPERIMENTAL-ETHERNET",
+            AmRadioAX25 => "AX.25",
+            ProteonTokenRing => "PROTEON-TOKEN-RING",
+            Chaos => "CHAOS",
+            IEEE802 => "IEEE802",
+            ARCNET => "ARCNET",
+            Hyperchannel => "HYPERCHANNEL",
+            Lanstar => "LANSTAR",
+            AutonetShortAddr => "AUTONET",
+            LocalTalk => "LOCALTALK",
+            LocalNet => "LOCALNET",
+            Ultralink => "ULTRALINK",
+            SMDS => "SMDS",
+            FrameRelay => "FRAME-RELAY",
+            HDLC => "HDLC",
+            FibreChannel => "FIBRE-CHANNEL",
+            SerialLine => "SERIAL-LINE",
+            MilStd188220 => "MIL-STD-188-220",
+            Metricom => "METRICOM",
+            MAPOS => "MAPOS",
+            Twinaxial => "TWINAXIAL",
+            ARPSec => "ARPSEC",
+            IPsecTunnel => "IPSEC-TUNNEL",
+            Infiniband => "INFINIBAND",
+            WiegandInt => "WIEGAND",
+            PureIP => "PURE-IP",
+            Unknown(_) => return None,
+        })
+    }
+}
+
+impl HType {
+    /// The `hlen` a message of this hardware type is expected to have, or
+    /// `None` if the address length isn't fixed or isn't known. InfiniBand
+    /// addresses don't fit in `chaddr`, so RFC 4390 requires an `hlen` of 0
+    /// ```
+    /// # use dhcproto::v4::HType;
+    /// assert_eq!(HType::Eth.expected_hlen(), Some(6));
+    /// assert_eq!(HType::Infiniband.expected_hlen(), Some(0));
+    /// assert_eq!(HType::Unknown(200).expected_hlen(), None);
+    /// ```
+    pub fn expected_hlen(&self) -> Option<u8> {
+        use HType::*;
+        Some(match self {
+            Eth | IEEE802 => 6,
+            ExperimentalEth | ARCNET | LocalTalk => 1,
+            AmRadioAX25 => 7,
+            Infiniband => 0,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for HType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "UNKNOWN({})", u8::from(*self)),
+        }
+    }
+}
+
+/// Parses the canonical name, ignoring case, `UNKNOWN(n)` or a number
+/// ```
+/// # use dhcproto::v4::HType;
+/// assert_eq!("ethernet".parse(), Ok(HType::Eth));
+/// assert_eq!("1".parse(), Ok(HType::Eth));
+/// assert_eq!(HType::Infiniband.to_string(), "INFINIBAND");
+/// ```
+impl FromStr for HType {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        super::parse_name("hardware type", s, Self::name)
+    }
+}
+
 impl Decodable for HType {
     fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
         Ok(decoder.read_u8()?.into())
@@ -0,0 +1,337 @@
+//! Lease parameters assembled from a DHCPACK or a leasequery reply
+use std::{net::Ipv4Addr, time::Duration};
+
+use crate::v4::{DhcpOption, HType, Message, MessageType, OptionCode};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// lease time of `0xffffffff` means the lease is infinite, RFC 2131 section 3.3
+const INFINITE: u32 = u32::MAX;
+
+/// The common parameters of a lease, gathered from a DHCPACK. See
+/// [`Lease::from_ack`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lease {
+    ip: Ipv4Addr,
+    server_id: Ipv4Addr,
+    lease_time: Duration,
+    renewal: Duration,
+    rebinding: Duration,
+    subnet_mask: Option<Ipv4Addr>,
+    routers: Vec<Ipv4Addr>,
+    dns_servers: Vec<Ipv4Addr>,
+    domain_name: Option<String>,
+}
+
+impl Lease {
+    /// Gather the lease from a DHCPACK. Returns `None` if `msg` isn't an ACK,
+    /// or is missing the assigned address (`yiaddr`), the Address Lease Time
+    /// (51) or Server Identifier (54) options, e.g. an ACK to a DHCPINFORM.
+    ///
+    /// If T1 (58) or T2 (59) are absent they default to 0.5 & 0.875 of the
+    /// lease time, as per RFC 2131 section 4.4.5
+    /// ```
+    /// # use std::{net::Ipv4Addr, time::Duration};
+    /// # use dhcproto::v4::{Message, DhcpOption, MessageType, Opcode, lease::Lease};
+    /// let mut msg = Message::default();
+    /// msg.set_opcode(Opcode::BootReply).set_yiaddr([192, 168, 0, 10]);
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Ack));
+    /// msg.opts_mut().insert(DhcpOption::ServerIdentifier([192, 168, 0, 1].into()));
+    /// msg.opts_mut().insert(DhcpOption::AddressLeaseTime(3600));
+    /// msg.opts_mut().insert(DhcpOption::Router(vec![[192, 168, 0, 1].into()]));
+    ///
+    /// let lease = Lease::from_ack(&msg).unwrap();
+    /// assert_eq!(lease.ip(), Ipv4Addr::new(192, 168, 0, 10));
+    /// assert_eq!(lease.lease_time(), Duration::from_secs(3600));
+    /// assert_eq!(lease.renewal(), Duration::from_secs(1800));
+    /// assert_eq!(lease.rebinding(), Duration::from_secs(3150));
+    /// assert_eq!(lease.routers(), &[Ipv4Addr::new(192, 168, 0, 1)]);
+    /// ```
+    pub fn from_ack(msg: &Message) -> Option<Self> {
+        let opts = msg.opts();
+        if opts.msg_type()? != MessageType::Ack || msg.yiaddr().is_unspecified() {
+            return None;
+        }
+        let lease_time = match opts.get(OptionCode::AddressLeaseTime)? {
+            DhcpOption::AddressLeaseTime(secs) => *secs,
+            _ => return None,
+        };
+        let server_id = match opts.get(OptionCode::ServerIdentifier)? {
+            DhcpOption::ServerIdentifier(ip) => *ip,
+            _ => return None,
+        };
+        let timer = |code, default: u64| match opts.get(code) {
+            Some(DhcpOption::Renewal(secs)) | Some(DhcpOption::Rebinding(secs)) => {
+                Duration::from_secs(*secs as u64)
+            }
+            _ => Duration::from_secs(default),
+        };
+        let renewal = timer(OptionCode::Renewal, lease_time as u64 / 2);
+        let rebinding = timer(OptionCode::Rebinding, lease_time as u64 * 7 / 8);
+        Some(Self {
+            ip: msg.yiaddr(),
+            server_id,
+            lease_time: Duration::from_secs(lease_time as u64),
+            renewal,
+            rebinding,
+            subnet_mask: match opts.get(OptionCode::SubnetMask) {
+                Some(DhcpOption::SubnetMask(mask)) => Some(*mask),
+                _ => None,
+            },
+            routers: opts.routers().unwrap_or_default().to_vec(),
+            dns_servers: opts.domain_name_servers().unwrap_or_default().to_vec(),
+            domain_name: match opts.get(OptionCode::DomainName) {
+                Some(DhcpOption::DomainName(name)) => Some(name.clone()),
+                _ => None,
+            },
+        })
+    }
+    /// get the assigned address (`yiaddr`)
+    pub fn ip(&self) -> Ipv4Addr {
+        self.ip
+    }
+    /// get the server identifier (option 54), the address of the server to
+    /// renew with
+    pub fn server_id(&self) -> Ipv4Addr {
+        self.server_id
+    }
+    /// get the lease time (option 51)
+    pub fn lease_time(&self) -> Duration {
+        self.lease_time
+    }
+    /// whether the lease never expires, a lease time of `0xffffffff`
+    pub fn is_infinite(&self) -> bool {
+        self.lease_time == Duration::from_secs(INFINITE as u64)
+    }
+    /// get the renewal time, T1 (option 58)
+    pub fn renewal(&self) -> Duration {
+        self.renewal
+    }
+    /// get the rebinding time, T2 (option 59)
+    pub fn rebinding(&self) -> Duration {
+        self.rebinding
+    }
+    /// get the subnet mask (option 1)
+    pub fn subnet_mask(&self) -> Option<Ipv4Addr> {
+        self.subnet_mask
+    }
+    /// get the routers (option 3), in order of preference
+    pub fn routers(&self) -> &[Ipv4Addr] {
+        &self.routers
+    }
+    /// get the domain name servers (option 6), in order of preference
+    pub fn dns_servers(&self) -> &[Ipv4Addr] {
+        &self.dns_servers
+    }
+    /// get the domain name (option 15)
+    pub fn domain_name(&self) -> Option<&str> {
+        self.domain_name.as_deref()
+    }
+}
+
+/// State of the queried lease, given by the type of a leasequery reply
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LeaseStatus {
+    /// DHCPLEASEACTIVE, the address is leased to a client
+    Active,
+    /// DHCPLEASEUNASSIGNED, the server owns the address but hasn't leased it
+    Unassigned,
+    /// DHCPLEASEUNKNOWN, the server has no information about the query
+    Unknown,
+}
+
+/// The lease data returned in reply to a DHCPLEASEQUERY (RFC 4388), see
+/// [`LeaseQueryReply::from_reply`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LeaseQueryReply {
+    status: LeaseStatus,
+    ip: Option<Ipv4Addr>,
+    htype: HType,
+    chaddr: Vec<u8>,
+    client_id: Option<Vec<u8>>,
+    server_id: Option<Ipv4Addr>,
+    lease_time: Option<Duration>,
+    last_transaction: Option<Duration>,
+    associated_ips: Vec<Ipv4Addr>,
+}
+
+impl LeaseQueryReply {
+    /// Gather the lease data from a DHCPLEASEACTIVE, DHCPLEASEUNASSIGNED or
+    /// DHCPLEASEUNKNOWN. Returns `None` for any other message type
+    /// ```
+    /// # use std::{net::Ipv4Addr, time::Duration};
+    /// # use dhcproto::v4::{HType, Message, lease::{LeaseQueryReply, LeaseStatus}};
+    /// let query = Message::leasequery_by_client_id([10, 0, 0, 2].into(), vec![1, 2, 3]);
+    /// let mut reply = Message::lease_active(
+    ///     &query,
+    ///     [10, 0, 0, 1].into(),
+    ///     [10, 0, 0, 50].into(),
+    ///     HType::Eth,
+    ///     &[1, 2, 3, 4, 5, 6],
+    ///     30,
+    /// );
+    /// reply.opts_mut().set_associated_ips(vec![[10, 0, 0, 51].into()]);
+    ///
+    /// let lease = LeaseQueryReply::from_reply(&reply).unwrap();
+    /// assert_eq!(lease.status(), LeaseStatus::Active);
+    /// assert_eq!(lease.ip(), Some(Ipv4Addr::new(10, 0, 0, 50)));
+    /// assert_eq!(lease.chaddr(), &[1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(lease.last_transaction(), Some(Duration::from_secs(30)));
+    /// assert_eq!(lease.associated_ips(), &[Ipv4Addr::new(10, 0, 0, 51)]);
+    /// assert!(LeaseQueryReply::from_reply(&query).is_none());
+    /// ```
+    pub fn from_reply(msg: &Message) -> Option<Self> {
+        let opts = msg.opts();
+        let status = match opts.msg_type()? {
+            MessageType::LeaseActive => LeaseStatus::Active,
+            MessageType::LeaseUnassigned => LeaseStatus::Unassigned,
+            MessageType::LeaseUnknown => LeaseStatus::Unknown,
+            _ => return None,
+        };
+        let secs = |secs: u32| Duration::from_secs(secs as u64);
+        Some(Self {
+            status,
+            ip: Some(msg.ciaddr()).filter(|ip| !ip.is_unspecified()),
+            htype: msg.htype(),
+            chaddr: msg.chaddr().to_vec(),
+            client_id: match opts.get(OptionCode::ClientIdentifier) {
+                Some(DhcpOption::ClientIdentifier(id)) => Some(id.clone()),
+                _ => None,
+            },
+            server_id: match opts.get(OptionCode::ServerIdentifier) {
+                Some(DhcpOption::ServerIdentifier(ip)) => Some(*ip),
+                _ => None,
+            },
+            lease_time: match opts.get(OptionCode::AddressLeaseTime) {
+                Some(DhcpOption::AddressLeaseTime(t)) => Some(secs(*t)),
+                _ => None,
+            },
+            last_transaction: opts.client_last_transaction_time().map(secs),
+            associated_ips: opts.associated_ips().unwrap_or_default().to_vec(),
+        })
+    }
+    /// get the state of the queried lease
+    pub fn status(&self) -> LeaseStatus {
+        self.status
+    }
+    /// get the leased (or, if unassigned, queried) address, from `ciaddr`
+    pub fn ip(&self) -> Option<Ipv4Addr> {
+        self.ip
+    }
+    /// get the client's hardware type
+    pub fn htype(&self) -> HType {
+        self.htype
+    }
+    /// get the client's hardware address, empty if it wasn't returned
+    pub fn chaddr(&self) -> &[u8] {
+        &self.chaddr
+    }
+    /// get the client identifier (option 61)
+    pub fn client_id(&self) -> Option<&[u8]> {
+        self.client_id.as_deref()
+    }
+    /// get the server identifier (option 54)
+    pub fn server_id(&self) -> Option<Ipv4Addr> {
+        self.server_id
+    }
+    /// get the remaining lease time (option 51)
+    pub fn lease_time(&self) -> Option<Duration> {
+        self.lease_time
+    }
+    /// get the time since the server last heard from the client (option 91)
+    pub fn last_transaction(&self) -> Option<Duration> {
+        self.last_transaction
+    }
+    /// get the other addresses leased to the client (option 92)
+    pub fn associated_ips(&self) -> &[Ipv4Addr] {
+        &self.associated_ips
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{v4::Opcode, Decodable, Decoder, Encodable};
+
+    fn ack() -> Message {
+        let mut msg = Message::default();
+        msg.set_opcode(Opcode::BootReply).set_yiaddr([10, 0, 0, 20]);
+        let opts = msg.opts_mut();
+        opts.insert(DhcpOption::MessageType(MessageType::Ack));
+        opts.insert(DhcpOption::ServerIdentifier([10, 0, 0, 1].into()));
+        opts.insert(DhcpOption::AddressLeaseTime(INFINITE));
+        opts.insert(DhcpOption::Renewal(100));
+        opts.insert(DhcpOption::Rebinding(200));
+        opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+        opts.insert(DhcpOption::DomainNameServer(vec![
+            [10, 0, 0, 2].into(),
+            [10, 0, 0, 3].into(),
+        ]));
+        opts.insert(DhcpOption::DomainName("example.com".to_owned()));
+        msg
+    }
+
+    #[test]
+    fn test_from_ack() -> Result<(), Box<dyn std::error::Error>> {
+        let msg = Message::decode(&mut Decoder::new(&ack().to_vec()?))?;
+        let lease = Lease::from_ack(&msg).unwrap();
+        assert_eq!(lease.ip(), Ipv4Addr::new(10, 0, 0, 20));
+        assert_eq!(lease.server_id(), Ipv4Addr::new(10, 0, 0, 1));
+        assert!(lease.is_infinite());
+        assert_eq!(lease.renewal(), Duration::from_secs(100));
+        assert_eq!(lease.rebinding(), Duration::from_secs(200));
+        assert_eq!(lease.subnet_mask(), Some([255, 255, 255, 0].into()));
+        assert!(lease.routers().is_empty());
+        assert_eq!(lease.dns_servers().len(), 2);
+        assert_eq!(lease.domain_name(), Some("example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_a_lease() {
+        let mut msg = ack();
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Offer));
+        assert_eq!(Lease::from_ack(&msg), None);
+
+        let mut msg = ack();
+        msg.set_yiaddr(Ipv4Addr::UNSPECIFIED);
+        assert_eq!(Lease::from_ack(&msg), None);
+
+        for code in [OptionCode::AddressLeaseTime, OptionCode::ServerIdentifier] {
+            let mut msg = ack();
+            msg.opts_mut().remove(code);
+            assert_eq!(Lease::from_ack(&msg), None);
+        }
+    }
+
+    #[test]
+    fn test_leasequery_by_remote_id() -> Result<(), Box<dyn std::error::Error>> {
+        let query = Message::leasequery_by_remote_id([10, 0, 0, 2].into(), b"port-7".to_vec());
+        let query = Message::decode(&mut Decoder::new(&query.to_vec()?))?;
+        let info = query.opts().relay_agent_information().unwrap();
+        assert_eq!(info.remote_id(), Some(&b"port-7"[..]));
+        assert_eq!((query.hlen(), query.ciaddr()), (0, Ipv4Addr::UNSPECIFIED));
+
+        let mut reply = Message::lease_unknown(&query, [10, 0, 0, 1].into());
+        reply.opts_mut().insert(DhcpOption::AddressLeaseTime(60));
+        let reply = Message::decode(&mut Decoder::new(&reply.to_vec()?))?;
+        let lease = LeaseQueryReply::from_reply(&reply).unwrap();
+        assert_eq!(lease.status(), LeaseStatus::Unknown);
+        assert_eq!(lease.ip(), None);
+        assert!(lease.chaddr().is_empty());
+        assert_eq!(lease.server_id(), Some([10, 0, 0, 1].into()));
+        assert_eq!(lease.lease_time(), Some(Duration::from_secs(60)));
+        assert_eq!((lease.client_id(), lease.last_transaction()), (None, None));
+
+        let query = Message::leasequery_by_remote_id_with_id(42, [10, 0, 0, 2].into(), vec![1]);
+        assert_eq!(query.xid(), 42);
+        let query = Message::leasequery_by_client_id_with_id(43, [10, 0, 0, 2].into(), vec![1]);
+        assert_eq!(query.xid(), 43);
+        Ok(())
+    }
+}
@@ -78,17 +78,29 @@
 //! # Ok(()) }
 //! ```
 //!
-use std::{fmt, net::Ipv4Addr, str::Utf8Error};
+use std::{borrow::Cow, collections::BTreeMap, fmt, net::Ipv4Addr, str::Utf8Error, time::Duration};
+
+use rand::RngCore;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 pub mod bulk_query;
+pub mod classless;
+pub mod fingerprint;
 mod flags;
+pub mod fqdn;
+pub mod handler;
 mod htype;
+pub mod lease;
 mod opcode;
 mod options;
+pub mod pxe;
 pub mod relay;
+pub mod shape;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_fixtures;
+pub mod user_class;
 
 // re-export submodules from proto::msg
 pub use self::{flags::*, htype::*, opcode::*, options::*};
@@ -99,11 +111,20 @@ pub use crate::{
 };
 
 pub const MAGIC: [u8; 4] = [99, 130, 83, 99];
+/// length of the BOOTP vendor area, used in place of the cookie & options by
+/// messages without the magic cookie
+pub const BOOTP_VEND_LEN: usize = 64;
 
 /// default dhcpv4 server port
 pub const SERVER_PORT: u16 = 67;
 /// default dhcpv4 client port
 pub const CLIENT_PORT: u16 = 68;
+/// commonly used limit on relay hops, see [`Message::hops_exceeded`]
+pub const MAX_HOPS: u8 = 16;
+/// size of the sname field, including the NUL terminator
+const SNAME_LEN: usize = 64;
+/// size of the file field, including the NUL terminator
+const FNAME_LEN: usize = 128;
 
 /// [Dynamic Host Configuration Protocol](https://tools.ietf.org/html/rfc2131#section-2)
 ///
@@ -134,8 +155,17 @@ pub const CLIENT_PORT: u16 = 68;
 /// |                          options (variable)                   |
 /// +---------------------------------------------------------------+
 /// ```
+///
+/// Messages with options encode to a minimal canonical form: the cookie, the
+/// options in ascending order of code (Relay Agent Information last) & a
+/// single `End`, with no `Pad` options or bytes after `End`. Encoding a
+/// message again after decoding it gives the same bytes, but inputs
+/// containing `Pad`, e.g. padded to the BOOTP minimum of 300 bytes, won't
+/// byte-match their re-encoding. BOOTP messages without the cookie have no
+/// options or `End`, their vendor area is written back as it was decoded, see
+/// [`Message::vendor_area`].
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct Message {
     /// op code / message type
     opcode: Opcode,
@@ -165,18 +195,123 @@ pub struct Message {
     sname: Option<Vec<u8>>,
     // File name
     fname: Option<Vec<u8>>,
+    /// original 64 byte sname region, see [`Message::decode_lossless`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw_sname: Option<Vec<u8>>,
+    /// original 128 byte file region, see [`Message::decode_lossless`]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw_fname: Option<Vec<u8>>,
     magic: [u8; 4],
+    /// BOOTP vendor area, written in place of the cookie & options when set
+    #[cfg_attr(feature = "serde", serde(default))]
+    vend: Option<Vec<u8>>,
     opts: DhcpOptions,
 }
 
 impl Default for Message {
     fn default() -> Self {
+        Self::empty(rand::random())
+    }
+}
+
+// the verbatim sname & file regions kept by `decode_lossless` only affect
+// encoding, so they're left out of comparisons & hashing. A message decodes
+// equal with or without them
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        let Message {
+            opcode,
+            htype,
+            hlen,
+            hops,
+            xid,
+            secs,
+            flags,
+            ciaddr,
+            yiaddr,
+            siaddr,
+            giaddr,
+            chaddr,
+            sname,
+            fname,
+            raw_sname: _,
+            raw_fname: _,
+            magic,
+            vend,
+            opts,
+        } = self;
+        *opcode == other.opcode
+            && *htype == other.htype
+            && *hlen == other.hlen
+            && *hops == other.hops
+            && *xid == other.xid
+            && *secs == other.secs
+            && *flags == other.flags
+            && *ciaddr == other.ciaddr
+            && *yiaddr == other.yiaddr
+            && *siaddr == other.siaddr
+            && *giaddr == other.giaddr
+            && *chaddr == other.chaddr
+            && *sname == other.sname
+            && *fname == other.fname
+            && *magic == other.magic
+            && *vend == other.vend
+            && *opts == other.opts
+    }
+}
+
+impl std::hash::Hash for Message {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let Message {
+            opcode,
+            htype,
+            hlen,
+            hops,
+            xid,
+            secs,
+            flags,
+            ciaddr,
+            yiaddr,
+            siaddr,
+            giaddr,
+            chaddr,
+            sname,
+            fname,
+            raw_sname: _,
+            raw_fname: _,
+            magic,
+            vend,
+            opts,
+        } = self;
+        opcode.hash(state);
+        htype.hash(state);
+        hlen.hash(state);
+        hops.hash(state);
+        xid.hash(state);
+        secs.hash(state);
+        flags.hash(state);
+        ciaddr.hash(state);
+        yiaddr.hash(state);
+        siaddr.hash(state);
+        giaddr.hash(state);
+        chaddr.hash(state);
+        sname.hash(state);
+        fname.hash(state);
+        magic.hash(state);
+        vend.hash(state);
+        opts.hash(state);
+    }
+}
+
+impl Message {
+    /// the default message with `xid`, doesn't touch the global rng
+    fn empty(xid: u32) -> Self {
         Self {
             opcode: Opcode::BootRequest,
             htype: HType::Eth,
             hlen: 0,
             hops: 0,
-            xid: rand::random(),
+            xid,
             secs: 0,
             flags: Flags::default(),
             ciaddr: Ipv4Addr::UNSPECIFIED,
@@ -186,13 +321,14 @@ impl Default for Message {
             chaddr: [0; 16],
             sname: None,
             fname: None,
+            raw_sname: None,
+            raw_fname: None,
             magic: MAGIC,
+            vend: None,
             opts: DhcpOptions::default(),
         }
     }
-}
 
-impl Message {
     /// returns a new Message with OpCode set to BootRequest and a new random id
     /// # Panic
     ///   panics if chaddr is greater len than 16
@@ -206,6 +342,33 @@ impl Message {
         Self::new_with_id(rand::random(), ciaddr, yiaddr, siaddr, giaddr, chaddr)
     }
 
+    /// returns a new Message with OpCode set to BootRequest and an id drawn
+    /// from `rng`, so a seeded rng gives reproducible messages
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::Message;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// let new = |rng: &mut StdRng| {
+    ///     let ip = Ipv4Addr::UNSPECIFIED;
+    ///     Message::new_with_rng(rng, ip, ip, ip, ip, &[1, 2, 3, 4, 5, 6])
+    /// };
+    /// let a = new(&mut StdRng::seed_from_u64(1));
+    /// let b = new(&mut StdRng::seed_from_u64(1));
+    /// assert_eq!(a.xid(), b.xid());
+    /// ```
+    /// # Panic
+    ///   panics if chaddr is greater len than 16
+    pub fn new_with_rng(
+        rng: &mut impl RngCore,
+        ciaddr: Ipv4Addr,
+        yiaddr: Ipv4Addr,
+        siaddr: Ipv4Addr,
+        giaddr: Ipv4Addr,
+        chaddr: &[u8],
+    ) -> Self {
+        Self::new_with_id(rng.next_u32(), ciaddr, yiaddr, siaddr, giaddr, chaddr)
+    }
+
     /// returns a new Message with OpCode set to BootRequest
     /// # Panic
     ///   panics if chaddr is greater len than 16
@@ -233,10 +396,198 @@ impl Message {
             siaddr,
             giaddr,
             chaddr: new_chaddr,
-            ..Self::default()
+            ..Self::empty(xid)
         }
     }
 
+    /// returns a new DHCPFORCERENEW (RFC 3203) to be unicast from the server
+    /// `server_id` to the client at `ciaddr`, with the Message Type &
+    /// Server Identifier options set
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::{Message, MessageType, Opcode};
+    /// let msg = Message::force_renew(
+    ///     [192, 168, 0, 1].into(),
+    ///     [192, 168, 0, 10].into(),
+    ///     &[0x00, 0x1b, 0x21, 0xaa, 0xbb, 0xcc],
+    /// );
+    /// assert_eq!(msg.opcode(), Opcode::BootReply);
+    /// assert_eq!(msg.opts().msg_type(), Some(MessageType::ForceRenew));
+    /// assert_eq!(msg.ciaddr(), Ipv4Addr::new(192, 168, 0, 10));
+    /// ```
+    /// # Panic
+    ///   panics if chaddr is greater len than 16
+    pub fn force_renew(server_id: Ipv4Addr, ciaddr: Ipv4Addr, chaddr: &[u8]) -> Self {
+        Self::force_renew_with_id(rand::random(), server_id, ciaddr, chaddr)
+    }
+
+    /// returns a new DHCPFORCERENEW with the given xid, see
+    /// [`Message::force_renew`]
+    /// # Panic
+    ///   panics if chaddr is greater len than 16
+    pub fn force_renew_with_id(
+        xid: u32,
+        server_id: Ipv4Addr,
+        ciaddr: Ipv4Addr,
+        chaddr: &[u8],
+    ) -> Self {
+        let mut msg = Self::new_with_id(
+            xid,
+            ciaddr,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            chaddr,
+        );
+        msg.set_opcode(Opcode::BootReply);
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::ForceRenew));
+        msg.opts_mut()
+            .insert(DhcpOption::ServerIdentifier(server_id));
+        msg
+    }
+
+    /// returns a DHCPLEASEACTIVE (RFC 4388) reply to the leasequery `query`,
+    /// for the lease on `ip` held by the client with hardware address
+    /// `htype` & `chaddr`. It carries the `xid`, `flags` & `giaddr` of the
+    /// query, `ip` in `ciaddr` (`yiaddr` is unused), & the Message Type,
+    /// Server Identifier & client-last-transaction-time (91) options. The
+    /// remaining lease time (51), associated IPs (92) etc. can be added to its
+    /// options
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::{DhcpOption, HType, Message, MessageType, Opcode};
+    /// let mut query = Message::default();
+    /// query.set_ciaddr([192, 168, 0, 10]).set_giaddr([192, 168, 0, 2]);
+    ///
+    /// let mut reply = Message::lease_active(
+    ///     &query,
+    ///     [192, 168, 0, 1].into(),
+    ///     [192, 168, 0, 10].into(),
+    ///     HType::Eth,
+    ///     &[0x00, 0x1b, 0x21, 0xaa, 0xbb, 0xcc],
+    ///     30,
+    /// );
+    /// reply.opts_mut().insert(DhcpOption::AddressLeaseTime(3570));
+    /// assert_eq!(reply.opcode(), Opcode::BootReply);
+    /// assert_eq!(reply.opts().msg_type(), Some(MessageType::LeaseActive));
+    /// assert_eq!(reply.ciaddr(), Ipv4Addr::new(192, 168, 0, 10));
+    /// assert_eq!(reply.giaddr(), Ipv4Addr::new(192, 168, 0, 2));
+    /// assert_eq!(reply.opts().client_last_transaction_time(), Some(30));
+    /// ```
+    /// # Panic
+    ///   panics if chaddr is greater len than 16
+    pub fn lease_active(
+        query: &Message,
+        server_id: Ipv4Addr,
+        ip: Ipv4Addr,
+        htype: HType,
+        chaddr: &[u8],
+        last_transaction: u32,
+    ) -> Self {
+        let mut msg = Self::leasequery_reply(query, MessageType::LeaseActive, server_id);
+        msg.set_ciaddr(ip).set_htype(htype).set_chaddr(chaddr);
+        msg.opts_mut()
+            .set_client_last_transaction_time(last_transaction);
+        msg
+    }
+
+    /// returns a DHCPLEASEUNASSIGNED (RFC 4388) reply to the leasequery
+    /// `query`, for an address the server is authoritative for but hasn't
+    /// leased. The queried address in `ciaddr` & the query's `xid`, `flags`,
+    /// `giaddr` & hardware address are kept, & the Message Type & Server
+    /// Identifier options are set
+    pub fn lease_unassigned(query: &Message, server_id: Ipv4Addr) -> Self {
+        let mut msg = Self::leasequery_reply(query, MessageType::LeaseUnassigned, server_id);
+        msg.set_ciaddr(query.ciaddr);
+        msg
+    }
+
+    /// returns a DHCPLEASEUNKNOWN (RFC 4388) reply to the leasequery `query`,
+    /// when the server has no information about the queried address, MAC or
+    /// client id. The query's `xid`, `flags`, `giaddr` & hardware address are
+    /// kept, `ciaddr` is left unset, & the Message Type & Server Identifier
+    /// options are set
+    pub fn lease_unknown(query: &Message, server_id: Ipv4Addr) -> Self {
+        Self::leasequery_reply(query, MessageType::LeaseUnknown, server_id)
+    }
+
+    /// returns a DHCPLEASEQUERY (RFC 4388) from the relay at `giaddr` for
+    /// the lease of the client with `client_id` (option 61). `ciaddr` &
+    /// `chaddr` are left unset. Add a Parameter Request List (55) to ask for
+    /// e.g. the lease time (51) or associated IPs (92) in the reply, see
+    /// [`lease::LeaseQueryReply`] for parsing it
+    /// ```
+    /// # use dhcproto::v4::{Message, MessageType, Opcode, OptionCode, DhcpOption};
+    /// let mut query = Message::leasequery_by_client_id([10, 0, 0, 2].into(), vec![1, 2, 3]);
+    /// query.opts_mut().insert(DhcpOption::ParameterRequestList(vec![
+    ///     OptionCode::AddressLeaseTime,
+    ///     OptionCode::ClientLastTransactionTime,
+    /// ]));
+    /// assert_eq!(query.opcode(), Opcode::BootRequest);
+    /// assert_eq!(query.opts().msg_type(), Some(MessageType::LeaseQuery));
+    /// assert!(query.chaddr().is_empty());
+    /// ```
+    pub fn leasequery_by_client_id(giaddr: Ipv4Addr, client_id: Vec<u8>) -> Self {
+        Self::leasequery_by_client_id_with_id(rand::random(), giaddr, client_id)
+    }
+
+    /// returns a new DHCPLEASEQUERY by client id with the given xid, see
+    /// [`Message::leasequery_by_client_id`]
+    pub fn leasequery_by_client_id_with_id(xid: u32, giaddr: Ipv4Addr, client_id: Vec<u8>) -> Self {
+        let mut msg = Self::leasequery(xid, giaddr);
+        msg.opts_mut()
+            .insert(DhcpOption::ClientIdentifier(client_id));
+        msg
+    }
+
+    /// returns a DHCPLEASEQUERY from the relay at `giaddr` for the leases of
+    /// clients behind the relay agent remote id `remote_id` (RFC 6148),
+    /// carried as sub-option 2 of the Relay Agent Information option (82).
+    /// See [`Message::leasequery_by_client_id`]
+    pub fn leasequery_by_remote_id(giaddr: Ipv4Addr, remote_id: Vec<u8>) -> Self {
+        Self::leasequery_by_remote_id_with_id(rand::random(), giaddr, remote_id)
+    }
+
+    /// returns a new DHCPLEASEQUERY by remote id with the given xid, see
+    /// [`Message::leasequery_by_remote_id`]
+    pub fn leasequery_by_remote_id_with_id(xid: u32, giaddr: Ipv4Addr, remote_id: Vec<u8>) -> Self {
+        let mut msg = Self::leasequery(xid, giaddr);
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::AgentRemoteId(remote_id));
+        msg.opts_mut()
+            .insert(DhcpOption::RelayAgentInformation(info));
+        msg
+    }
+
+    fn leasequery(xid: u32, giaddr: Ipv4Addr) -> Self {
+        let mut msg = Self {
+            giaddr,
+            // a query by client or remote id has no hardware address
+            htype: HType::Unknown(0),
+            ..Self::empty(xid)
+        };
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::LeaseQuery));
+        msg
+    }
+
+    fn leasequery_reply(query: &Message, msg_type: MessageType, server_id: Ipv4Addr) -> Self {
+        let mut msg = Self {
+            opcode: Opcode::BootReply,
+            htype: query.htype,
+            hlen: query.hlen,
+            flags: query.flags,
+            giaddr: query.giaddr,
+            chaddr: query.chaddr,
+            ..Self::empty(query.xid)
+        };
+        msg.opts_mut().insert(DhcpOption::MessageType(msg_type));
+        msg.opts_mut()
+            .insert(DhcpOption::ServerIdentifier(server_id));
+        msg
+    }
+
     /// Get the message's opcode.
     /// op code / message type
     pub fn opcode(&self) -> Opcode {
@@ -279,9 +630,259 @@ impl Message {
         self
     }
 
-    /// Get the message's chaddr.
+    /// Returns true if the message's hops is greater than `limit`. Servers &
+    /// relays should drop these messages to avoid relay loops,
+    /// [`MAX_HOPS`] is a common limit.
+    /// ```
+    /// # use dhcproto::v4::{Message, MAX_HOPS};
+    /// let mut msg = Message::default();
+    /// msg.set_hops(MAX_HOPS);
+    /// assert!(!msg.hops_exceeded(MAX_HOPS));
+    /// msg.set_hops(MAX_HOPS + 1);
+    /// assert!(msg.hops_exceeded(MAX_HOPS));
+    /// ```
+    pub fn hops_exceeded(&self, limit: u8) -> bool {
+        self.hops > limit
+    }
+
+    /// Prepare a client message for forwarding by a relay agent at
+    /// `relay_ip`, as per RFC 2131 section 4.1 & RFC 1542 section 4.1.1.
+    /// `giaddr` is set to `relay_ip` only if it is zero, a message already
+    /// relayed keeps the `giaddr` of the first relay, & `hops` is incremented.
+    /// Returns an error, leaving the message unchanged, if `hops` would exceed
+    /// `max_hops`
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::{Message, RelayError, MAX_HOPS};
+    /// let mut msg = Message::default();
+    /// msg.relay_forward([10, 0, 0, 1].into(), MAX_HOPS)?;
+    /// msg.relay_forward([10, 1, 0, 1].into(), MAX_HOPS)?;
+    /// assert_eq!(msg.giaddr(), Ipv4Addr::new(10, 0, 0, 1));
+    /// assert_eq!(msg.hops(), 2);
+    ///
+    /// msg.set_hops(MAX_HOPS);
+    /// assert_eq!(
+    ///     msg.relay_forward([10, 1, 0, 1].into(), MAX_HOPS),
+    ///     Err(RelayError::HopsExceeded { hops: MAX_HOPS, max: MAX_HOPS })
+    /// );
+    /// # Ok::<(), RelayError>(())
+    /// ```
+    pub fn relay_forward(&mut self, relay_ip: Ipv4Addr, max_hops: u8) -> Result<(), RelayError> {
+        let err = RelayError::HopsExceeded {
+            hops: self.hops,
+            max: max_hops,
+        };
+        let hops = self.hops.checked_add(1).ok_or(err)?;
+        if hops > max_hops {
+            return Err(err);
+        }
+        if !self.has_giaddr() {
+            self.giaddr = relay_ip;
+        }
+        self.hops = hops;
+        Ok(())
+    }
+
+    /// Where a server should send this reply, as per RFC 2131 section 4.1.
+    /// Returns the destination IP & whether to use the broadcast MAC,
+    /// otherwise the reply is unicast to the client's `chaddr` (or, when
+    /// relayed, to the relay). The reply must carry the `giaddr`, `ciaddr` &
+    /// `flags` of the request.
+    ///
+    /// - relayed (`giaddr` set): the relay at `giaddr`, on the server port
+    /// - a Nak that wasn't relayed: broadcast
+    /// - `ciaddr` set: unicast to `ciaddr`
+    /// - broadcast flag set, or `yiaddr` unset: broadcast
+    /// - otherwise: unicast to `yiaddr`
+    ///
+    /// Replies not sent to a relay go to the client port.
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::{Flags, Message};
+    /// let mut reply = Message::default();
+    /// reply.set_yiaddr([192, 168, 0, 5]);
+    /// assert_eq!(reply.reply_destination(), (Ipv4Addr::new(192, 168, 0, 5), false));
+    ///
+    /// reply.set_flags(Flags::default().set_broadcast());
+    /// assert_eq!(reply.reply_destination(), (Ipv4Addr::BROADCAST, true));
+    ///
+    /// reply.set_giaddr([10, 0, 0, 1]);
+    /// assert_eq!(reply.reply_destination(), (Ipv4Addr::new(10, 0, 0, 1), false));
+    /// ```
+    pub fn reply_destination(&self) -> (Ipv4Addr, bool) {
+        if self.has_giaddr() {
+            (self.giaddr, false)
+        } else if self.opts.has_msg_type(MessageType::Nak) {
+            (Ipv4Addr::BROADCAST, true)
+        } else if self.has_ciaddr() {
+            (self.ciaddr, false)
+        } else if self.flags.broadcast() || !self.has_yiaddr() {
+            (Ipv4Addr::BROADCAST, true)
+        } else {
+            (self.yiaddr, false)
+        }
+    }
+
+    /// Whether this reply must be broadcast rather than unicast, i.e. it
+    /// isn't relayed & is a Nak, or the client has no usable address: it
+    /// set the broadcast flag or neither `ciaddr` nor `yiaddr` is set. See
+    /// [`Message::reply_destination`]
+    /// ```
+    /// # use dhcproto::v4::{Flags, Message};
+    /// let mut reply = Message::default();
+    /// // nowhere to unicast to
+    /// assert!(reply.requires_broadcast_reply());
+    /// reply.set_yiaddr([192, 168, 0, 5]);
+    /// assert!(!reply.requires_broadcast_reply());
+    /// reply.set_flags(Flags::default().set_broadcast());
+    /// assert!(reply.requires_broadcast_reply());
+    /// ```
+    pub fn requires_broadcast_reply(&self) -> bool {
+        self.reply_destination().1
+    }
+
+    /// The client's fingerprint: its Parameter Request List (55), in the order
+    /// sent, & its Vendor Class Identifier (60). See [`fingerprint`]
+    pub fn fingerprint(&self) -> fingerprint::Fingerprint {
+        fingerprint::Fingerprint::from(&self.opts)
+    }
+
+    /// The client state a DHCPREQUEST was sent from, as per RFC 2131 section
+    /// 4.3.2, derived from `ciaddr` & the presence of the Server Identifier
+    /// (54) & Requested IP Address (50) options. RENEWING & REBINDING
+    /// requests have the same contents, a RENEWING client unicasts to the
+    /// server whereas a REBINDING one broadcasts, so `broadcast` is whether
+    /// the request was received on a broadcast address. Relays don't forward
+    /// RENEWING requests, so a request with `giaddr` set is REBINDING even
+    /// though the relay unicasts it to the server. The exception is a relay
+    /// using the Server Identifier Override (RFC 5107, sub-option 11 of the
+    /// Relay Agent Information option), which clients unicast their RENEWING
+    /// requests to, so a relayed request carrying it is RENEWING unless
+    /// `broadcast`.
+    ///
+    /// Returns `None` if the message isn't a Request or the combination
+    /// matches none of the states
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, Message, MessageType, RequestState};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Request));
+    /// msg.opts_mut()
+    ///     .insert(DhcpOption::RequestedIpAddress([192, 168, 0, 5].into()));
+    /// assert_eq!(msg.dhcp_request_state(true), Some(RequestState::InitReboot));
+    /// msg.opts_mut()
+    ///     .insert(DhcpOption::ServerIdentifier([192, 168, 0, 1].into()));
+    /// assert_eq!(msg.dhcp_request_state(true), Some(RequestState::Selecting));
+    /// ```
+    pub fn dhcp_request_state(&self, broadcast: bool) -> Option<RequestState> {
+        if !self.opts.has_msg_type(MessageType::Request) {
+            return None;
+        }
+        let server_id = self.opts.get(OptionCode::ServerIdentifier).is_some();
+        let requested_ip = self.opts.get(OptionCode::RequestedIpAddress).is_some();
+        let server_id_override = self
+            .opts
+            .relay_agent_information()
+            .and_then(|info| info.server_identifier_override())
+            .is_some();
+        let relayed_rebind = self.has_giaddr() && !server_id_override;
+        match (self.has_ciaddr(), server_id, requested_ip) {
+            (false, true, true) => Some(RequestState::Selecting),
+            (false, false, true) => Some(RequestState::InitReboot),
+            (true, false, false) if broadcast || relayed_rebind => Some(RequestState::Rebinding),
+            (true, false, false) => Some(RequestState::Renewing),
+            _ => None,
+        }
+    }
+
+    /// Get the message's chaddr. Returns the first `hlen` bytes, clamped to
+    /// the 16 bytes of the `chaddr` field if `hlen` is larger
     pub fn chaddr(&self) -> &[u8] {
-        &self.chaddr[..(self.hlen as usize)]
+        &self.chaddr[..(self.hlen as usize).min(self.chaddr.len())]
+    }
+
+    /// Get the client's hardware address, using `min(hlen, 16)` bytes of
+    /// `chaddr`. For Ethernet the result is further clamped to 6 bytes, so a
+    /// malformed `hlen` still yields the MAC.
+    /// ```
+    /// # use dhcproto::v4::{Message, HType};
+    /// let mut msg = Message::default();
+    /// msg.set_chaddr(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01, 0xff, 0xff]);
+    /// assert_eq!(msg.htype(), HType::Eth);
+    /// assert_eq!(msg.client_mac(), &[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]);
+    /// ```
+    pub fn client_mac(&self) -> &[u8] {
+        let chaddr = self.chaddr();
+        match self.htype {
+            HType::Eth => &chaddr[..chaddr.len().min(6)],
+            _ => chaddr,
+        }
+    }
+
+    /// Whether `other` is from the same client hardware, comparing `htype`,
+    /// `hlen` & the first `hlen` bytes of `chaddr`. Padding after `hlen` is
+    /// ignored
+    /// ```
+    /// # use dhcproto::v4::Message;
+    /// let mut a = Message::default();
+    /// a.set_chaddr(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01, 0xaa]);
+    /// let mut b = a.clone();
+    /// assert!(a.same_client_hw(&b));
+    /// b.set_chaddr(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]);
+    /// assert!(!a.same_client_hw(&b));
+    /// ```
+    pub fn same_client_hw(&self, other: &Message) -> bool {
+        self.htype == other.htype && self.hlen == other.hlen && self.chaddr() == other.chaddr()
+    }
+
+    /// A digest of the message's content, suitable as a dedup cache key.
+    /// `secs` & `hops` are excluded since they change on retransmission &
+    /// relaying, as are the bytes of `chaddr` beyond `hlen`. Options are
+    /// hashed in their encoded form, which is in ascending order of code.
+    ///
+    /// The digest is 64-bit FNV-1a
+    /// (<http://www.isthe.com/chongo/tech/comp/fnv/>), so it is stable across
+    /// platforms & releases, but it is not cryptographic.
+    /// ```
+    /// # use dhcproto::v4::{Message, DhcpOption, MessageType};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// let mut retransmit = msg.clone();
+    /// retransmit.set_secs(4);
+    /// assert_eq!(msg.content_digest(), retransmit.content_digest());
+    /// retransmit.opts_mut().insert(DhcpOption::Hostname("host".to_owned()));
+    /// assert_ne!(msg.content_digest(), retransmit.content_digest());
+    /// ```
+    pub fn content_digest(&self) -> u64 {
+        let mut h = Fnv1a::new();
+        h.write(&[self.opcode.into(), self.htype.into(), self.hlen]);
+        h.write(&self.xid.to_be_bytes());
+        h.write(&u16::from(self.flags).to_be_bytes());
+        for addr in [self.ciaddr, self.yiaddr, self.siaddr, self.giaddr] {
+            h.write(&addr.octets());
+        }
+        h.write(self.chaddr());
+        for field in [self.sname(), self.fname()] {
+            // length prefix keeps adjacent fields from running together
+            match field {
+                Some(bytes) => {
+                    h.write(&(bytes.len() as u32).to_be_bytes());
+                    h.write(bytes);
+                }
+                None => h.write(&u32::MAX.to_be_bytes()),
+            }
+        }
+        h.write(&self.magic);
+        if let Some(vend) = &self.vend {
+            h.write(vend);
+        }
+        for (code, opt) in self.opts.iter() {
+            match opt.to_vec() {
+                Ok(buf) => h.write(&buf),
+                // can't be put on the wire, fall back to the code
+                Err(_) => h.write(&[(*code).into()]),
+            }
+        }
+        h.finish()
     }
 
     /// Set the message's chaddr. `chaddr` can only up to 16 bytes in length
@@ -297,6 +898,20 @@ impl Message {
         self
     }
 
+    /// Set the message's chaddr to an Ethernet MAC, also setting `htype` to
+    /// [`HType::Eth`] & `hlen` to 6
+    /// ```
+    /// # use dhcproto::v4::{HType, Message};
+    /// let mut msg = Message::default();
+    /// msg.set_chaddr_from_mac([0x00, 0x1b, 0x21, 0xaa, 0xbb, 0xcc]);
+    /// assert_eq!(msg.htype(), HType::Eth);
+    /// assert_eq!(msg.chaddr(), &[0x00, 0x1b, 0x21, 0xaa, 0xbb, 0xcc]);
+    /// ```
+    pub fn set_chaddr_from_mac(&mut self, mac: [u8; 6]) -> &mut Self {
+        self.htype = HType::Eth;
+        self.set_chaddr(&mac)
+    }
+
     /// Get the message's giaddr.
     /// Gateway IP
     pub fn giaddr(&self) -> Ipv4Addr {
@@ -308,6 +923,10 @@ impl Message {
         self.giaddr = giaddr.into();
         self
     }
+    /// returns `true` if giaddr isn't `0.0.0.0`, i.e. the message was relayed
+    pub fn has_giaddr(&self) -> bool {
+        !self.giaddr.is_unspecified()
+    }
 
     /// Get the message's siaddr.
     /// Server IP
@@ -320,6 +939,10 @@ impl Message {
         self.siaddr = siaddr.into();
         self
     }
+    /// returns `true` if siaddr isn't `0.0.0.0`, i.e. a next server was given
+    pub fn has_siaddr(&self) -> bool {
+        !self.siaddr.is_unspecified()
+    }
 
     /// Get the message's yiaddr.
     /// Your IP
@@ -334,6 +957,10 @@ impl Message {
         self.yiaddr = yiaddr.into();
         self
     }
+    /// returns `true` if yiaddr isn't `0.0.0.0`, i.e. an address was assigned
+    pub fn has_yiaddr(&self) -> bool {
+        !self.yiaddr.is_unspecified()
+    }
 
     /// Get the message's ciaddr.
     /// Client IP
@@ -347,6 +974,10 @@ impl Message {
         self.ciaddr = ciaddr.into();
         self
     }
+    /// returns `true` if ciaddr isn't `0.0.0.0`, i.e. the client has an address
+    pub fn has_ciaddr(&self) -> bool {
+        !self.ciaddr.is_unspecified()
+    }
 
     /// clear addrs
     pub fn clear_addrs(&mut self) -> &mut Self {
@@ -372,6 +1003,18 @@ impl Message {
     pub fn secs(&self) -> u16 {
         self.secs
     }
+    /// Get the message's secs, read with the opposite byte order. Some clients
+    /// (notably early Windows) send `secs` little-endian; a small value here
+    /// alongside a large [`secs`](Message::secs) suggests this quirk.
+    /// ```
+    /// # use dhcproto::v4::Message;
+    /// let mut msg = Message::default();
+    /// msg.set_secs(0x0300); // client meant 3 seconds
+    /// assert_eq!(msg.secs_byteswapped(), 3);
+    /// ```
+    pub fn secs_byteswapped(&self) -> u16 {
+        self.secs.swap_bytes()
+    }
     /// Set the message's secs.
     pub fn set_secs(&mut self, secs: u16) -> &mut Self {
         self.secs = secs;
@@ -388,6 +1031,35 @@ impl Message {
         self.xid = xid;
         self
     }
+
+    /// Clone the message to retransmit it as part of the same transaction.
+    /// The xid is kept & `secs` set to `elapsed`, the time since the client
+    /// began acquiring or renewing its lease, saturating at `u16::MAX`
+    /// ```
+    /// # use std::time::Duration;
+    /// # use dhcproto::v4::Message;
+    /// let discover = Message::default();
+    /// let retry = discover.clone_for_retransmit(Duration::from_secs(4));
+    /// assert_eq!(retry.xid(), discover.xid());
+    /// assert_eq!(retry.secs(), 4);
+    ///
+    /// let next = retry.clone_new_transaction(rand::random());
+    /// assert_eq!(next.secs(), 0);
+    /// ```
+    pub fn clone_for_retransmit(&self, elapsed: Duration) -> Self {
+        let mut msg = self.clone();
+        msg.secs = u16::try_from(elapsed.as_secs()).unwrap_or(u16::MAX);
+        msg
+    }
+
+    /// Clone the message to start a new transaction, e.g. when a client
+    /// restarts its DISCOVER cycle. The xid is replaced & `secs` reset to 0
+    pub fn clone_new_transaction(&self, xid: u32) -> Self {
+        let mut msg = self.clone();
+        msg.xid = xid;
+        msg.secs = 0;
+        msg
+    }
     /// Get a reference to the message's fname. No particular encoding is enforced.
     pub fn fname(&self) -> Option<&[u8]> {
         self.fname.as_deref()
@@ -396,6 +1068,17 @@ impl Message {
     pub fn fname_str(&self) -> Option<Result<&str, Utf8Error>> {
         self.fname().map(std::str::from_utf8)
     }
+    /// Get the message's fname as a string, without the NUL terminator.
+    /// Borrows unless it holds invalid UTF-8, which is replaced with `U+FFFD`
+    /// ```
+    /// # use dhcproto::v4::Message;
+    /// let mut msg = Message::default();
+    /// msg.set_fname(b"boot\xff.img\0");
+    /// assert_eq!(msg.fname_lossy().unwrap(), "boot\u{fffd}.img");
+    /// ```
+    pub fn fname_lossy(&self) -> Option<Cow<'_, str>> {
+        self.fname().map(lossy_str)
+    }
     /// Set the message's fname using a UTF-8 string
     /// # Panic
     /// panics if file is greater than 128 bytes long
@@ -403,6 +1086,7 @@ impl Message {
         let file = file.as_ref().as_bytes();
         assert!(file.len() <= 128);
         self.fname = Some(file.to_vec());
+        self.raw_fname = None;
         self
     }
     /// Set the message's fname. No particular encoding is enforced.
@@ -411,6 +1095,7 @@ impl Message {
     pub fn set_fname(&mut self, file: &[u8]) -> &mut Self {
         assert!(file.len() <= 128);
         self.fname = Some(file.to_vec());
+        self.raw_fname = None;
         self
     }
     /// Get a reference to the message's sname. No particular encoding is enforced.
@@ -421,12 +1106,18 @@ impl Message {
     pub fn sname_str(&self) -> Option<Result<&str, Utf8Error>> {
         self.sname().map(std::str::from_utf8)
     }
+    /// Get the message's sname as a string, without the NUL terminator.
+    /// Borrows unless it holds invalid UTF-8, see [`Message::fname_lossy`]
+    pub fn sname_lossy(&self) -> Option<Cow<'_, str>> {
+        self.sname().map(lossy_str)
+    }
     /// Set the message's sname. No particular encoding is enforced.
     /// # Panic
     /// panics will if sname is greater than 64 bytes long
     pub fn set_sname(&mut self, sname: &[u8]) -> &mut Self {
         assert!(sname.len() <= 64);
         self.sname = Some(sname.to_vec());
+        self.raw_sname = None;
         self
     }
     /// Set the message's sname using a UTF-8 string
@@ -436,8 +1127,43 @@ impl Message {
         let sname = sname.as_ref().as_bytes();
         assert!(sname.len() <= 64);
         self.sname = Some(sname.to_vec());
+        self.raw_sname = None;
         self
     }
+    /// Set the message's sname, leaving room for the NUL terminator. Unlike
+    /// [`set_sname`](Self::set_sname) this neither panics nor allows a value
+    /// that fills the whole field, which would be decoded as no sname at all
+    ///
+    /// Returns
+    ///    Err - if sname is longer than 63 bytes, the message is unchanged
+    ///
+    /// ```rust
+    /// # use dhcproto::v4::Message;
+    /// let mut msg = Message::default();
+    /// msg.try_set_sname(b"tftp.example.com")?;
+    /// assert!(msg.try_set_sname(&[b'a'; 64]).is_err());
+    /// assert_eq!(msg.sname(), Some(&b"tftp.example.com"[..]));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_set_sname(&mut self, sname: &[u8]) -> Result<&mut Self, EncodeError> {
+        check_field_len("sname", sname, SNAME_LEN)?;
+        self.sname = Some(sname.to_vec());
+        self.raw_sname = None;
+        Ok(self)
+    }
+    /// Set the message's fname (boot file name), leaving room for the NUL
+    /// terminator. Unlike [`set_fname`](Self::set_fname) this neither panics
+    /// nor allows a value that fills the whole field, which would be decoded
+    /// as no file at all
+    ///
+    /// Returns
+    ///    Err - if file is longer than 127 bytes, the message is unchanged
+    pub fn try_set_fname(&mut self, file: &[u8]) -> Result<&mut Self, EncodeError> {
+        check_field_len("file", file, FNAME_LEN)?;
+        self.fname = Some(file.to_vec());
+        self.raw_fname = None;
+        Ok(self)
+    }
     /// Get a reference to the message's opts.
     pub fn opts(&self) -> &DhcpOptions {
         &self.opts
@@ -450,278 +1176,2324 @@ impl Message {
     }
 
     /// Get a mutable reference to the message's options.
+    ///
+    /// Nothing about the options is cached, accessors like
+    /// [`encoded_len`](Self::encoded_len) or the typed getters on
+    /// [`DhcpOptions`] read the current state, so changes made through the
+    /// returned reference are visible immediately without re-encoding.
     pub fn opts_mut(&mut self) -> &mut DhcpOptions {
         &mut self.opts
     }
-}
 
-impl Decodable for Message {
-    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
-        Ok(Message {
-            opcode: Opcode::decode(decoder)?,
-            htype: decoder.read_u8()?.into(),
-            hlen: decoder.read_u8()?,
-            hops: decoder.read_u8()?,
-            xid: decoder.read_u32()?,
-            secs: decoder.read_u16()?,
-            flags: decoder.read_u16()?.into(),
-            ciaddr: decoder.read_u32()?.into(),
-            yiaddr: decoder.read_u32()?.into(),
-            siaddr: decoder.read_u32()?.into(),
-            giaddr: decoder.read_u32()?.into(),
-            chaddr: decoder.read::<16>()?,
-            sname: decoder.read_nul_bytes::<64>()?,
-            fname: decoder.read_nul_bytes::<128>()?,
-            // TODO: check magic bytes against expected?
-            magic: decoder.read::<4>()?,
-            opts: DhcpOptions::decode(decoder)?,
-        })
+    /// number of bytes the message encodes to, the 240 byte fixed header
+    /// (including the magic cookie) plus the options, or the 236 byte fixed
+    /// header plus the vendor area for BOOTP messages
+    /// ```
+    /// # use dhcproto::{Encodable, v4::{DhcpOption, Message, MessageType}};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// assert_eq!(msg.encoded_len(), 244);
+    /// assert_eq!(msg.encoded_len(), msg.to_vec()?.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        const HEADER_LEN: usize = 236;
+        match &self.vend {
+            Some(vend) => HEADER_LEN + vend.len().max(BOOTP_VEND_LEN),
+            None => HEADER_LEN + MAGIC.len() + self.opts.encoded_len(),
+        }
     }
-}
 
-impl Encodable for Message {
-    fn encode(&self, e: &mut Encoder<'_>) -> EncodeResult<()> {
-        self.opcode.encode(e)?;
-        self.htype.encode(e)?;
-        e.write_u8(self.hlen)?;
-        e.write_u8(self.hops)?;
-        e.write_u32(self.xid)?;
-        e.write_u16(self.secs)?;
-        e.write_u16(self.flags.into())?;
-        e.write_u32(self.ciaddr.into())?;
-        e.write_u32(self.yiaddr.into())?;
-        e.write_u32(self.siaddr.into())?;
-        e.write_u32(self.giaddr.into())?;
-        e.write_slice(&self.chaddr[..])?;
-        e.write_fill(&self.sname, 64)?;
-        e.write_fill(&self.fname, 128)?;
+    /// Whether the message includes the DHCP magic cookie. BOOTP messages
+    /// decoded with [`Message::decode_bootp`] may not, and messages decoded
+    /// with a different cookie don't
+    pub fn has_magic_cookie(&self) -> bool {
+        self.vend.is_none() && self.magic == MAGIC
+    }
+
+    /// Include or omit the DHCP magic cookie. Without it the message is
+    /// encoded as BOOTP, options are not written & the 64 byte vendor area is
+    /// zero filled
+    pub fn set_magic_cookie(&mut self, present: bool) -> &mut Self {
+        if present {
+            self.magic = MAGIC;
+            self.vend = None;
+        } else {
+            self.magic = [0; 4];
+            self.vend = Some(Vec::new());
+        }
+        self
+    }
 
-        e.write(self.magic)?;
-        self.opts.encode(e)?;
+    /// The BOOTP vendor area (RFC 951) of a message without the magic cookie,
+    /// as decoded by [`Message::decode_bootp`]. It is written back unchanged,
+    /// zero filled to 64 bytes, in place of the cookie & options. `None` for
+    /// DHCP messages
+    /// ```
+    /// # use dhcproto::{Decoder, Encodable, v4::Message};
+    /// let mut msg = Message::default();
+    /// msg.set_magic_cookie(false);
+    /// let mut buf = msg.to_vec()?;
+    /// buf[236..240].copy_from_slice(b"vend");
+    ///
+    /// let msg = Message::decode_bootp(&mut Decoder::new(&buf))?;
+    /// assert_eq!(&msg.vendor_area().unwrap()[..4], b"vend");
+    /// assert_eq!(msg.to_vec()?, buf);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn vendor_area(&self) -> Option<&[u8]> {
+        self.vend.as_deref()
+    }
+
+    /// Check the message for common construction mistakes. A message with the
+    /// magic cookie & options is DHCP rather than BOOTP, so it must include
+    /// the Message Type option (53)
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, Message, MessageType, ValidationError};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::Hostname("client".to_owned()));
+    /// assert_eq!(msg.validate(), Err(ValidationError::MissingMessageType));
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// assert!(msg.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let is_dhcp = self.magic == MAGIC && !self.opts.is_empty();
+        if is_dhcp && self.opts.get(OptionCode::MessageType).is_none() {
+            return Err(ValidationError::MissingMessageType);
+        }
+        if !self.opcode_matches_msg_type() {
+            return Err(ValidationError::OpcodeMismatch {
+                opcode: self.opcode,
+                // mismatch requires a message type
+                msg_type: self.opts.msg_type().unwrap(),
+            });
+        }
         Ok(())
     }
-}
 
-impl fmt::Display for Message {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Message")
-            .field("xid", &self.xid())
-            .field("broadcast_flag", &self.flags().broadcast())
-            .field("ciaddr", &self.ciaddr())
-            .field("yiaddr", &self.yiaddr())
-            .field("siaddr", &self.siaddr())
-            .field("giaddr", &self.giaddr())
-            .field(
-                "chaddr",
-                &hex::encode(self.chaddr())
-                    .chars()
-                    .enumerate()
-                    .flat_map(|(i, c)| {
-                        if i != 0 && i % 2 == 0 {
-                            Some(':')
-                        } else {
-                            None
-                        }
-                        .into_iter()
-                        .chain(std::iter::once(c))
-                    })
-                    .collect::<String>(),
-            )
-            .field(
-                "opts",
-                &self.opts().iter().map(|(_, v)| v).collect::<Vec<_>>(),
-            )
-            .finish()
+    /// Check that `hlen` matches the address length of `htype`, flagging
+    /// malformed or spoofed hardware addresses. Hardware types without a known
+    /// address length always pass, see [`HType::expected_hlen`]
+    /// ```
+    /// # use dhcproto::v4::{HType, Message, ValidationError};
+    /// let mut msg = Message::default();
+    /// msg.set_chaddr(&[1, 2, 3, 4, 5, 6]);
+    /// assert!(msg.validate_hlen().is_ok());
+    /// msg.set_chaddr(&[1; 16]);
+    /// assert_eq!(
+    ///     msg.validate_hlen(),
+    ///     Err(ValidationError::HlenMismatch { htype: HType::Eth, hlen: 16, expected: 6 })
+    /// );
+    /// ```
+    pub fn validate_hlen(&self) -> Result<(), ValidationError> {
+        match self.htype.expected_hlen() {
+            Some(expected) if expected != self.hlen => Err(ValidationError::HlenMismatch {
+                htype: self.htype,
+                hlen: self.hlen,
+                expected,
+            }),
+            _ => Ok(()),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Returns false if the opcode contradicts the message type, e.g. an
+    /// Offer sent as a `BootRequest`. Messages without a message type, or
+    /// whose type may be sent either way, always match. See
+    /// [`MessageType::opcode`]
+    pub fn opcode_matches_msg_type(&self) -> bool {
+        match self.opts.msg_type().and_then(|ty| ty.opcode()) {
+            Some(expected) => self.opcode == expected,
+            None => true,
+        }
+    }
 
-    use super::*;
+    /// Returns the message with `opts` added to its options, replacing any
+    /// existing options with the same code
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, Message, MessageType};
+    /// let msg = Message::default().with_options([
+    ///     DhcpOption::MessageType(MessageType::Offer),
+    ///     DhcpOption::SubnetMask([255, 255, 255, 0].into()),
+    ///     DhcpOption::AddressLeaseTime(3600),
+    /// ]);
+    /// assert_eq!(msg.opts().msg_type(), Some(MessageType::Offer));
+    /// assert_eq!(msg.opts().len(), 3);
+    /// ```
+    pub fn with_options<I: IntoIterator<Item = DhcpOption>>(mut self, opts: I) -> Self {
+        self.opts.extend(opts);
+        self
+    }
 
-    type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+    /// A key for matching replies to in-flight requests, made of the xid and
+    /// client hardware address. Bytes of `chaddr` past `hlen` are ignored, so
+    /// messages with differently padded `chaddr` fields produce equal keys.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use dhcproto::v4::{Message, Opcode};
+    /// let mut req = Message::default();
+    /// req.set_xid(1).set_chaddr(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]);
+    /// let mut in_flight = HashMap::new();
+    /// in_flight.insert(req.transaction_key(), req.clone());
+    ///
+    /// let mut reply = req.clone();
+    /// reply.set_opcode(Opcode::BootReply);
+    /// assert!(in_flight.contains_key(&reply.transaction_key()));
+    /// ```
+    pub fn transaction_key(&self) -> TransactionKey {
+        let hlen = self.chaddr().len();
+        let mut chaddr = [0; 16];
+        chaddr[..hlen].copy_from_slice(self.chaddr());
+        TransactionKey {
+            xid: self.xid,
+            htype: self.htype,
+            hlen: hlen as u8,
+            chaddr,
+        }
+    }
 
-    fn decode_ipv4(input: Vec<u8>, expected: MessageType) -> Result<()> {
-        // decode
-        let msg = Message::decode(&mut Decoder::new(&input))?;
-        dbg!(&msg);
-        assert_eq!(msg.opts().msg_type().unwrap(), expected);
-        // now encode
-        let mut buf = Vec::new();
-        let mut e = Encoder::new(&mut buf);
-        msg.encode(&mut e)?;
-        println!("{:?}", buf);
-        println!("{:?}", input);
-        // decode again
-        let res = Message::decode(&mut Decoder::new(&buf))?;
-        // check Messages are equal after decoding/encoding
-        assert_eq!(msg, res);
-        Ok(())
+    /// A human-readable key for the client, for logging. The Client
+    /// Identifier (61) if present & not empty, as text if it's all printable
+    /// ASCII or else as colon separated hex, otherwise the client's hardware
+    /// address (see [`Message::client_mac`]) as colon separated hex
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, Message};
+    /// let mut msg = Message::default();
+    /// msg.set_chaddr(&[0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]);
+    /// assert_eq!(msg.client_identity_string(), "de:ad:be:ef:00:01");
+    ///
+    /// msg.opts_mut()
+    ///     .insert(DhcpOption::ClientIdentifier(vec![1, 0xde, 0xad, 0xbe, 0xef, 0x00, 0x01]));
+    /// assert_eq!(msg.client_identity_string(), "01:de:ad:be:ef:00:01");
+    ///
+    /// msg.opts_mut()
+    ///     .insert(DhcpOption::ClientIdentifier(b"router-7".to_vec()));
+    /// assert_eq!(msg.client_identity_string(), "router-7");
+    /// ```
+    pub fn client_identity_string(&self) -> String {
+        match self.opts.get(OptionCode::ClientIdentifier) {
+            Some(DhcpOption::ClientIdentifier(id)) if !id.is_empty() => {
+                if id.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+                    String::from_utf8_lossy(id).into_owned()
+                } else {
+                    colon_hex(id)
+                }
+            }
+            _ => colon_hex(self.client_mac()),
+        }
     }
-    #[test]
-    fn decode_offer() -> Result<()> {
-        decode_ipv4(offer(), MessageType::Offer)?;
-        Ok(())
+
+    /// Add relay agent circuit & remote ids to the Relay Agent Information
+    /// option (82), creating it if not present. Sub-options already in the
+    /// option are preserved, including a circuit or remote id added by a
+    /// downstream relay, so `circuit_id` & `remote_id` are only added if the
+    /// option doesn't already have them. Returns the previous relay info if
+    /// the option was already present.
+    /// ```
+    /// # use dhcproto::v4::{Message, relay::{RelayCode, RelayInfo}};
+    /// let mut msg = Message::default();
+    /// msg.push_relay_info(b"eth0".to_vec(), Some(b"relay1".to_vec()))?;
+    /// // a second relay keeps the first relay's ids
+    /// msg.push_relay_info(b"eth1".to_vec(), None)?;
+    /// // reply path, remove option 82 before forwarding to the client
+    /// let info = msg.pop_relay_info().unwrap();
+    /// assert_eq!(
+    ///     info.get(RelayCode::AgentCircuitId),
+    ///     Some(&RelayInfo::AgentCircuitId(b"eth0".to_vec()))
+    /// );
+    /// assert!(msg.pop_relay_info().is_none());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`EncodeError::EmptyOptionValue`] or [`EncodeError::OptionValueTooLong`]
+    /// if `circuit_id` or `remote_id` are empty or longer than 255 bytes, the
+    /// message is left unchanged
+    pub fn push_relay_info(
+        &mut self,
+        circuit_id: Vec<u8>,
+        remote_id: Option<Vec<u8>>,
+    ) -> EncodeResult<Option<relay::RelayAgentInformation>> {
+        check_sub_opt_len(relay::RelayCode::AgentCircuitId, &circuit_id)?;
+        if let Some(remote_id) = &remote_id {
+            check_sub_opt_len(relay::RelayCode::AgentRemoteId, remote_id)?;
+        }
+        let mut info = match self.opts.get(OptionCode::RelayAgentInformation) {
+            Some(DhcpOption::RelayAgentInformation(info)) => info.clone(),
+            _ => relay::RelayAgentInformation::default(),
+        };
+        if info.circuit_id().is_none() {
+            info.insert(relay::RelayInfo::AgentCircuitId(circuit_id));
+        }
+        if let Some(remote_id) = remote_id {
+            if info.remote_id().is_none() {
+                info.insert(relay::RelayInfo::AgentRemoteId(remote_id));
+            }
+        }
+        Ok(
+            match self.opts.insert(DhcpOption::RelayAgentInformation(info)) {
+                Some(DhcpOption::RelayAgentInformation(prev)) => Some(prev),
+                _ => None,
+            },
+        )
     }
 
-    #[test]
-    fn decode_discover() -> Result<()> {
-        decode_ipv4(discover(), MessageType::Discover)?;
-        Ok(())
+    /// Remove the Relay Agent Information option (82), returning it if present.
+    /// Relays remove option 82 from replies before forwarding them to the client
+    /// ```
+    /// # use dhcproto::{Decodable, Encodable, v4::{Message, relay::{RelayCode, RelayInfo}}};
+    /// // relay adds its info to the client's request
+    /// let mut req = Message::default();
+    /// req.push_relay_info(b"eth0/1".to_vec(), None)?;
+    /// // server echoes it in the reply
+    /// let mut reply = Message::from_bytes(&req.to_vec()?)?;
+    /// // relay strips it before forwarding to the client
+    /// let info = reply.pop_relay_info().unwrap();
+    /// assert_eq!(
+    ///     info.get(RelayCode::AgentCircuitId),
+    ///     Some(&RelayInfo::AgentCircuitId(b"eth0/1".to_vec()))
+    /// );
+    /// assert!(reply.pop_relay_info().is_none());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pop_relay_info(&mut self) -> Option<relay::RelayAgentInformation> {
+        match self.opts.remove(OptionCode::RelayAgentInformation) {
+            Some(DhcpOption::RelayAgentInformation(info)) => Some(info),
+            _ => None,
+        }
     }
 
-    #[test]
-    fn decode_offer_two() -> Result<()> {
-        decode_ipv4(other_offer(), MessageType::Offer)?;
-        Ok(())
+    /// Strip the Relay Agent Information option (82) from a reply before
+    /// forwarding it to the client, returning it if present. Same as
+    /// [`Message::pop_relay_info`]
+    pub fn strip_relay_info(&mut self) -> Option<relay::RelayAgentInformation> {
+        self.pop_relay_info()
     }
 
-    #[test]
-    fn decode_bootreq() -> Result<()> {
-        let offer = bootreq();
-        let msg = Message::decode(&mut Decoder::new(&offer))?;
-        println!("{:?}", msg);
-        // now encode
-        let mut buf = Vec::new();
-        let mut e = Encoder::new(&mut buf);
-        msg.encode(&mut e)?;
-        assert_eq!(buf, bootreq());
-        Ok(())
+    /// The routes a client should install, as `(destination, gateway)` pairs.
+    /// As per RFC 3442, if the Classless Static Route option (121) is present
+    /// its routes are used & the Router option (3) is ignored. Otherwise each
+    /// router becomes a default route (`0.0.0.0/0`), in order of preference.
+    /// ```
+    /// # use dhcproto::v4::{Message, DhcpOption, classless::ClasslessRoute};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::Router(vec![[10, 0, 0, 1].into()]));
+    /// assert_eq!(
+    ///     msg.effective_routes(),
+    ///     vec![("0.0.0.0/0".parse()?, [10, 0, 0, 1].into())]
+    /// );
+    /// msg.opts_mut().insert(DhcpOption::ClasslessStaticRoute(vec![ClasslessRoute::new(
+    ///     "10.1.0.0/16".parse()?,
+    ///     [10, 0, 0, 254].into(),
+    /// )]));
+    /// assert_eq!(
+    ///     msg.effective_routes(),
+    ///     vec![("10.1.0.0/16".parse()?, [10, 0, 0, 254].into())]
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn effective_routes(&self) -> Vec<(classless::Ipv4Net, Ipv4Addr)> {
+        if let Some(routes) = self.opts.classless_static_routes() {
+            return routes.iter().map(|r| r.to_route_entry()).collect();
+        }
+        self.opts
+            .routers_iter()
+            // Ipv4Net::default() is 0.0.0.0/0
+            .map(|r| (classless::Ipv4Net::default(), r))
+            .collect()
     }
 
-    #[test]
-    fn test_set_chaddr() -> Result<()> {
-        let mut msg = Message::new(
-            Ipv4Addr::UNSPECIFIED,
-            Ipv4Addr::UNSPECIFIED,
-            Ipv4Addr::UNSPECIFIED,
-            Ipv4Addr::UNSPECIFIED,
-            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
-        );
-        msg.set_chaddr(&[0, 1, 2, 3, 4, 5]);
-        assert_eq!(msg.chaddr().len(), 6);
-        Ok(())
+    /// The name the client wants to be known by. Returns the domain from the
+    /// Client FQDN option (81) if present & non-empty, otherwise the Host Name
+    /// option (12). Servers performing DNS updates should prefer option 81
+    /// when both are sent, as per RFC 4702 section 3.
+    /// ```
+    /// # use dhcproto::{Domain, v4::{Message, DhcpOption, fqdn::{ClientFQDN, ClientName, FqdnFlags}}};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::Hostname("host".to_owned()));
+    /// assert_eq!(msg.preferred_name(), Some(ClientName::Hostname("host")));
+    ///
+    /// let domain = Domain::from("host.example.com.".parse::<trust_dns_proto::rr::Name>()?);
+    /// msg.opts_mut().insert(DhcpOption::ClientFQDN(ClientFQDN::new(
+    ///     FqdnFlags::default().set_e(true),
+    ///     domain.clone(),
+    /// )));
+    /// assert_eq!(msg.preferred_name(), Some(ClientName::Fqdn(&domain)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn preferred_name(&self) -> Option<fqdn::ClientName<'_>> {
+        match self.opts.client_fqdn() {
+            Some(fqdn) if fqdn.domain().0.num_labels() > 0 => {
+                Some(fqdn::ClientName::Fqdn(fqdn.domain()))
+            }
+            _ => match self.opts.get(OptionCode::Hostname) {
+                Some(DhcpOption::Hostname(name)) if !name.is_empty() => {
+                    Some(fqdn::ClientName::Hostname(name))
+                }
+                _ => None,
+            },
+        }
     }
 
-    #[cfg(feature = "serde")]
-    #[test]
-    fn test_json() -> Result<()> {
-        let msg = Message::decode(&mut Decoder::new(&offer()))?;
-        let s = serde_json::to_string_pretty(&msg)?;
-        println!("{s}");
-        let other = serde_json::from_str(&s)?;
-        assert_eq!(msg, other);
-        Ok(())
+    /// Remove every option the crate doesn't recognize, i.e. those decoded as
+    /// [`DhcpOption::Unknown`], leaving only typed options
+    /// ```
+    /// # use dhcproto::v4::{Message, DhcpOption, MessageType, UnknownOption, OptionCode};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// msg.opts_mut().insert(DhcpOption::Unknown(UnknownOption::new(
+    ///     OptionCode::Unknown(224),
+    ///     vec![1, 2, 3],
+    /// )));
+    /// msg.strip_unknown_options();
+    /// assert_eq!(msg.opts().len(), 1);
+    /// ```
+    pub fn strip_unknown_options(&mut self) -> &mut Self {
+        self.opts.retain(|code, _| code.is_known());
+        self
     }
 
-    fn offer() -> Vec<u8> {
-        vec![
-            0x02, 0x01, 0x06, 0x00, 0x00, 0x00, 0x15, 0x5c, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0xc0, 0xa8, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xcc, 0x00, 0x0a, 0xc4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82,
-            0x53, 0x63, 0x35, 0x01, 0x02, 0x36, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x33, 0x04, 0x00,
-            0x00, 0x00, 0x3c, 0x3a, 0x04, 0x00, 0x00, 0x00, 0x1e, 0x3b, 0x04, 0x00, 0x00, 0x00,
-            0x34, 0x01, 0x04, 0xff, 0xff, 0xff, 0x00, 0x03, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x06,
-            0x08, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x01, 0x01, 0xff, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        ]
-    }
-    fn bootreq() -> Vec<u8> {
-        vec![
-            1u8, // op
-            2,   // htype
-            3,   // hlen
-            4,   // ops
-            5, 6, 7, 8, // xid
-            9, 10, // secs
-            11, 12, // flags
-            13, 14, 15, 16, // ciaddr
-            17, 18, 19, 20, // yiaddr
-            21, 22, 23, 24, // siaddr
-            25, 26, 27, 28, // giaddr
-            29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, // chaddr
-            45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66,
-            67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88,
-            89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107,
-            0, // sname: "-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijk",
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125,
-            109, 0, 0, 0, 0, 0, 0, 0,
-            0, // file: "mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}m",
-            99, 130, 83, 99, // magic cookie
-        ]
-    }
-    fn discover() -> Vec<u8> {
-        vec![
-            0x01, 0x01, 0x06, 0x00, 0xa6, 0x80, 0x56, 0x74, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xde, 0xad, 0xc0, 0xde, 0xca, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82,
-            0x53, 0x63, 0x35, 0x01, 0x01, 0x37, 0x40, 0xfc, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
-            0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14,
-            0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22,
-            0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30,
-            0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x43,
-            0x42, 0x33, 0x04, 0x00, 0x00, 0x00, 0x01, 0xff,
-        ]
-    }
-    fn other_offer() -> Vec<u8> {
-        vec![
-            0x02, 0x01, 0x06, 0x00, 0xa6, 0x80, 0x56, 0x74, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0xc0, 0xa8, 0x00, 0x95, 0xc0, 0xa8, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
-            0xde, 0xad, 0xc0, 0xde, 0xca, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82,
-            0x53, 0x63, 0x35, 0x01, 0x02, 0x36, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x33, 0x04, 0x00,
-            0x00, 0x00, 0x78, 0x3a, 0x04, 0x00, 0x00, 0x00, 0x3c, 0x3b, 0x04, 0x00, 0x00, 0x00,
-            0x69, 0x01, 0x04, 0xff, 0xff, 0xff, 0x00, 0x1c, 0x04, 0xc0, 0xa8, 0x00, 0xff, 0x06,
-            0x04, 0xc0, 0xa8, 0x00, 0x01, 0x03, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0xff, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        ]
+    /// Redact identifying information for privacy preserving logging, keeping
+    /// the structure of the message intact:
+    /// - `chaddr` is zeroed, except for the first 3 bytes (the OUI) if
+    ///   `keep_oui` is set
+    /// - `ciaddr`, `yiaddr`, `siaddr`, `giaddr` & the Requested IP Address
+    ///   option (50) are set to 0.0.0.0
+    /// - Host Name (12), Client Identifier (61) & Client FQDN (81) are removed
+    /// ```
+    /// # use dhcproto::v4::{Message, DhcpOption};
+    /// let mut msg = Message::default();
+    /// msg.set_chaddr(&[0x00, 0x1b, 0x21, 0xaa, 0xbb, 0xcc])
+    ///     .set_ciaddr([192, 168, 0, 10]);
+    /// msg.opts_mut().insert(DhcpOption::Hostname("alice-laptop".to_owned()));
+    /// msg.anonymize(true);
+    /// assert_eq!(msg.chaddr(), &[0x00, 0x1b, 0x21, 0, 0, 0]);
+    /// assert!(msg.ciaddr().is_unspecified());
+    /// assert!(msg.opts().is_empty());
+    /// ```
+    pub fn anonymize(&mut self, keep_oui: bool) -> &mut Self {
+        const OUI_LEN: usize = 3;
+        let keep = if keep_oui { OUI_LEN } else { 0 };
+        self.chaddr[keep..].fill(0);
+        for addr in [
+            &mut self.ciaddr,
+            &mut self.yiaddr,
+            &mut self.siaddr,
+            &mut self.giaddr,
+        ] {
+            *addr = Ipv4Addr::UNSPECIFIED;
+        }
+        for code in [
+            OptionCode::Hostname,
+            OptionCode::ClientIdentifier,
+            OptionCode::ClientFQDN,
+        ] {
+            self.opts.remove(code);
+        }
+        if let Some(DhcpOption::RequestedIpAddress(ip)) =
+            self.opts.get_mut(OptionCode::RequestedIpAddress)
+        {
+            *ip = Ipv4Addr::UNSPECIFIED;
+        }
+        self
+    }
+
+    /// Map of the option codes present in the message to their standardized
+    /// names, ordered by code. Unrecognized codes map to `"Unknown"`
+    pub fn options_summary(&self) -> BTreeMap<u8, String> {
+        self.opts
+            .iter()
+            .map(|(code, _)| (u8::from(*code), code.name().to_owned()))
+            .collect()
+    }
+}
+
+impl Message {
+    /// Decode a message, also returning the original wire bytes of each
+    /// option. See [`DhcpOptions::decode_with_raw`]
+    pub fn decode_with_raw_opts<'a>(
+        decoder: &mut Decoder<'a>,
+    ) -> DecodeResult<(Self, RawOptions<'a>)> {
+        let mut msg = Self::decode_header(decoder)?;
+        let (opts, raw) = DhcpOptions::decode_with_raw(decoder)?;
+        msg.opts = opts;
+        Ok((msg, raw))
+    }
+
+    /// Decode a message, calling `on_unknown` for each option that isn't
+    /// modeled by [`DhcpOption`]. See [`DhcpOptions::decode_with_unknown`]
+    pub fn decode_with_unknown(
+        decoder: &mut Decoder<'_>,
+        on_unknown: impl FnMut(u8, &[u8]) -> KeepOrDrop,
+    ) -> DecodeResult<Self> {
+        let mut msg = Self::decode_header(decoder)?;
+        msg.opts = DhcpOptions::decode_with_unknown(decoder, on_unknown)?;
+        Ok(msg)
+    }
+
+    /// Decode a message, retaining the original sname & file regions
+    /// verbatim. Bytes following the NUL terminator in those fields are
+    /// normally dropped, a message decoded this way re-encodes them unchanged
+    /// until `set_sname`/`set_fname` is called. The retained bytes aren't
+    /// compared or hashed, so it is equal to the same message from `decode`.
+    /// ```
+    /// # use dhcproto::{Decoder, Encodable, v4::Message};
+    /// let mut input = Message::default().to_vec()?;
+    /// // junk after the (empty) sname
+    /// input[44 + 1] = 0xAB;
+    /// let msg = Message::decode_lossless(&mut Decoder::new(&input))?;
+    /// assert_eq!(msg.sname(), None);
+    /// assert_eq!(msg.to_vec()?, input);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_lossless(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        let buf = decoder.remaining();
+        let mut msg = Self::decode(decoder)?;
+        // header decoded successfully, so these ranges are in bounds
+        msg.raw_sname = Some(buf[44..108].to_vec());
+        msg.raw_fname = Some(buf[108..236].to_vec());
+        Ok(msg)
+    }
+
+    /// Decode consecutive messages from `buf`, where `lengths` are the sizes
+    /// of each message, e.g. UDP payloads read from a capture. Each message is
+    /// decoded from its own region, so an error decoding one message is
+    /// returned for that message without affecting the rest of the batch.
+    ///
+    /// A message's region starts where the previous one ends, so the lengths
+    /// must be consistent with `buf`: a length running past the end of `buf`
+    /// is a [`DecodeError::NotEnoughBytes`] error for that message & every
+    /// message after it, as there are no bytes left for them.
+    /// ```
+    /// # use dhcproto::{Encodable, v4::Message};
+    /// let (a, b) = (Message::default().to_vec()?, Message::default().to_vec()?);
+    /// let stream = [&a[..], &b[..10], &a[..]].concat();
+    ///
+    /// let msgs = Message::decode_all(&stream, &[a.len(), 10, a.len()]).collect::<Vec<_>>();
+    /// assert!(msgs[0].is_ok());
+    /// assert!(msgs[1].is_err());
+    /// assert!(msgs[2].is_ok());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_all<'a>(
+        buf: &'a [u8],
+        lengths: &'a [usize],
+    ) -> impl Iterator<Item = DecodeResult<Self>> + 'a {
+        let mut start = 0_usize;
+        lengths.iter().map(move |&len| {
+            let end = start.saturating_add(len);
+            let msg = buf.get(start..end).ok_or(DecodeError::NotEnoughBytes);
+            start = end;
+            Self::decode(&mut Decoder::new(msg?))
+        })
+    }
+
+    /// Find the Message Type (option 53) in an encoded message without
+    /// decoding it. Only the options region after the magic cookie is
+    /// scanned, stopping at the first option 53 or the End option, so a
+    /// message type carried in an overloaded sname/file field isn't found.
+    /// Returns `None` if the cookie or option 53 is missing, or the options
+    /// are truncated before it
+    /// ```
+    /// # use dhcproto::{Encodable, v4::{DhcpOption, Message, MessageType}};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// let buf = msg.to_vec()?;
+    /// assert_eq!(Message::peek_msg_type(&buf), Some(MessageType::Discover));
+    /// assert_eq!(Message::peek_msg_type(&buf[..236]), None);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn peek_msg_type(buf: &[u8]) -> Option<MessageType> {
+        const OPTS_START: usize = 236 + MAGIC.len();
+        if buf.get(OPTS_START - MAGIC.len()..OPTS_START)? != MAGIC {
+            return None;
+        }
+        let mut i = OPTS_START;
+        loop {
+            match *buf.get(i)? {
+                0 => i += 1,
+                255 => return None,
+                code => {
+                    let len = *buf.get(i + 1)? as usize;
+                    if code == u8::from(OptionCode::MessageType) && len > 0 {
+                        return buf.get(i + 2).map(|&ty| ty.into());
+                    }
+                    i += 2 + len;
+                }
+            }
+        }
+    }
+
+    /// Decode a message that may be BOOTP without the magic cookie, as
+    /// forwarded by some legacy relays. If the cookie is missing, the options
+    /// are empty & the vendor area (if any) is kept as raw bytes, see
+    /// [`Message::vendor_area`], rather than being interpreted as DHCP options.
+    /// ```
+    /// # use dhcproto::{Decoder, Encodable, v4::Message};
+    /// let mut msg = Message::default();
+    /// msg.set_magic_cookie(false);
+    /// let bootp = msg.to_vec()?;
+    /// assert_eq!(bootp.len(), 300);
+    /// // truncated after the fixed fields
+    /// let msg = Message::decode_bootp(&mut Decoder::new(&bootp[..236]))?;
+    /// assert!(!msg.has_magic_cookie());
+    /// assert!(msg.opts().is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_bootp(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Self::decode_optional_cookie(decoder, OptionsMode::Dhcp)
+    }
+
+    /// decode a message that may not have the magic cookie, reading the
+    /// options following it as `mode`
+    fn decode_optional_cookie(decoder: &mut Decoder<'_>, mode: OptionsMode) -> DecodeResult<Self> {
+        let mut msg = Self::decode_fixed(decoder)?;
+        if decoder.peek::<4>().ok() == Some(MAGIC) {
+            msg.magic = decoder.read::<4>()?;
+            skip_repeated_cookies(decoder)?;
+            msg.opts = match mode {
+                OptionsMode::Dhcp => DhcpOptions::decode(decoder)?,
+                OptionsMode::Bootp => DhcpOptions::decode_vendor_extensions(decoder)?,
+            };
+        } else {
+            msg.vend = Some(decoder.read_slice(decoder.buffer().len())?.to_vec());
+        }
+        Ok(msg)
+    }
+
+    /// Decode a message, interpreting the options following the magic cookie
+    /// as either DHCP options or BOOTP vendor extensions. See [`OptionsMode`]
+    /// ```
+    /// # use dhcproto::{Decoder, Encodable, error::DecodeError, v4::*};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::Hostname("client".into()));
+    /// msg.opts_mut().insert(DhcpOption::AddressLeaseTime(60));
+    /// let buf = msg.to_vec()?;
+    ///
+    /// let bootp = Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Bootp)?;
+    /// assert!(bootp.opts().get(OptionCode::Hostname).is_some());
+    /// assert!(bootp.opts().get(OptionCode::AddressLeaseTime).is_none());
+    /// assert!(matches!(
+    ///     bootp.opts().get(OptionCode::Unknown(51)),
+    ///     Some(DhcpOption::Unknown(opt)) if opt.data() == [0, 0, 0, 60]
+    /// ));
+    /// assert!(matches!(
+    ///     Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Dhcp),
+    ///     Err(DecodeError::MissingMessageType)
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_as(decoder: &mut Decoder<'_>, mode: OptionsMode) -> DecodeResult<Self> {
+        match mode {
+            OptionsMode::Dhcp => {
+                let cookie = decoder.offset() + 236;
+                let msg = Self::decode(decoder)?;
+                if !msg.has_magic_cookie() {
+                    return Err(DecodeError::MagicCookieNotFound { window: 0 }
+                        .in_field("magic cookie", cookie));
+                }
+                if msg.opts.msg_type().is_none() {
+                    return Err(DecodeError::MissingMessageType);
+                }
+                Ok(msg)
+            }
+            OptionsMode::Bootp => Self::decode_optional_cookie(decoder, mode),
+        }
+    }
+
+    /// Encode the message & write it to `writer`, e.g. a socket
+    ///
+    /// [`Encoder`] writes to a `Vec`, so the message is staged in `scratch`,
+    /// which is cleared first. Reusing the same `scratch` for every message
+    /// means its capacity is only grown for the largest one, rather than
+    /// allocating per message
+    /// ```
+    /// # use dhcproto::{Encodable, v4::Message};
+    /// let mut scratch = Vec::new();
+    /// let mut out = Vec::new();
+    /// for msg in [Message::default(), Message::default()] {
+    ///     msg.encode_to_writer(&mut out, &mut scratch)?;
+    ///     assert!(out.ends_with(&msg.to_vec()?));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn encode_to_writer<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        scratch: &mut Vec<u8>,
+    ) -> EncodeResult<()> {
+        scratch.clear();
+        scratch.reserve(self.encoded_len());
+        self.encode(&mut Encoder::new(scratch))?;
+        writer.write_all(scratch)?;
+        Ok(())
+    }
+
+    /// Encode the message with the options in `order` written first, see
+    /// [`DhcpOptions::encode_ordered`]. Some clients expect the Message Type
+    /// option (53) to be the first option
+    /// ```
+    /// # use dhcproto::{Encoder, v4::{Message, DhcpOption, MessageType, OptionCode}};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Offer));
+    ///
+    /// let mut buf = Vec::new();
+    /// msg.encode_ordered(&[OptionCode::MessageType], &mut Encoder::new(&mut buf))?;
+    /// assert_eq!(&buf[240..243], &[53, 1, 2]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn encode_ordered(&self, order: &[OptionCode], e: &mut Encoder<'_>) -> EncodeResult<()> {
+        self.opcode.encode(e)?;
+        self.htype.encode(e)?;
+        e.write_u8(self.hlen)?;
+        e.write_u8(self.hops)?;
+        e.write_u32(self.xid)?;
+        e.write_u16(self.secs)?;
+        e.write_u16(self.flags.into())?;
+        e.write_u32(self.ciaddr.into())?;
+        e.write_u32(self.yiaddr.into())?;
+        e.write_u32(self.siaddr.into())?;
+        e.write_u32(self.giaddr.into())?;
+        e.write_slice(&self.chaddr[..])?;
+        match &self.raw_sname {
+            Some(raw) => e.write_slice(raw),
+            None => e.write_fill(&self.sname, SNAME_LEN),
+        }
+        .map_err(|err| err.in_field("sname"))?;
+        match &self.raw_fname {
+            Some(raw) => e.write_slice(raw),
+            None => e.write_fill(&self.fname, FNAME_LEN),
+        }
+        .map_err(|err| err.in_field("file"))?;
+
+        match &self.vend {
+            Some(vend) => {
+                e.write_slice(vend)?;
+                let fill = BOOTP_VEND_LEN.saturating_sub(vend.len());
+                e.write_slice(&[0; BOOTP_VEND_LEN][..fill])?;
+            }
+            None => {
+                e.write(self.magic)?;
+                self.opts.encode_ordered(order, e)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode a message, returning an error if an option that must appear at
+    /// most once is repeated (see [`DhcpOptions::decode_strict`]), or if the
+    /// magic cookie doesn't directly follow `file` or is repeated. The regular
+    /// decode skips repeated cookies
+    /// ```
+    /// # use dhcproto::{Decodable, Decoder, Encodable, error::DecodeError, v4::{Message, MAGIC}};
+    /// let mut buf = Message::default().to_vec()?;
+    /// buf.splice(240..240, MAGIC);
+    /// assert!(Message::from_bytes(&buf).is_ok());
+    /// assert!(matches!(
+    ///     Message::decode_strict(&mut Decoder::new(&buf)),
+    ///     Err(DecodeError::DuplicateMagicCookie { offset: 240 })
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_strict(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        let mut msg = Self::decode_fixed(decoder)?;
+        msg.magic = field(decoder, "magic cookie", |d| match d.read::<4>()? {
+            MAGIC => Ok(MAGIC),
+            _ => Err(DecodeError::MagicCookieNotFound { window: 0 }),
+        })?;
+        if decoder.peek::<4>().ok() == Some(MAGIC) {
+            return Err(DecodeError::DuplicateMagicCookie {
+                offset: decoder.offset(),
+            });
+        }
+        msg.opts = DhcpOptions::decode_strict(decoder)?;
+        Ok(msg)
+    }
+
+    /// Decode a message whose magic cookie may not immediately follow the
+    /// `file` field. Up to `window` stray bytes after `file` are skipped to
+    /// find the cookie, a `window` of 0 is equivalent to the regular decode.
+    /// Returns [`DecodeError::MagicCookieNotFound`] if the cookie isn't found.
+    /// For analyzing slightly malformed captures, the skipped bytes are lost
+    /// ```
+    /// # use dhcproto::{Decoder, Encodable, v4::Message};
+    /// let mut buf = Message::default().to_vec()?;
+    /// // insert 2 junk bytes before the cookie
+    /// buf.splice(236..236, [0xde, 0xad]);
+    /// let msg = Message::decode_scan_cookie(&mut Decoder::new(&buf), 4)?;
+    /// assert!(msg.has_magic_cookie());
+    /// assert!(Message::decode_scan_cookie(&mut Decoder::new(&buf), 1).is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_scan_cookie(decoder: &mut Decoder<'_>, window: usize) -> DecodeResult<Self> {
+        let mut msg = Self::decode_fixed(decoder)?;
+        let offset = decoder
+            .buffer()
+            .windows(MAGIC.len())
+            .take(window.saturating_add(1))
+            .position(|w| w == MAGIC)
+            .ok_or(DecodeError::MagicCookieNotFound { window })?;
+        decoder.read_slice(offset)?;
+        msg.magic = decoder.read::<4>()?;
+        skip_repeated_cookies(decoder)?;
+        msg.opts = DhcpOptions::decode(decoder)?;
+        Ok(msg)
+    }
+
+    /// Best-effort decode for analyzing malformed traffic. Rather than failing
+    /// outright, returns whatever was decoded before the first error along
+    /// with the error: the header & the options preceding the option that
+    /// failed. See [`PartialMessage`]
+    /// ```
+    /// # use dhcproto::{Encodable, v4::{Message, DhcpOption, MessageType}};
+    /// let mut msg = Message::default();
+    /// msg.opts_mut().insert(DhcpOption::MessageType(MessageType::Discover));
+    /// let mut buf = msg.to_vec()?;
+    /// // replace the End option with a truncated hostname
+    /// buf.pop();
+    /// buf.extend([12, 10, b'h']);
+    ///
+    /// let (partial, err) = Message::decode_partial(&buf);
+    /// assert!(err.is_some());
+    /// let msg = partial.message().unwrap();
+    /// assert_eq!(msg.opts().msg_type(), Some(MessageType::Discover));
+    /// assert_eq!(partial.valid_len(), buf.len() - 3);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_partial(buf: &[u8]) -> (PartialMessage, Option<DecodeError>) {
+        let mut decoder = Decoder::new(buf);
+        let mut msg = match Self::decode_header(&mut decoder) {
+            Ok(msg) => msg,
+            Err(err) => {
+                let partial = PartialMessage {
+                    message: None,
+                    valid_len: 0,
+                };
+                return (partial, Some(err));
+            }
+        };
+        let (opts, err) = DhcpOptions::decode_partial(&mut decoder);
+        msg.opts = opts;
+        let partial = PartialMessage {
+            message: Some(msg),
+            valid_len: buf.len() - decoder.buffer().len(),
+        };
+        (partial, err)
+    }
+
+    /// decode everything up to & including the magic cookie
+    fn decode_header(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        let mut msg = Self::decode_fixed(decoder)?;
+        // anything else is BOOTP, see `Message::decode_strict` to reject it
+        msg.magic = field(decoder, "magic cookie", |d| d.read::<4>())?;
+        if msg.magic == MAGIC {
+            skip_repeated_cookies(decoder)?;
+        }
+        Ok(msg)
+    }
+
+    /// decode the fixed fields preceding the magic cookie, leaving it unset
+    fn decode_fixed(d: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Ok(Message {
+            opcode: field(d, "op", Opcode::decode)?,
+            htype: field(d, "htype", |d| d.read_u8())?.into(),
+            hlen: field(d, "hlen", |d| d.read_u8())?,
+            hops: field(d, "hops", |d| d.read_u8())?,
+            xid: field(d, "xid", |d| d.read_u32())?,
+            secs: field(d, "secs", |d| d.read_u16())?,
+            flags: field(d, "flags", |d| d.read_u16())?.into(),
+            ciaddr: field(d, "ciaddr", |d| d.read_u32())?.into(),
+            yiaddr: field(d, "yiaddr", |d| d.read_u32())?.into(),
+            siaddr: field(d, "siaddr", |d| d.read_u32())?.into(),
+            giaddr: field(d, "giaddr", |d| d.read_u32())?.into(),
+            chaddr: field(d, "chaddr", |d| d.read::<16>())?,
+            sname: field(d, "sname", |d| d.read_nul_bytes::<SNAME_LEN>())?,
+            fname: field(d, "file", |d| d.read_nul_bytes::<FNAME_LEN>())?,
+            raw_sname: None,
+            raw_fname: None,
+            magic: [0; 4],
+            vend: None,
+            opts: DhcpOptions::default(),
+        })
+    }
+}
+
+/// decode a header field, adding its name & offset to any error
+fn field<'a, T>(
+    decoder: &mut Decoder<'a>,
+    name: &'static str,
+    f: impl FnOnce(&mut Decoder<'a>) -> DecodeResult<T>,
+) -> DecodeResult<T> {
+    let offset = decoder.offset();
+    f(decoder).map_err(|err| err.in_field(name, offset))
+}
+
+/// skip copies of the magic cookie directly following it, as sent by some
+/// broken relays. Otherwise it would read as option 99 (GeoConf Civic) of 130
+/// bytes starting with 83, which isn't a valid `what` element
+fn skip_repeated_cookies(decoder: &mut Decoder<'_>) -> DecodeResult<()> {
+    while decoder.peek::<4>().ok() == Some(MAGIC) {
+        decoder.read::<4>()?;
+    }
+    Ok(())
+}
+
+/// check `value` fits in a NUL terminated field of `size` bytes
+fn check_field_len(field: &'static str, value: &[u8], size: usize) -> EncodeResult<()> {
+    if value.len() >= size {
+        return Err(EncodeError::FieldTooLong {
+            field,
+            len: value.len(),
+            max: size - 1,
+        });
+    }
+    Ok(())
+}
+
+/// check `value` fits in a relay agent sub-option, which can't be empty
+fn check_sub_opt_len(code: relay::RelayCode, value: &[u8]) -> EncodeResult<()> {
+    let code = u8::from(code);
+    match value.len() {
+        0 => Err(EncodeError::EmptyOptionValue { code }),
+        1..=255 => Ok(()),
+        len => Err(EncodeError::OptionValueTooLong { code, len }),
+    }
+}
+
+/// `bytes` as a string, lossily converted & without trailing NULs
+fn lossy_str(bytes: &[u8]) -> Cow<'_, str> {
+    let len = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&bytes[..len])
+}
+
+/// `bytes` as lowercase hex, separated by colons
+fn colon_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Parse one of the `u8` backed enums from a canonical `name` (ignoring
+/// case), `UNKNOWN(n)` or a plain number `n`
+pub(crate) fn parse_name<T: From<u8>>(
+    kind: &'static str,
+    s: &str,
+    name: impl Fn(&T) -> Option<&'static str>,
+) -> Result<T, ParseNameError> {
+    let s = s.trim();
+    let num = s
+        .get(..8)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("UNKNOWN("))
+        .and_then(|_| s[8..].strip_suffix(')'))
+        .unwrap_or(s);
+    if let Ok(n) = num.parse::<u8>() {
+        return Ok(T::from(n));
+    }
+    (0..=u8::MAX)
+        .map(T::from)
+        .find(|t| name(t).map_or(false, |n| n.eq_ignore_ascii_case(s)))
+        .ok_or_else(|| ParseNameError {
+            kind,
+            name: s.to_owned(),
+        })
+}
+
+/// Client state a DHCPREQUEST was sent from, see
+/// [`Message::dhcp_request_state`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RequestState {
+    /// accepting an offer, with the Server Identifier & Requested IP set
+    Selecting,
+    /// verifying a previous lease after a reboot, with the Requested IP set
+    InitReboot,
+    /// extending the lease with the server that granted it, unicast with
+    /// `ciaddr` set
+    Renewing,
+    /// extending the lease with any server, broadcast with `ciaddr` set
+    Rebinding,
+}
+
+/// How to interpret the options following the magic cookie, see
+/// [`Message::decode_as`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OptionsMode {
+    /// DHCP options (RFC 2132). The message must have the magic cookie,
+    /// otherwise [`DecodeError::MagicCookieNotFound`] is returned, & the
+    /// Message Type option (53), otherwise [`DecodeError::MissingMessageType`]
+    /// is returned
+    Dhcp,
+    /// BOOTP vendor extensions (RFC 1497). The cookie is optional, the DHCP
+    /// extensions (options 50 to 61, e.g. Message Type) have no meaning in
+    /// BOOTP & are kept as [`DhcpOption::Unknown`], under
+    /// [`OptionCode::Unknown`]
+    Bootp,
+}
+
+impl OptionsMode {
+    /// codes of the DHCP extensions, RFC 2132 section 9
+    const DHCP_EXTENSIONS: std::ops::RangeInclusive<u8> = 50..=61;
+
+    /// whether options with `code` are decoded as their [`DhcpOption`] type
+    pub(crate) fn is_typed(self, code: u8) -> bool {
+        self == OptionsMode::Dhcp || !Self::DHCP_EXTENSIONS.contains(&code)
+    }
+}
+
+/// The result of [`Message::decode_partial`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialMessage {
+    message: Option<Message>,
+    valid_len: usize,
+}
+
+impl PartialMessage {
+    /// the message decoded so far, `None` if the header couldn't be decoded
+    pub fn message(&self) -> Option<&Message> {
+        self.message.as_ref()
+    }
+    /// consume the partial, returning the message decoded so far
+    pub fn into_message(self) -> Option<Message> {
+        self.message
+    }
+    /// number of bytes at the start of the buffer that decoded successfully,
+    /// i.e. the offset of the option that failed
+    pub fn valid_len(&self) -> usize {
+        self.valid_len
+    }
+}
+
+/// 64-bit FNV-1a hasher, used for [`Message::content_digest`]
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Canonical key identifying a transaction, see [`Message::transaction_key`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TransactionKey {
+    xid: u32,
+    htype: HType,
+    hlen: u8,
+    chaddr: [u8; 16],
+}
+
+impl TransactionKey {
+    /// the transaction id
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+    /// the hardware type
+    pub fn htype(&self) -> HType {
+        self.htype
+    }
+    /// the client hardware address, `hlen` bytes long
+    pub fn chaddr(&self) -> &[u8] {
+        &self.chaddr[..self.hlen as usize]
+    }
+}
+
+impl Decodable for Message {
+    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("decode", len = decoder.buffer().len()).entered();
+        let mut msg = Self::decode_header(decoder)?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(xid = msg.xid, "decoded header");
+        msg.opts = DhcpOptions::decode(decoder)?;
+        Ok(msg)
+    }
+}
+
+impl Encodable for Message {
+    fn encode(&self, e: &mut Encoder<'_>) -> EncodeResult<()> {
+        self.encode_ordered(&[], e)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Message")
+            .field("xid", &self.xid())
+            .field("broadcast_flag", &self.flags().broadcast())
+            .field("ciaddr", &self.ciaddr())
+            .field("yiaddr", &self.yiaddr())
+            .field("siaddr", &self.siaddr())
+            .field("giaddr", &self.giaddr())
+            .field(
+                "chaddr",
+                &hex::encode(self.chaddr())
+                    .chars()
+                    .enumerate()
+                    .flat_map(|(i, c)| {
+                        if i != 0 && i % 2 == 0 {
+                            Some(':')
+                        } else {
+                            None
+                        }
+                        .into_iter()
+                        .chain(std::iter::once(c))
+                    })
+                    .collect::<String>(),
+            )
+            .field(
+                "opts",
+                &self.opts().iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Message {
+    /// Decode `bytes`, re-encode the result & assert that the output matches
+    /// the input, returning the decoded message. Requires the `test-util`
+    /// feature.
+    ///
+    /// The header must match byte for byte, and each option must re-encode to
+    /// exactly its original bytes. Options are not required to appear in their
+    /// original order, since [`DhcpOptions`] does not preserve it. With
+    /// `ignore_pad` set, `Pad` options & trailing bytes after `End` in the
+    /// input are allowed, otherwise the input & output lengths must match too.
+    ///
+    /// # Panics
+    ///
+    /// if decoding or encoding fails, or the output diverges from `bytes`
+    pub fn assert_roundtrip(bytes: &[u8], ignore_pad: bool) -> Message {
+        const HEADER_LEN: usize = 240;
+
+        let (msg, raw) = Message::decode_with_raw_opts(&mut Decoder::new(bytes))
+            .expect("failed to decode message");
+        let out = msg.to_vec().expect("failed to encode message");
+        assert_eq!(
+            &bytes[..HEADER_LEN],
+            &out[..HEADER_LEN],
+            "message header differs after roundtrip"
+        );
+        let (_, out_raw) = DhcpOptions::decode_with_raw(&mut Decoder::new(&out[HEADER_LEN..]))
+            .expect("failed to decode encoded options");
+        // compare the spans option by option, `RawOptions` equality also
+        // covers the whole options region, which differs when the input has
+        // padding or options out of code order
+        assert_eq!(
+            raw.iter().collect::<Vec<_>>(),
+            out_raw.iter().collect::<Vec<_>>(),
+            "options differ after roundtrip"
+        );
+        if !ignore_pad {
+            assert_eq!(
+                bytes.len(),
+                out.len(),
+                "message length differs after roundtrip"
+            );
+        }
+        msg
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Message {
+    /// Export the message as a stable, human readable JSON value, intended for
+    /// logging & diffing captures. Options are listed in order of their code as
+    /// `{ "code": 1, "name": "Subnet Mask", "value": "255.255.255.0" }`, unknown
+    /// options have their data encoded as a hex string.
+    pub fn to_json_value(&self) -> serde_json::Result<serde_json::Value> {
+        use serde_json::{Map, Value};
+
+        let mut opts = self.opts().iter().collect::<Vec<_>>();
+        opts.sort_unstable_by_key(|(code, _)| u8::from(**code));
+        let opts = opts
+            .into_iter()
+            .map(|(code, opt)| {
+                let value = match opt {
+                    DhcpOption::Unknown(unknown) => Value::String(hex::encode(unknown.data())),
+                    _ => match serde_json::to_value(opt)? {
+                        // newtype variants serialize as `{ "Variant": value }`
+                        Value::Object(map) if map.len() == 1 => {
+                            map.into_iter().next().map(|(_, v)| v).unwrap_or_default()
+                        }
+                        // unit variants carry no value
+                        _ => Value::Null,
+                    },
+                };
+                let mut entry = Map::new();
+                entry.insert("code".to_owned(), u8::from(*code).into());
+                entry.insert("name".to_owned(), code.name().into());
+                entry.insert("value".to_owned(), value);
+                Ok(Value::Object(entry))
+            })
+            .collect::<serde_json::Result<Vec<_>>>()?;
+
+        let lossy = |name: Option<Cow<'_, str>>| match name {
+            Some(name) => Value::String(name.into_owned()),
+            None => Value::Null,
+        };
+        let mut msg = Map::new();
+        msg.insert("opcode".to_owned(), serde_json::to_value(self.opcode)?);
+        msg.insert("htype".to_owned(), serde_json::to_value(self.htype)?);
+        msg.insert("hlen".to_owned(), self.hlen.into());
+        msg.insert("hops".to_owned(), self.hops.into());
+        msg.insert("xid".to_owned(), self.xid.into());
+        msg.insert("secs".to_owned(), self.secs.into());
+        msg.insert("flags".to_owned(), u16::from(self.flags).into());
+        msg.insert("ciaddr".to_owned(), self.ciaddr.to_string().into());
+        msg.insert("yiaddr".to_owned(), self.yiaddr.to_string().into());
+        msg.insert("siaddr".to_owned(), self.siaddr.to_string().into());
+        msg.insert("giaddr".to_owned(), self.giaddr.to_string().into());
+        msg.insert("chaddr".to_owned(), hex::encode(self.chaddr()).into());
+        msg.insert("sname".to_owned(), lossy(self.sname_lossy()));
+        msg.insert("fname".to_owned(), lossy(self.fname_lossy()));
+        msg.insert("magic".to_owned(), hex::encode(self.magic).into());
+        msg.insert("options".to_owned(), Value::Array(opts));
+        Ok(Value::Object(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use test_fixtures::{bootreq, discover, offer, other_offer};
+
+    type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn decode_ipv4(input: Vec<u8>, expected: MessageType) -> Result<()> {
+        let msg = Message::assert_roundtrip(&input, true);
+        dbg!(&msg);
+        assert_eq!(msg.opts().msg_type().unwrap(), expected);
+        // decode again
+        let out = msg.to_vec()?;
+        let res = Message::decode(&mut Decoder::new(&out))?;
+        // check Messages are equal after decoding/encoding
+        assert_eq!(msg, res);
+        // the canonical form: no padding, a single End & a fixed point
+        let mut tlvs = &out[240..];
+        while let [code, rest @ ..] = tlvs {
+            assert_ne!(*code, u8::from(OptionCode::Pad));
+            if *code == u8::from(OptionCode::End) {
+                assert!(rest.is_empty(), "bytes after End");
+                break;
+            }
+            tlvs = &rest[1 + rest[0] as usize..];
+        }
+        assert_eq!(out.last(), Some(&u8::from(OptionCode::End)));
+        assert_eq!(out.len(), msg.encoded_len());
+        assert_eq!(res.to_vec()?, out);
+        Ok(())
+    }
+    #[test]
+    fn decode_offer() -> Result<()> {
+        decode_ipv4(offer(), MessageType::Offer)?;
+        Ok(())
+    }
+
+    #[test]
+    fn decode_discover() -> Result<()> {
+        decode_ipv4(discover(), MessageType::Discover)?;
+        Ok(())
+    }
+
+    #[test]
+    fn decode_offer_two() -> Result<()> {
+        decode_ipv4(other_offer(), MessageType::Offer)?;
+        Ok(())
+    }
+
+    #[test]
+    fn decode_bootreq() -> Result<()> {
+        let msg = Message::assert_roundtrip(&bootreq(), false);
+        println!("{:?}", msg);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_lossless() -> Result<()> {
+        let mut input = discover();
+        // garbage after the NUL terminators of sname & file
+        input[44 + 10] = 0x11;
+        input[108 + 100] = 0x22;
+        let lossy = Message::decode(&mut Decoder::new(&input))?;
+        assert_ne!(lossy.to_vec()?[..240], input[..240]);
+
+        let mut msg = Message::decode_lossless(&mut Decoder::new(&input))?;
+        assert_eq!(msg.sname(), lossy.sname());
+        assert_eq!(msg.fname(), lossy.fname());
+        assert_eq!(msg.to_vec()?[..240], input[..240]);
+        // the verbatim regions don't affect equality or hashing
+        assert_eq!(msg, lossy);
+        let hash = |msg: &Message| {
+            use std::hash::{Hash, Hasher};
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            msg.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&msg), hash(&lossy));
+
+        // setting a field drops the verbatim copy
+        msg.set_sname_str("server").set_fname_str("boot");
+        let out = msg.to_vec()?;
+        assert_eq!(out[44..52], *b"server\0\0");
+        assert!(out[44..236].iter().all(|b| ![0x11, 0x22].contains(b)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_chaddr() -> Result<()> {
+        let mut msg = Message::new(
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        );
+        msg.set_chaddr(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(msg.chaddr().len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_with_raw_opts() -> Result<()> {
+        let input = offer();
+        let (msg, raw) = Message::decode_with_raw_opts(&mut Decoder::new(&input))?;
+        assert_eq!(msg, Message::decode(&mut Decoder::new(&input))?);
+        assert_eq!(raw.len(), msg.opts().len());
+        // each span re-decodes to the same option
+        for (code, bytes) in raw.iter() {
+            assert_eq!(bytes[0], u8::from(*code));
+            let opt = DhcpOption::decode(&mut Decoder::new(bytes))?;
+            assert_eq!(msg.opts().get(*code), Some(&opt));
+        }
+        let opts = raw.options_bytes();
+        assert_eq!(opts, &input[240..240 + opts.len()]);
+        assert_eq!(opts.last(), Some(&255));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transaction_key() -> Result<()> {
+        let mut a = Message::default();
+        a.set_xid(7).set_chaddr(&[1, 2, 3, 4, 5, 6]);
+        let mut b = a.clone();
+        // junk past hlen is ignored
+        b.chaddr[6..].copy_from_slice(&[0xff; 10]);
+        assert_eq!(a.transaction_key(), b.transaction_key());
+        assert_eq!(b.transaction_key().chaddr(), &[1, 2, 3, 4, 5, 6]);
+        // hlen larger than chaddr is clamped
+        b.hlen = 200;
+        assert_eq!(b.transaction_key().chaddr().len(), 16);
+        // different xid or htype are different transactions
+        b = a.clone();
+        b.set_xid(8);
+        assert_ne!(a.transaction_key(), b.transaction_key());
+        b = a.clone();
+        b.set_htype(HType::ExperimentalEth);
+        assert_ne!(a.transaction_key(), b.transaction_key());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_mac() -> Result<()> {
+        let mut msg = Message::default();
+        msg.set_chaddr(&[1, 2, 3, 4, 5, 6]);
+        // bad hlen claims more bytes than chaddr holds
+        msg.hlen = 20;
+        assert_eq!(msg.chaddr().len(), 16);
+        assert_eq!(msg.client_mac(), &[1, 2, 3, 4, 5, 6]);
+        // other hardware types are only clamped to 16
+        msg.set_htype(HType::ExperimentalEth);
+        assert_eq!(msg.client_mac().len(), 16);
+        // short hlen is respected
+        msg.hlen = 4;
+        msg.set_htype(HType::Eth);
+        assert_eq!(msg.client_mac(), &[1, 2, 3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_chaddr_from_mac() -> Result<()> {
+        let mut msg = Message::new(
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            &[0xff; 16],
+        );
+        msg.set_htype(HType::ExperimentalEth)
+            .set_chaddr_from_mac([1, 2, 3, 4, 5, 6]);
+        assert_eq!(msg.htype(), HType::Eth);
+        assert_eq!(msg.hlen, 6);
+        assert_eq!(msg.chaddr, [1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_client_hw() {
+        let mut a = Message::default();
+        a.set_chaddr(&[1, 2, 3, 4, 5, 6]);
+        let mut b = a.clone();
+        // differs only in the padding beyond hlen
+        b.chaddr[6..].copy_from_slice(&[0xff; 10]);
+        assert!(a.same_client_hw(&b));
+        b.set_htype(HType::ExperimentalEth);
+        assert!(!a.same_client_hw(&b));
+        b.set_htype(HType::Eth).hlen = 5;
+        assert!(!a.same_client_hw(&b));
+    }
+
+    #[test]
+    fn test_content_digest() -> Result<()> {
+        // known FNV-1a test vectors
+        let mut h = Fnv1a::new();
+        assert_eq!(h.finish(), 0xcbf29ce484222325);
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63dc4c8601ec8c);
+
+        let msg = Message::decode(&mut Decoder::new(&discover()))?;
+        let mut other = msg.clone();
+        other.set_secs(msg.secs() + 1).set_hops(msg.hops() + 1);
+        other.chaddr[15] ^= 0xff;
+        assert_eq!(msg.content_digest(), other.content_digest());
+        other.set_xid(msg.xid() + 1);
+        assert_ne!(msg.content_digest(), other.content_digest());
+
+        // bytes moving between sname & fname change the digest
+        let mut a = Message::default();
+        a.set_xid(1).set_sname(b"ab").set_fname(b"c");
+        let mut b = a.clone();
+        b.set_sname(b"a").set_fname(b"bc");
+        assert_ne!(a.content_digest(), b.content_digest());
+        Ok(())
+    }
+
+    #[test]
+    fn test_names_roundtrip() {
+        for n in 0..=u8::MAX {
+            let ty = MessageType::from(n);
+            assert_eq!(ty.to_string().parse(), Ok(ty));
+            let op = Opcode::from(n);
+            assert_eq!(op.to_string().parse(), Ok(op));
+            let htype = HType::from(n);
+            assert_eq!(htype.to_string().parse(), Ok(htype));
+        }
+        assert_eq!(" dhcpdiscover ".parse(), Ok(MessageType::Discover));
+        assert_eq!("unknown(7)".parse(), Ok(MessageType::Release));
+        let err = "UNKNOWN(300)".parse::<Opcode>().unwrap_err();
+        assert_eq!(err.kind, "opcode");
+        assert_eq!(err.name, "UNKNOWN(300)");
+        assert!("DHCP".parse::<MessageType>().is_err());
+        assert!("".parse::<HType>().is_err());
+    }
+
+    #[test]
+    fn test_relay_forward() {
+        let mut msg = Message::default();
+        msg.set_giaddr([192, 168, 1, 1]);
+        msg.relay_forward([10, 0, 0, 1].into(), 1).unwrap();
+        assert_eq!(msg.giaddr(), Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(msg.hops(), 1);
+        let before = msg.clone();
+        assert!(msg.relay_forward([10, 0, 0, 1].into(), 1).is_err());
+        assert_eq!(msg, before);
+
+        let mut msg = Message::default();
+        msg.set_hops(u8::MAX);
+        assert_eq!(
+            msg.relay_forward([10, 0, 0, 1].into(), u8::MAX),
+            Err(RelayError::HopsExceeded {
+                hops: u8::MAX,
+                max: u8::MAX
+            })
+        );
+        assert!(msg.giaddr().is_unspecified());
+    }
+
+    #[test]
+    fn test_strip_unknown_options() -> Result<()> {
+        let mut buf = Message::decode(&mut Decoder::new(&discover()))?.to_vec()?;
+        let end = buf.len() - 1;
+        buf.splice(end..end, [224, 2, 0xaa, 0xbb, 250, 0]);
+        let mut msg = Message::decode(&mut Decoder::new(&buf))?;
+        let len = msg.opts().len();
+        msg.strip_unknown_options();
+        assert_eq!(msg.opts().len(), len - 2);
+        assert!(msg.opts().iter().all(|(code, _)| code.is_known()));
+        assert_eq!(
+            msg.to_vec()?,
+            Message::decode(&mut Decoder::new(&discover()))?.to_vec()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_ordered() -> Result<()> {
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let mut buf = Vec::new();
+        msg.encode_ordered(&[OptionCode::MessageType], &mut Encoder::new(&mut buf))?;
+        assert_eq!(&buf[240..243], &[53, 1, 2]);
+        // same message, only option order differs
+        assert_eq!(buf.len(), msg.to_vec()?.len());
+        assert_eq!(Message::decode(&mut Decoder::new(&buf))?, msg);
+        Ok(())
+    }
+
+    #[test]
+    fn test_preferred_name() -> Result<()> {
+        let mut msg = Message::default();
+        assert_eq!(msg.preferred_name(), None);
+        msg.opts_mut()
+            .insert(DhcpOption::Hostname("host".to_owned()));
+        // empty fqdn falls back to hostname
+        msg.opts_mut()
+            .insert(DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(
+                fqdn::FqdnFlags::default(),
+                crate::Domain::from(trust_dns_proto::rr::Name::new()),
+            )));
+        assert_eq!(
+            msg.preferred_name(),
+            Some(fqdn::ClientName::Hostname("host"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash() -> Result<()> {
+        use std::collections::{hash_map::DefaultHasher, HashSet};
+        use std::hash::{Hash, Hasher};
+
+        fn hash<T: Hash>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        // same options inserted in a different order
+        let mut opts = msg
+            .opts()
+            .iter()
+            .map(|(_, v)| v.clone())
+            .collect::<Vec<_>>();
+        opts.reverse();
+        let mut other = msg.clone();
+        other.set_opts(opts.into_iter().collect());
+        assert_eq!(msg, other);
+        assert_eq!(hash(&msg), hash(&other));
+
+        let mut set = HashSet::new();
+        set.insert(msg);
+        assert!(!set.insert(other.clone()));
+        other.set_xid(1);
+        assert!(set.insert(other));
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_relay_info() -> Result<()> {
+        use relay::{RelayAgentInformation, RelayCode, RelayInfo};
+
+        let mut msg = Message::decode(&mut Decoder::new(&offer()))?;
+        assert!(msg.push_relay_info(vec![1], None)?.is_none());
+        // existing sub-options are kept
+        let mut info = RelayAgentInformation::default();
+        info.insert(RelayInfo::LinkSelection([10, 0, 0, 1].into()));
+        msg.opts_mut()
+            .insert(DhcpOption::RelayAgentInformation(info));
+        msg.push_relay_info(vec![1, 2], Some(vec![3, 4, 5]))?
+            .unwrap();
+        // including a downstream relay's ids
+        let prev = msg.push_relay_info(vec![9], Some(vec![9]))?.unwrap();
+        assert_eq!(
+            msg.opts().get(OptionCode::RelayAgentInformation),
+            Some(&DhcpOption::RelayAgentInformation(prev))
+        );
+
+        // invalid ids are errors & leave the message unchanged
+        let before = msg.clone();
+        assert!(matches!(
+            msg.push_relay_info(vec![], None),
+            Err(EncodeError::EmptyOptionValue { code: 1 })
+        ));
+        assert!(matches!(
+            msg.push_relay_info(vec![1], Some(vec![0; 256])),
+            Err(EncodeError::OptionValueTooLong { code: 2, len: 256 })
+        ));
+        assert_eq!(msg, before);
+        let mut stripped = before;
+        assert!(stripped.strip_relay_info().is_some());
+        assert!(stripped.strip_relay_info().is_none());
+
+        let mut msg = Message::decode(&mut Decoder::new(&msg.to_vec()?))?;
+        let info = msg.pop_relay_info().unwrap();
+        assert_eq!(
+            info.get(RelayCode::LinkSelection),
+            Some(&RelayInfo::LinkSelection([10, 0, 0, 1].into()))
+        );
+        assert_eq!(
+            info.get(RelayCode::AgentCircuitId),
+            Some(&RelayInfo::AgentCircuitId(vec![1, 2]))
+        );
+        assert_eq!(
+            info.get(RelayCode::AgentRemoteId),
+            Some(&RelayInfo::AgentRemoteId(vec![3, 4, 5]))
+        );
+        assert!(msg.opts().get(OptionCode::RelayAgentInformation).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_renew() -> Result<()> {
+        let server = Ipv4Addr::new(10, 0, 0, 1);
+        let msg = Message::force_renew(server, [10, 0, 0, 50].into(), &[1, 2, 3, 4, 5, 6]);
+        let msg = Message::decode(&mut Decoder::new(&msg.to_vec()?))?;
+        assert_eq!(msg.opcode(), Opcode::BootReply);
+        assert_eq!(msg.chaddr(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(msg.opts().msg_type(), Some(MessageType::ForceRenew));
+        assert_eq!(
+            msg.opts().get(OptionCode::ServerIdentifier),
+            Some(&DhcpOption::ServerIdentifier(server))
+        );
+        let msg = Message::force_renew_with_id(42, server, [10, 0, 0, 50].into(), &[1; 6]);
+        assert_eq!(msg.xid(), 42);
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_msg_type() {
+        for (name, buf, msg) in test_fixtures::all() {
+            assert_eq!(
+                Message::peek_msg_type(&buf),
+                msg.opts().msg_type(),
+                "{}",
+                name
+            );
+        }
+        let mut buf = discover();
+        buf.truncate(240);
+        // pad, then an option before 53
+        buf.extend([0, 0, 12, 2, b'h', b'i', 53, 1, 3, 255]);
+        assert_eq!(Message::peek_msg_type(&buf), Some(MessageType::Request));
+        // truncated value
+        assert_eq!(Message::peek_msg_type(&buf[..248]), None);
+        // after End
+        buf[246..].copy_from_slice(&[255, 53, 1, 3]);
+        assert_eq!(Message::peek_msg_type(&buf), None);
+    }
+
+    #[test]
+    fn test_leasequery_replies() -> Result<()> {
+        let server = Ipv4Addr::new(10, 0, 0, 1);
+        let mut query = Message::new_with_id(
+            7,
+            [10, 0, 0, 50].into(),
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            [10, 0, 0, 2].into(),
+            &[9; 6],
+        );
+        query.set_flags(Flags::default().set_broadcast());
+        query
+            .opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::LeaseQuery));
+
+        let active = Message::lease_active(
+            &query,
+            server,
+            [10, 0, 0, 50].into(),
+            HType::Eth,
+            &[1; 6],
+            5,
+        );
+        let active = Message::from_bytes(&active.to_vec()?)?;
+        assert_eq!(active.opts().msg_type(), Some(MessageType::LeaseActive));
+        assert_eq!(active.ciaddr(), Ipv4Addr::new(10, 0, 0, 50));
+        assert_eq!(active.chaddr(), &[1; 6]);
+        assert!(!active.has_yiaddr());
+        assert_eq!(active.opts().client_last_transaction_time(), Some(5));
+
+        let unassigned = Message::lease_unassigned(&query, server);
+        assert_eq!(
+            unassigned.opts().msg_type(),
+            Some(MessageType::LeaseUnassigned)
+        );
+        assert_eq!(unassigned.ciaddr(), Ipv4Addr::new(10, 0, 0, 50));
+
+        let unknown = Message::lease_unknown(&query, server);
+        assert_eq!(unknown.opts().msg_type(), Some(MessageType::LeaseUnknown));
+        assert!(!unknown.has_ciaddr());
+        for reply in [&active, &unassigned, &unknown] {
+            assert_eq!(reply.opcode(), Opcode::BootReply);
+            assert_eq!(reply.xid(), 7);
+            assert_eq!(reply.giaddr(), Ipv4Addr::new(10, 0, 0, 2));
+            assert!(reply.flags().broadcast());
+            assert_eq!(
+                reply.opts().get(OptionCode::ServerIdentifier),
+                Some(&DhcpOption::ServerIdentifier(server))
+            );
+        }
+        assert_eq!(unknown.chaddr(), &[9; 6]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let ip = Ipv4Addr::UNSPECIFIED;
+        let mut rng = StdRng::seed_from_u64(7);
+        let a = Message::new_with_rng(&mut rng, ip, ip, ip, ip, &[1; 6]);
+        let b = Message::new_with_rng(&mut StdRng::seed_from_u64(7), ip, ip, ip, ip, &[1; 6]);
+        assert_eq!(a, b);
+        let c = Message::new_with_rng(&mut rng, ip, ip, ip, ip, &[1; 6]);
+        assert_ne!(a.xid(), c.xid());
+    }
+
+    #[test]
+    fn test_encoded_len() -> Result<()> {
+        for input in [bootreq(), discover(), offer()] {
+            let msg = Message::decode(&mut Decoder::new(&input))?;
+            assert_eq!(msg.encoded_len(), msg.to_vec()?.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_all() -> Result<()> {
+        let (offer, disc) = (offer(), discover());
+        let stream = [&offer[..], &disc[..]].concat();
+        let msgs = Message::decode_all(&stream, &[offer.len(), disc.len()])
+            .collect::<DecodeResult<Vec<_>>>()?;
+        assert_eq!(msgs[0], Message::decode(&mut Decoder::new(&offer))?);
+        assert_eq!(msgs[1], Message::decode(&mut Decoder::new(&disc))?);
+
+        // lengths past the end of the buffer error, & so does everything after
+        let res = Message::decode_all(&stream, &[offer.len(), stream.len(), 1, usize::MAX])
+            .map(|msg| msg.is_ok())
+            .collect::<Vec<_>>();
+        assert_eq!(res, [true, false, false, false]);
+        let res = Message::decode_all(&stream, &[stream.len() + 1, offer.len()])
+            .map(|msg| matches!(msg, Err(DecodeError::NotEnoughBytes)))
+            .collect::<Vec<_>>();
+        assert_eq!(res, [true, true]);
+        assert_eq!(Message::decode_all(&stream, &[]).count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_strict() -> Result<()> {
+        let mut buf = Message::decode(&mut Decoder::new(&discover()))?.to_vec()?;
+        assert!(Message::decode_strict(&mut Decoder::new(&buf)).is_ok());
+        // append a second message type before the end option
+        let end = buf.len() - 1;
+        buf.splice(end..end, [53, 1, 3]);
+        let msg = Message::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(msg.opts().msg_type(), Some(MessageType::Discover));
+        assert!(matches!(
+            Message::decode_strict(&mut Decoder::new(&buf)),
+            Err(DecodeError::DuplicateOption {
+                code: OptionCode::MessageType
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_as() -> Result<()> {
+        let input = discover();
+        let dhcp = Message::decode_as(&mut Decoder::new(&input), OptionsMode::Dhcp)?;
+        assert_eq!(dhcp, Message::decode(&mut Decoder::new(&input))?);
+        let bootp = Message::decode_as(&mut Decoder::new(&input), OptionsMode::Bootp)?;
+        assert_eq!(bootp.opts().msg_type(), None);
+        assert!(bootp.opts().get(OptionCode::Unknown(53)).is_some());
+        assert_eq!(bootp.opts().len(), dhcp.opts().len());
+        // the DHCP extensions re-encode unchanged
+        let reencoded = Message::decode(&mut Decoder::new(&bootp.to_vec()?))?;
+        assert_eq!(reencoded.opts().msg_type(), dhcp.opts().msg_type());
+
+        // a vendor extension that isn't a valid lease time doesn't stop decoding
+        let mut buf = input[..240].to_vec();
+        buf.extend([51, 2, 1, 2, 12, 2, b'h', b'i', 255]);
+        let bootp = Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Bootp)?;
+        assert!(matches!(
+            bootp.opts().get(OptionCode::Unknown(51)),
+            Some(DhcpOption::Unknown(opt)) if opt.data() == [1, 2]
+        ));
+        assert_eq!(
+            bootp.opts().get(OptionCode::Hostname),
+            Some(&DhcpOption::Hostname("hi".into()))
+        );
+        // wire bytes are kept, in ascending order of code
+        assert_eq!(
+            bootp.to_vec()?[240..],
+            [12, 2, b'h', b'i', 51, 2, 1, 2, 255]
+        );
+
+        let mut msg = Message::default();
+        msg.set_magic_cookie(false);
+        let buf = msg.to_vec()?;
+        assert!(
+            Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Bootp)?
+                .opts()
+                .is_empty()
+        );
+        let err = Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Dhcp).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            DecodeError::MagicCookieNotFound { window: 0 }
+        ));
+        assert_eq!(err.offset(), Some(236));
+        // cookie, but no message type
+        let buf = Message::default().to_vec()?;
+        assert!(matches!(
+            Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Dhcp),
+            Err(DecodeError::MissingMessageType)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_cookie() -> Result<()> {
+        let input = discover();
+        let expected = Message::decode(&mut Decoder::new(&input))?;
+        let mut buf = input.clone();
+        buf.splice(240..240, MAGIC.repeat(2));
+        assert_eq!(Message::decode(&mut Decoder::new(&buf))?, expected);
+        assert_eq!(
+            Message::decode_scan_cookie(&mut Decoder::new(&buf), 0)?,
+            expected
+        );
+        assert_eq!(Message::decode_bootp(&mut Decoder::new(&buf))?, expected);
+        let bootp = Message::decode_as(&mut Decoder::new(&buf), OptionsMode::Bootp)?;
+        assert_eq!(bootp.opts().len(), expected.opts().len());
+        // re-encodes with a single cookie
+        assert_eq!(
+            Message::decode(&mut Decoder::new(&buf))?.to_vec()?,
+            expected.to_vec()?
+        );
+        assert!(matches!(
+            Message::decode_strict(&mut Decoder::new(&buf)),
+            Err(DecodeError::DuplicateMagicCookie { offset: 240 })
+        ));
+
+        // misplaced cookie
+        let mut buf = input;
+        buf.splice(236..236, [0; 2]);
+        let err = Message::decode_strict(&mut Decoder::new(&buf)).unwrap_err();
+        assert!(matches!(
+            err,
+            DecodeError::InField {
+                field: "magic cookie",
+                offset: 236,
+                ..
+            }
+        ));
+        assert!(matches!(
+            err.kind(),
+            DecodeError::MagicCookieNotFound { window: 0 }
+        ));
+        assert_eq!(
+            Message::decode_scan_cookie(&mut Decoder::new(&buf), 2)?,
+            expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_scan_cookie() -> Result<()> {
+        let input = discover();
+        let expected = Message::decode(&mut Decoder::new(&input))?;
+        assert_eq!(
+            Message::decode_scan_cookie(&mut Decoder::new(&input), 0)?,
+            expected
+        );
+        let mut buf = input.clone();
+        buf.splice(236..236, [0; 3]);
+        let msg = Message::decode_scan_cookie(&mut Decoder::new(&buf), 3)?;
+        assert_eq!(msg, expected);
+        assert!(matches!(
+            Message::decode_scan_cookie(&mut Decoder::new(&buf), 2),
+            Err(DecodeError::MagicCookieNotFound { window: 2 })
+        ));
+        // window larger than the buffer
+        assert!(Message::decode_scan_cookie(&mut Decoder::new(&input[..238]), 64).is_err());
+        // unbounded
+        assert_eq!(
+            Message::decode_scan_cookie(&mut Decoder::new(&buf), usize::MAX)?,
+            expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_partial() -> Result<()> {
+        let input = discover();
+        let (partial, err) = Message::decode_partial(&input);
+        assert!(err.is_none());
+        assert_eq!(
+            partial.into_message(),
+            Some(Message::decode(&mut Decoder::new(&input))?)
+        );
+
+        // header truncated
+        let (partial, err) = Message::decode_partial(&input[..100]);
+        let err = err.unwrap();
+        assert!(matches!(
+            err,
+            DecodeError::InField {
+                field: "sname",
+                offset: 44,
+                ..
+            }
+        ));
+        assert!(matches!(err.kind(), DecodeError::NotEnoughBytes));
+        assert_eq!(partial.message(), None);
+        assert_eq!(partial.valid_len(), 0);
+
+        // first option truncated
+        let (partial, err) = Message::decode_partial(&input[..242]);
+        assert!(matches!(
+            err,
+            Some(DecodeError::InOption {
+                code: 53,
+                offset: 240,
+                ..
+            })
+        ));
+        assert_eq!(err.and_then(|e| e.offset()), Some(240));
+        assert!(partial.message().unwrap().opts().is_empty());
+        assert_eq!(partial.valid_len(), 240);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bootp() -> Result<()> {
+        let mut input = bootreq();
+        // no cookie, BOOTP vendor area
+        input.truncate(236);
+        assert!(Message::decode(&mut Decoder::new(&input)).is_err());
+        let msg = Message::decode_bootp(&mut Decoder::new(&input))?;
+        assert!(!msg.has_magic_cookie());
+        let out = msg.to_vec()?;
+        assert_eq!(out.len(), 300);
+        assert_eq!(msg.encoded_len(), 300);
+        assert_eq!(out[..236], input[..]);
+        assert!(out[236..].iter().all(|b| *b == 0));
+
+        // vendor area is kept
+        let mut input = bootreq();
+        input.truncate(236);
+        input.extend((0..BOOTP_VEND_LEN as u8).rev());
+        let msg = Message::decode_bootp(&mut Decoder::new(&input))?;
+        assert_eq!(msg.vendor_area(), Some(&input[236..]));
+        assert_eq!(msg.to_vec()?, input);
+
+        // cookie present, decoded as DHCP
+        let msg = Message::decode_bootp(&mut Decoder::new(&offer()))?;
+        assert_eq!(msg, Message::decode(&mut Decoder::new(&offer()))?);
+        assert_eq!(msg.vendor_area(), None);
+
+        // a foreign cookie keeps its options on a round trip
+        let mut input = Message::decode(&mut Decoder::new(&offer()))?.to_vec()?;
+        input[236..240].copy_from_slice(&[1, 2, 3, 4]);
+        let msg = Message::decode(&mut Decoder::new(&input))?;
+        assert!(!msg.has_magic_cookie());
+        assert!(!msg.opts().is_empty());
+        assert_eq!(msg.to_vec()?, input);
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_options() -> Result<()> {
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let len = msg.opts().len();
+        let msg = msg.with_options([
+            DhcpOption::MessageType(MessageType::Ack),
+            DhcpOption::InterfaceMtu(1400),
+        ]);
+        assert_eq!(msg.opts().msg_type(), Some(MessageType::Ack));
+        assert_eq!(msg.opts().len(), len + 1);
+
+        let mut opts = DhcpOptions::new();
+        opts.extend(msg.opts().clone());
+        assert_eq!(&opts, msg.opts());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> Result<()> {
+        // BOOTP, no options
+        assert!(Message::decode(&mut Decoder::new(&bootreq()))?
+            .validate()
+            .is_ok());
+        let mut msg = Message::decode(&mut Decoder::new(&discover()))?;
+        assert!(msg.validate().is_ok());
+        msg.opts_mut().remove(OptionCode::MessageType);
+        assert_eq!(msg.validate(), Err(ValidationError::MissingMessageType));
+        // without the cookie the options are vendor extensions, not DHCP
+        msg.magic = [0; 4];
+        assert!(msg.validate().is_ok());
+
+        let mut msg = Message::decode(&mut Decoder::new(&discover()))?;
+        assert!(msg.opcode_matches_msg_type());
+        msg.set_opcode(Opcode::BootReply);
+        assert!(!msg.opcode_matches_msg_type());
+        assert_eq!(
+            msg.validate(),
+            Err(ValidationError::OpcodeMismatch {
+                opcode: Opcode::BootReply,
+                msg_type: MessageType::Discover
+            })
+        );
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        assert!(msg.opcode_matches_msg_type());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_identity_string() -> Result<()> {
+        let mut msg = Message::decode(&mut Decoder::new(&discover()))?;
+        msg.opts_mut().remove(OptionCode::ClientIdentifier);
+        assert_eq!(msg.client_identity_string(), colon_hex(msg.client_mac()));
+        // an empty client id falls back to chaddr
+        msg.opts_mut().insert(DhcpOption::ClientIdentifier(vec![]));
+        assert_eq!(msg.client_identity_string(), colon_hex(msg.client_mac()));
+        msg.opts_mut()
+            .insert(DhcpOption::ClientIdentifier(b"a b\n".to_vec()));
+        assert_eq!(msg.client_identity_string(), "61:20:62:0a");
+        assert_eq!(Message::default().client_identity_string(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_anonymize() -> Result<()> {
+        let mut msg = Message::decode(&mut Decoder::new(&discover()))?;
+        let chaddr = msg.chaddr().to_vec();
+        msg.opts_mut()
+            .insert(DhcpOption::ClientIdentifier(vec![1, 2, 3]));
+        msg.opts_mut()
+            .insert(DhcpOption::RequestedIpAddress([192, 168, 0, 5].into()));
+        let len = msg.opts().len();
+        msg.anonymize(false);
+        assert_eq!(msg.chaddr().len(), chaddr.len());
+        assert!(msg.chaddr().iter().all(|b| *b == 0));
+        assert!(msg.opts().get(OptionCode::ClientIdentifier).is_none());
+        assert_eq!(
+            msg.opts().get(OptionCode::RequestedIpAddress),
+            Some(&DhcpOption::RequestedIpAddress(Ipv4Addr::UNSPECIFIED))
+        );
+        assert!(msg.opts().len() < len);
+        // still encodes
+        Message::decode(&mut Decoder::new(&msg.to_vec()?))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_opts_mut_visible() -> Result<()> {
+        let mut msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let len = msg.encoded_len();
+        assert_eq!(msg.opts().msg_type(), Some(MessageType::Offer));
+
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Ack));
+        msg.opts_mut()
+            .insert(DhcpOption::Hostname("example".to_owned()));
+        assert_eq!(msg.opts().msg_type(), Some(MessageType::Ack));
+        assert_eq!(msg.encoded_len(), len + 9);
+        assert_eq!(msg.encoded_len(), msg.to_vec()?.len());
+
+        msg.opts_mut().remove(OptionCode::Hostname);
+        assert_eq!(msg.encoded_len(), len);
+        assert_eq!(msg.opts().get(OptionCode::Hostname), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_to_writer() -> Result<()> {
+        struct Full;
+        impl std::io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let mut out = std::io::Cursor::new(Vec::new());
+        let mut scratch = Vec::new();
+        msg.encode_to_writer(&mut out, &mut scratch)?;
+        assert_eq!(out.into_inner(), msg.to_vec()?);
+        // the scratch buffer is reused, not reallocated
+        let ptr = scratch.as_ptr();
+        msg.encode_to_writer(&mut std::io::sink(), &mut scratch)?;
+        assert_eq!(scratch.as_ptr(), ptr);
+        assert!(matches!(
+            msg.encode_to_writer(&mut Full, &mut scratch),
+            Err(EncodeError::IoError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_error_context() {
+        let msg = Message {
+            sname: Some(vec![b'a'; 65]),
+            ..Message::default()
+        };
+        let err = msg.to_vec().unwrap_err();
+        assert!(matches!(err, EncodeError::InField { field: "sname", .. }));
+        assert!(matches!(
+            err.kind(),
+            EncodeError::StringSizeTooBig { len: 65 }
+        ));
+
+        let mut msg = Message::default();
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::AgentCircuitId(vec![0; 256]));
+        msg.opts_mut()
+            .insert(DhcpOption::RelayAgentInformation(info));
+        let err = msg.to_vec().unwrap_err();
+        assert!(matches!(err, EncodeError::InOption { code: 82, .. }));
+        assert!(matches!(
+            err.kind(),
+            EncodeError::OptionValueTooLong { code: 1, len: 256 }
+        ));
+    }
+
+    #[test]
+    fn test_decode_with_unknown() -> Result<()> {
+        let mut msg = Message::default();
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Request));
+        msg.opts_mut()
+            .insert(DhcpOption::Unknown(UnknownOption::new(
+                OptionCode::Unknown(250),
+                vec![0; 300],
+            )));
+        let buf = msg.to_vec()?;
+        let mut calls = Vec::new();
+        let decoded = Message::decode_with_unknown(&mut Decoder::new(&buf), |code, data| {
+            calls.push((code, data.len()));
+            KeepOrDrop::Drop
+        })?;
+        // called once with the concatenated value of a split option
+        assert_eq!(calls, [(250, 300)]);
+        assert_eq!(decoded.opts().len(), 1);
+        assert_eq!(decoded.opts().msg_type(), Some(MessageType::Request));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reply_destination() {
+        let mut reply = Message::default();
+        reply
+            .set_yiaddr([192, 168, 0, 5])
+            .set_ciaddr([192, 168, 0, 4])
+            .set_flags(Flags::default().set_broadcast());
+        // ciaddr wins over the broadcast flag
+        assert_eq!(
+            reply.reply_destination(),
+            (Ipv4Addr::new(192, 168, 0, 4), false)
+        );
+        reply
+            .opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Nak));
+        assert_eq!(reply.reply_destination(), (Ipv4Addr::BROADCAST, true));
+        // even a Nak goes back through the relay
+        reply.set_giaddr([10, 0, 0, 1]);
+        assert_eq!(
+            reply.reply_destination(),
+            (Ipv4Addr::new(10, 0, 0, 1), false)
+        );
+        assert!(!reply.requires_broadcast_reply());
+
+        // never unicast to 0.0.0.0
+        let mut reply = Message::default();
+        reply.set_chaddr(&[1; 6]);
+        assert_eq!(reply.reply_destination(), (Ipv4Addr::BROADCAST, true));
+        reply.set_ciaddr([192, 168, 0, 4]);
+        assert!(!reply.requires_broadcast_reply());
+    }
+
+    #[test]
+    fn test_lossy_names() {
+        let mut msg = Message::default();
+        assert!(msg.sname_lossy().is_none());
+        msg.set_sname(b"server\0\0").set_fname(b"\0");
+        assert!(matches!(msg.sname_lossy(), Some(Cow::Borrowed("server"))));
+        assert!(matches!(msg.fname_lossy(), Some(Cow::Borrowed(""))));
+        msg.set_sname(&[b'a', 0xc0, 0]);
+        assert!(matches!(msg.sname_lossy(), Some(Cow::Owned(s)) if s == "a\u{fffd}"));
+    }
+
+    #[test]
+    fn test_clone_for_retransmit() {
+        let mut msg = Message::new_with_id(
+            9,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            Ipv4Addr::UNSPECIFIED,
+            &[1; 6],
+        );
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Discover));
+        let retry = msg.clone_for_retransmit(Duration::from_secs(70_000));
+        assert_eq!(retry.secs(), u16::MAX);
+        assert_eq!((retry.xid(), retry.opts()), (9, msg.opts()));
+        let next = retry.clone_new_transaction(10);
+        assert_eq!((next.xid(), next.secs()), (10, 0));
+        assert_eq!(next.chaddr(), msg.chaddr());
+    }
+
+    #[test]
+    fn test_dhcp_request_state() {
+        let mut msg = Message::default();
+        assert_eq!(msg.dhcp_request_state(false), None);
+        msg.opts_mut()
+            .insert(DhcpOption::MessageType(MessageType::Request));
+        // nothing to identify the lease by
+        assert_eq!(msg.dhcp_request_state(false), None);
+        msg.set_ciaddr([192, 168, 0, 5]);
+        assert_eq!(msg.dhcp_request_state(false), Some(RequestState::Renewing));
+        assert_eq!(msg.dhcp_request_state(true), Some(RequestState::Rebinding));
+        // unicast from a relay
+        let mut relayed = msg.clone();
+        relayed.set_giaddr([10, 0, 0, 1]);
+        assert_eq!(
+            relayed.dhcp_request_state(false),
+            Some(RequestState::Rebinding)
+        );
+        // unicast to a relay overriding the server id, as a RENEWING client does
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::ServerIdentifierOverride(
+            [10, 0, 0, 1].into(),
+        ));
+        relayed
+            .opts_mut()
+            .insert(DhcpOption::RelayAgentInformation(info));
+        assert_eq!(
+            relayed.dhcp_request_state(false),
+            Some(RequestState::Renewing)
+        );
+        assert_eq!(
+            relayed.dhcp_request_state(true),
+            Some(RequestState::Rebinding)
+        );
+        msg.opts_mut()
+            .insert(DhcpOption::ServerIdentifier([192, 168, 0, 1].into()));
+        assert_eq!(msg.dhcp_request_state(false), None);
+        msg.opts_mut().remove(OptionCode::ServerIdentifier);
+        msg.opts_mut()
+            .insert(DhcpOption::RequestedIpAddress([192, 168, 0, 5].into()));
+        assert_eq!(msg.dhcp_request_state(false), None);
+    }
+
+    #[test]
+    fn test_has_addrs() {
+        let mut msg = Message::default();
+        assert!(!msg.has_ciaddr() && !msg.has_yiaddr());
+        assert!(!msg.has_siaddr() && !msg.has_giaddr());
+        msg.set_giaddr([10, 0, 0, 1]).set_yiaddr([192, 168, 0, 5]);
+        assert!(msg.has_giaddr() && msg.has_yiaddr());
+        assert!(!msg.has_ciaddr() && !msg.has_siaddr());
+        msg.clear_addrs();
+        assert!(!msg.has_giaddr() && !msg.has_yiaddr());
+    }
+
+    #[test]
+    fn test_try_set_sname_fname() -> Result<()> {
+        let mut msg = Message::default();
+        msg.try_set_sname(&[b'a'; 63])?
+            .try_set_fname(&[b'b'; 127])?;
+        assert!(matches!(
+            msg.try_set_sname(&[b'c'; 64]),
+            Err(EncodeError::FieldTooLong {
+                field: "sname",
+                len: 64,
+                max: 63
+            })
+        ));
+        assert!(matches!(
+            msg.try_set_fname(&[b'd'; 128]),
+            Err(EncodeError::FieldTooLong { max: 127, .. })
+        ));
+        // failed calls leave the fields alone
+        assert_eq!(msg.sname(), Some(&[b'a'; 63][..]));
+        assert_eq!(msg.fname(), Some(&[b'b'; 127][..]));
+        // both survive decoding, with their NUL terminator
+        let decoded = Message::decode(&mut Decoder::new(&msg.to_vec()?))?;
+        assert_eq!(decoded.sname().map(|s| &s[..63]), Some(&[b'a'; 63][..]));
+        assert_eq!(decoded.fname().map(|f| &f[..127]), Some(&[b'b'; 127][..]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_options_summary() -> Result<()> {
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let summary = msg.options_summary();
+        assert_eq!(summary.len(), msg.opts().len());
+        assert_eq!(summary[&1], "Subnet Mask");
+        assert_eq!(summary[&53], "DHCP Message Type");
+        assert!(summary
+            .keys()
+            .zip(summary.keys().skip(1))
+            .all(|(a, b)| a < b));
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() -> Result<()> {
+        let msg = Message::decode(&mut Decoder::new(&offer()))?;
+        let s = serde_json::to_string_pretty(&msg)?;
+        println!("{s}");
+        let other = serde_json::from_str(&s)?;
+        assert_eq!(msg, other);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_json_value() -> Result<()> {
+        let mut msg = Message::decode(&mut Decoder::new(&offer()))?;
+        msg.opts_mut()
+            .insert(DhcpOption::Unknown(UnknownOption::new(
+                240.into(),
+                vec![0xde, 0xad],
+            )));
+        let json = msg.to_json_value()?;
+        assert_eq!(json["xid"], 0x155c);
+        assert_eq!(json["yiaddr"], "192.168.0.3");
+        let opts = json["options"].as_array().unwrap();
+        assert_eq!(opts.len(), msg.opts().len());
+        // sorted by code
+        assert!(opts
+            .windows(2)
+            .all(|w| w[0]["code"].as_u64() < w[1]["code"].as_u64()));
+        assert_eq!(opts[0]["code"], 1);
+        assert_eq!(opts[0]["name"], "Subnet Mask");
+        let unknown = opts.last().unwrap();
+        assert_eq!(unknown["code"], 240);
+        assert_eq!(unknown["value"], "dead");
+        Ok(())
     }
 }
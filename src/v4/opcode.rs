@@ -1,14 +1,16 @@
+use std::{fmt, str::FromStr};
+
 use crate::{
     decoder::{Decodable, Decoder},
     encoder::{Encodable, Encoder},
-    error::{DecodeResult, EncodeResult},
+    error::{DecodeResult, EncodeResult, ParseNameError},
 };
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// Opcode of Message
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Opcode {
     /// BootRequest - <https://datatracker.ietf.org/doc/html/rfc1534#section-2>
     BootRequest,
@@ -18,6 +20,40 @@ pub enum Opcode {
     Unknown(u8),
 }
 
+impl Opcode {
+    /// canonical name of the opcode, from RFC 951
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Opcode::BootRequest => Some("BOOTREQUEST"),
+            Opcode::BootReply => Some("BOOTREPLY"),
+            Opcode::Unknown(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "UNKNOWN({})", u8::from(*self)),
+        }
+    }
+}
+
+/// Parses the canonical name, ignoring case, `UNKNOWN(n)` or a number
+/// ```
+/// # use dhcproto::v4::Opcode;
+/// assert_eq!("BootRequest".parse(), Ok(Opcode::BootRequest));
+/// assert_eq!(Opcode::BootReply.to_string(), "BOOTREPLY");
+/// ```
+impl FromStr for Opcode {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        super::parse_name("opcode", s, Self::name)
+    }
+}
+
 impl Decodable for Opcode {
     fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
         Ok(decoder.read_u8()?.into())
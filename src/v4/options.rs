@@ -1,12 +1,24 @@
-use std::{borrow::Cow, collections::HashMap, iter, net::Ipv4Addr};
+use std::{
+    borrow::Cow,
+    collections::{btree_map, BTreeMap},
+    fmt,
+    net::Ipv4Addr,
+    str::FromStr,
+};
 
 pub use crate::Domain;
 use crate::{
     decoder::{Decodable, Decoder},
     encoder::{Encodable, Encoder},
-    error::{DecodeResult, EncodeResult},
+    error::{DecodeError, DecodeResult, EncodeResult, ParseNameError},
     v4::bulk_query,
+    v4::classless,
+    v4::fqdn,
+    v4::pxe,
     v4::relay,
+    v4::shape::{Schema, ShapedValue, ValueShape},
+    v4::user_class,
+    v4::OptionsMode,
 };
 
 #[cfg(feature = "serde")]
@@ -18,6 +30,10 @@ use trust_dns_proto::{
 
 /// Options for DHCP. This implemention of options ignores PAD bytes.
 ///
+/// Options are kept ordered by [`OptionCode`], so iteration (and encoding) is
+/// in ascending order of code. An option split into several instances on the
+/// wire (RFC 3396) is stored as a single, concatenated, entry.
+///
 /// ex
 /// ```rust
 /// use dhcproto::v4;
@@ -37,8 +53,8 @@ use trust_dns_proto::{
 ///       ]));
 /// ```
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct DhcpOptions(HashMap<OptionCode, DhcpOption>);
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct DhcpOptions(BTreeMap<OptionCode, DhcpOption>);
 
 impl DhcpOptions {
     /// Create new [`DhcpOptions`]
@@ -53,6 +69,63 @@ impl DhcpOptions {
     pub fn get(&self, code: OptionCode) -> Option<&DhcpOption> {
         self.0.get(&code)
     }
+    /// Get the value bytes of an option as they would appear on the wire,
+    /// without the code & length. A value split across several instances
+    /// (RFC 3396) is returned concatenated. Options holding raw bytes are
+    /// borrowed, anything else is encoded, which can fail.
+    ///
+    /// For the exact bytes of a decoded message use
+    /// [`DhcpOptions::decode_with_raw`]
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions, OptionCode};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::AddressLeaseTime(3600));
+    /// opts.insert(DhcpOption::VendorExtensions(vec![1, 2, 3]));
+    /// assert_eq!(&*opts.get_raw(OptionCode::AddressLeaseTime).unwrap()?, [0, 0, 14, 16]);
+    /// assert_eq!(&*opts.get_raw(OptionCode::VendorExtensions).unwrap()?, [1, 2, 3]);
+    /// assert!(opts.get_raw(OptionCode::Router).is_none());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_raw(&self, code: OptionCode) -> Option<EncodeResult<Cow<'_, [u8]>>> {
+        use DhcpOption::*;
+        Some(match self.get(code)? {
+            Pad | End => Ok(Cow::Borrowed(&[])),
+            Unknown(opt) => Ok(Cow::Borrowed(opt.data())),
+            VendorExtensions(bytes)
+            | ClassIdentifier(bytes)
+            | ClientIdentifier(bytes)
+            | ClientMachineIdentifier(bytes) => Ok(Cow::Borrowed(bytes)),
+            UserClass(class) => Ok(Cow::Borrowed(class.raw())),
+            opt => opt.to_vec().map(|buf| {
+                // strip the code & length of each instance
+                let mut value = Vec::with_capacity(buf.len());
+                let mut rest = &buf[..];
+                while let [_, len, tail @ ..] = rest {
+                    let (data, tail) = tail.split_at(*len as usize);
+                    value.extend_from_slice(data);
+                    rest = tail;
+                }
+                Cow::Owned(value)
+            }),
+        })
+    }
+    /// Decode the value of `code` as `shape`, see [`shape`](crate::v4::shape).
+    /// Returns `None` if the option isn't present
+    pub fn get_as(&self, code: OptionCode, shape: ValueShape) -> Option<DecodeResult<ShapedValue>> {
+        Some(match self.get_raw(code)? {
+            Ok(value) => shape.decode(&value),
+            Err(err) => Err(DecodeError::Unknown(Box::new(err))),
+        })
+    }
+    /// Decode the value of `code` using the shape given to it by `schema`.
+    /// Returns `None` if the option isn't present or isn't in `schema`
+    pub fn get_with_schema(
+        &self,
+        code: OptionCode,
+        schema: &Schema,
+    ) -> Option<DecodeResult<ShapedValue>> {
+        self.get_as(code, *schema.get(&code)?)
+    }
     /// Get the mutable data for a particular [`OptionCode`]
     ///
     /// [`OptionCode`]: crate::v4::OptionCode
@@ -63,27 +136,57 @@ impl DhcpOptions {
     pub fn remove(&mut self, code: OptionCode) -> Option<DhcpOption> {
         self.0.remove(&code)
     }
-    /// insert a new [`DhcpOption`]
+    /// insert a new [`DhcpOption`], returning the option it replaced with the
+    /// same code. The typed value is written with its code & length when the
+    /// options are encoded, values longer than 255 bytes are split (RFC 3396)
     ///
     /// ```
-    /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions};
+    /// # use dhcproto::{Encodable, v4::{MessageType, DhcpOption, DhcpOptions}};
     /// let mut opts = DhcpOptions::new();
     /// opts.insert(DhcpOption::MessageType(MessageType::Discover));
+    /// opts.insert(DhcpOption::AddressLeaseTime(3600));
+    /// assert_eq!(opts.to_vec()?, [51, 4, 0, 0, 14, 16, 53, 1, 1, 255]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     /// [`DhcpOption`]: crate::v4::DhcpOption
     pub fn insert(&mut self, opt: DhcpOption) -> Option<DhcpOption> {
         self.0.insert((&opt).into(), opt)
     }
-    /// iterate over entries
+    /// push a typed [`DhcpOption`], it's serialized to its code & bytes when
+    /// the options are encoded. Same as [`DhcpOptions::insert`], an existing
+    /// option with the same code is replaced & returned
+    ///
     /// ```
-    /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions};
+    /// # use dhcproto::{Encodable, v4::{MessageType, DhcpOption, DhcpOptions}};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.push(DhcpOption::MessageType(MessageType::Discover));
+    /// let old = opts.push(DhcpOption::MessageType(MessageType::Request));
+    /// assert_eq!(old, Some(DhcpOption::MessageType(MessageType::Discover)));
+    /// assert_eq!(opts.to_vec()?, [53, 1, 3, 255]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    /// [`DhcpOption`]: crate::v4::DhcpOption
+    pub fn push(&mut self, opt: DhcpOption) -> Option<DhcpOption> {
+        self.insert(opt)
+    }
+    /// iterate over entries in ascending order of code. Options are already
+    /// typed, options this crate doesn't know are [`DhcpOption::Unknown`]
+    /// ```
+    /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions, OptionCode, UnknownOption};
     /// let mut opts = DhcpOptions::new();
     /// opts.insert(DhcpOption::MessageType(MessageType::Offer));
     /// opts.insert(DhcpOption::SubnetMask([198, 168, 0, 1].into()));
+    /// opts.insert(DhcpOption::Unknown(UnknownOption::new(OptionCode::Unknown(224), vec![1])));
     /// for (code, opt) in opts.iter() {
-    ///     println!("{code:?} {opt:?}");
+    ///     match opt {
+    ///         DhcpOption::SubnetMask(mask) => println!("mask {mask}"),
+    ///         DhcpOption::MessageType(ty) => println!("type {ty:?}"),
+    ///         DhcpOption::Unknown(opt) => println!("{code:?} {:?}", opt.data()),
+    ///         _ => {}
+    ///     }
     /// }
     /// ```
+    #[doc(alias = "iter_typed")]
     pub fn iter(&self) -> impl Iterator<Item = (&OptionCode, &DhcpOption)> {
         self.0.iter()
     }
@@ -91,6 +194,18 @@ impl DhcpOptions {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&OptionCode, &mut DhcpOption)> {
         self.0.iter_mut()
     }
+    /// iterate over the codes of the options present, in ascending order,
+    /// without touching their values
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions, MessageType};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::MessageType(MessageType::Offer));
+    /// opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+    /// assert_eq!(opts.codes().collect::<Vec<_>>(), [1, 53]);
+    /// ```
+    pub fn codes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.keys().map(|code| u8::from(*code))
+    }
     /// return message type
     /// ```
     /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions};
@@ -116,6 +231,551 @@ impl DhcpOptions {
     pub fn has_msg_type(&self, opt: MessageType) -> bool {
         matches!(self.get(OptionCode::MessageType), Some(DhcpOption::MessageType(msg)) if *msg == opt)
     }
+    /// return the routers (option 3)
+    pub fn routers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::Router) {
+            Some(DhcpOption::Router(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// iterate over the routers (option 3) without allocating, yields nothing
+    /// if the option is not present
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::Router(vec![[10, 0, 0, 1].into()]));
+    /// assert_eq!(opts.routers_iter().next(), Some([10, 0, 0, 1].into()));
+    /// assert_eq!(DhcpOptions::new().routers_iter().count(), 0);
+    /// ```
+    pub fn routers_iter(&self) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.routers().unwrap_or_default().iter().copied()
+    }
+    /// return the time servers (option 4)
+    pub fn time_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::TimeServer) {
+            Some(DhcpOption::TimeServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// set the time servers (option 4)
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_time_servers(vec![[10, 0, 0, 1].into()])
+    ///     .set_log_servers(vec![[10, 0, 0, 2].into()]);
+    /// assert_eq!(opts.time_servers(), Some(&[[10, 0, 0, 1].into()][..]));
+    /// assert_eq!(opts.log_servers(), Some(&[[10, 0, 0, 2].into()][..]));
+    /// ```
+    pub fn set_time_servers(&mut self, ips: Vec<Ipv4Addr>) -> &mut Self {
+        self.insert(DhcpOption::TimeServer(ips));
+        self
+    }
+    /// return the log servers (option 7)
+    pub fn log_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::LogServer) {
+            Some(DhcpOption::LogServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// set the log servers (option 7)
+    pub fn set_log_servers(&mut self, ips: Vec<Ipv4Addr>) -> &mut Self {
+        self.insert(DhcpOption::LogServer(ips));
+        self
+    }
+    /// return the boot file size (option 13), in 512 byte blocks
+    pub fn boot_file_size(&self) -> Option<u16> {
+        match self.get(OptionCode::BootFileSize) {
+            Some(DhcpOption::BootFileSize(size)) => Some(*size),
+            _ => None,
+        }
+    }
+    /// set the boot file size (option 13), in 512 byte blocks
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_boot_file_size(128)
+    ///     .set_merit_dump_file("/var/crash/core");
+    /// assert_eq!(opts.boot_file_size(), Some(128));
+    /// assert_eq!(opts.merit_dump_file(), Some("/var/crash/core"));
+    /// ```
+    pub fn set_boot_file_size(&mut self, blocks: u16) -> &mut Self {
+        self.insert(DhcpOption::BootFileSize(blocks));
+        self
+    }
+    /// return the merit dump file (option 14), where the client should dump
+    /// its core image on a crash
+    pub fn merit_dump_file(&self) -> Option<&str> {
+        match self.get(OptionCode::MeritDumpFile) {
+            Some(DhcpOption::MeritDumpFile(path)) => Some(path),
+            _ => None,
+        }
+    }
+    /// set the merit dump file (option 14)
+    pub fn set_merit_dump_file<S: Into<String>>(&mut self, path: S) -> &mut Self {
+        self.insert(DhcpOption::MeritDumpFile(path.into()));
+        self
+    }
+    /// return the swap server (option 16)
+    pub fn swap_server(&self) -> Option<Ipv4Addr> {
+        match self.get(OptionCode::SwapServer) {
+            Some(DhcpOption::SwapServer(ip)) => Some(*ip),
+            _ => None,
+        }
+    }
+    /// set the swap server (option 16)
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_swap_server([10, 0, 0, 5].into())
+    ///     .set_root_path("10.0.0.5:/srv/nfsroot");
+    /// assert_eq!(opts.swap_server(), Some([10, 0, 0, 5].into()));
+    /// assert_eq!(opts.root_path(), Some("10.0.0.5:/srv/nfsroot"));
+    /// ```
+    pub fn set_swap_server(&mut self, ip: Ipv4Addr) -> &mut Self {
+        self.insert(DhcpOption::SwapServer(ip));
+        self
+    }
+    /// return the root path (option 17)
+    pub fn root_path(&self) -> Option<&str> {
+        match self.get(OptionCode::RootPath) {
+            Some(DhcpOption::RootPath(path)) => Some(path),
+            _ => None,
+        }
+    }
+    /// set the root path (option 17)
+    pub fn set_root_path<S: Into<String>>(&mut self, path: S) -> &mut Self {
+        self.insert(DhcpOption::RootPath(path.into()));
+        self
+    }
+    /// return the POSIX timezone string (option 100)
+    pub fn posix_timezone(&self) -> Option<&str> {
+        match self.get(OptionCode::PosixTimezone) {
+            Some(DhcpOption::PosixTimezone(tz)) => Some(tz),
+            _ => None,
+        }
+    }
+    /// set the POSIX timezone string (option 100)
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_posix_timezone("CET-1CEST,M3.5.0,M10.5.0/3")
+    ///     .set_tz_database_timezone("Europe/Zurich");
+    /// assert_eq!(opts.posix_timezone(), Some("CET-1CEST,M3.5.0,M10.5.0/3"));
+    /// assert_eq!(opts.tz_database_timezone(), Some("Europe/Zurich"));
+    /// ```
+    pub fn set_posix_timezone<S: Into<String>>(&mut self, tz: S) -> &mut Self {
+        self.insert(DhcpOption::PosixTimezone(tz.into()));
+        self
+    }
+    /// return the TZ database timezone name (option 101)
+    pub fn tz_database_timezone(&self) -> Option<&str> {
+        match self.get(OptionCode::TzDatabaseTimezone) {
+            Some(DhcpOption::TzDatabaseTimezone(tz)) => Some(tz),
+            _ => None,
+        }
+    }
+    /// set the TZ database timezone name (option 101)
+    pub fn set_tz_database_timezone<S: Into<String>>(&mut self, tz: S) -> &mut Self {
+        self.insert(DhcpOption::TzDatabaseTimezone(tz.into()));
+        self
+    }
+    /// return the NIS domain (option 40)
+    pub fn nis_domain(&self) -> Option<&str> {
+        match self.get(OptionCode::NISDomain) {
+            Some(DhcpOption::NISDomain(domain)) => Some(domain),
+            _ => None,
+        }
+    }
+    /// set the NIS domain (option 40)
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_nis_domain("yp.example.edu")
+    ///     .set_nis_servers(vec![[10, 0, 0, 2].into()]);
+    /// assert_eq!(opts.nis_domain(), Some("yp.example.edu"));
+    /// assert_eq!(opts.nis_servers(), Some(&[[10, 0, 0, 2].into()][..]));
+    /// ```
+    pub fn set_nis_domain<S: Into<String>>(&mut self, domain: S) -> &mut Self {
+        self.insert(DhcpOption::NISDomain(domain.into()));
+        self
+    }
+    /// return the NIS servers (option 41), in order of preference
+    pub fn nis_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::NIS) {
+            Some(DhcpOption::NIS(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// set the NIS servers (option 41)
+    pub fn set_nis_servers(&mut self, ips: Vec<Ipv4Addr>) -> &mut Self {
+        self.insert(DhcpOption::NIS(ips));
+        self
+    }
+    /// return whether the client should enable IP forwarding (option 19)
+    pub fn ip_forwarding(&self) -> Option<bool> {
+        match self.get(OptionCode::IpForwarding) {
+            Some(DhcpOption::IpForwarding(b)) => Some(*b),
+            _ => None,
+        }
+    }
+    /// set whether the client should enable IP forwarding (option 19)
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_ip_forwarding(true)
+    ///     .set_non_local_src_routing(true)
+    ///     .set_policy_filter(vec![([10, 0, 0, 0].into(), [255, 0, 0, 0].into())]);
+    /// assert_eq!(opts.ip_forwarding(), Some(true));
+    /// assert_eq!(opts.non_local_src_routing(), Some(true));
+    /// assert_eq!(opts.policy_filter().map(|f| f.len()), Some(1));
+    /// ```
+    pub fn set_ip_forwarding(&mut self, enable: bool) -> &mut Self {
+        self.insert(DhcpOption::IpForwarding(enable));
+        self
+    }
+    /// return whether the client should allow forwarding of datagrams with
+    /// non-local source routes (option 20)
+    pub fn non_local_src_routing(&self) -> Option<bool> {
+        match self.get(OptionCode::NonLocalSrcRouting) {
+            Some(DhcpOption::NonLocalSrcRouting(b)) => Some(*b),
+            _ => None,
+        }
+    }
+    /// set whether the client should allow forwarding of datagrams with
+    /// non-local source routes (option 20)
+    pub fn set_non_local_src_routing(&mut self, enable: bool) -> &mut Self {
+        self.insert(DhcpOption::NonLocalSrcRouting(enable));
+        self
+    }
+    /// return the policy filter (option 21) as (address, mask) pairs
+    pub fn policy_filter(&self) -> Option<&[(Ipv4Addr, Ipv4Addr)]> {
+        match self.get(OptionCode::PolicyFilter) {
+            Some(DhcpOption::PolicyFilter(filters)) => Some(filters),
+            _ => None,
+        }
+    }
+    /// set the policy filter (option 21) as (address, mask) pairs
+    pub fn set_policy_filter(&mut self, filters: Vec<(Ipv4Addr, Ipv4Addr)>) -> &mut Self {
+        self.insert(DhcpOption::PolicyFilter(filters));
+        self
+    }
+    /// return the default IP TTL (option 23) the client should use for
+    /// outgoing datagrams
+    pub fn default_ip_ttl(&self) -> Option<u8> {
+        match self.get(OptionCode::DefaultIpTtl) {
+            Some(DhcpOption::DefaultIpTtl(ttl)) => Some(*ttl),
+            _ => None,
+        }
+    }
+    /// set the default IP TTL (option 23)
+    pub fn set_default_ip_ttl(&mut self, ttl: u8) -> &mut Self {
+        self.insert(DhcpOption::DefaultIpTtl(ttl));
+        self
+    }
+    /// return the TCP keepalive interval (option 38), in seconds. `0` means
+    /// keepalive messages shouldn't be sent unless requested by the application
+    pub fn tcp_keepalive_interval(&self) -> Option<u32> {
+        match self.get(OptionCode::TcpKeepaliveInterval) {
+            Some(DhcpOption::TcpKeepaliveInterval(secs)) => Some(*secs),
+            _ => None,
+        }
+    }
+    /// set the TCP keepalive interval (option 38), in seconds
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_default_ip_ttl(64)
+    ///     .set_tcp_keepalive_interval(7200)
+    ///     .set_tcp_keepalive_garbage(true);
+    /// assert_eq!(opts.default_ip_ttl(), Some(64));
+    /// assert_eq!(opts.tcp_keepalive_interval(), Some(7200));
+    /// assert_eq!(opts.tcp_keepalive_garbage(), Some(true));
+    /// ```
+    pub fn set_tcp_keepalive_interval(&mut self, secs: u32) -> &mut Self {
+        self.insert(DhcpOption::TcpKeepaliveInterval(secs));
+        self
+    }
+    /// return whether TCP keepalive messages should include an octet of
+    /// garbage, for compatibility with older implementations (option 39)
+    pub fn tcp_keepalive_garbage(&self) -> Option<bool> {
+        match self.get(OptionCode::TcpKeepaliveGarbage) {
+            Some(DhcpOption::TcpKeepaliveGarbage(b)) => Some(*b),
+            _ => None,
+        }
+    }
+    /// set whether TCP keepalive messages should include an octet of garbage
+    /// (option 39)
+    pub fn set_tcp_keepalive_garbage(&mut self, enable: bool) -> &mut Self {
+        self.insert(DhcpOption::TcpKeepaliveGarbage(enable));
+        self
+    }
+    /// return the path MTU aging timeout (option 24), in seconds
+    pub fn path_mtu_aging_timeout(&self) -> Option<u32> {
+        match self.get(OptionCode::PathMtuAgingTimeout) {
+            Some(DhcpOption::PathMtuAgingTimeout(secs)) => Some(*secs),
+            _ => None,
+        }
+    }
+    /// set the path MTU aging timeout (option 24), in seconds
+    /// ```
+    /// # use dhcproto::v4::DhcpOptions;
+    /// let mut opts = DhcpOptions::new();
+    /// opts.set_path_mtu_aging_timeout(600)
+    ///     .set_path_mtu_plateau_table(vec![576, 1006, 1492, 1500]);
+    /// assert_eq!(opts.path_mtu_aging_timeout(), Some(600));
+    /// assert_eq!(opts.path_mtu_plateau_table(), Some(&[576, 1006, 1492, 1500][..]));
+    /// ```
+    pub fn set_path_mtu_aging_timeout(&mut self, secs: u32) -> &mut Self {
+        self.insert(DhcpOption::PathMtuAgingTimeout(secs));
+        self
+    }
+    /// return the path MTU plateau table (option 25)
+    pub fn path_mtu_plateau_table(&self) -> Option<&[u16]> {
+        match self.get(OptionCode::PathMtuPlateauTable) {
+            Some(DhcpOption::PathMtuPlateauTable(mtus)) => Some(mtus),
+            _ => None,
+        }
+    }
+    /// set the path MTU plateau table (option 25), MTU sizes should be
+    /// ordered from smallest to largest
+    pub fn set_path_mtu_plateau_table(&mut self, mtus: Vec<u16>) -> &mut Self {
+        self.insert(DhcpOption::PathMtuPlateauTable(mtus));
+        self
+    }
+    /// return the mobile IP home agents (option 68). An empty list is legal,
+    /// meaning the option was sent but no home agents are available
+    pub fn mobile_ip_home_agents(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::MobileIpHomeAgent) {
+            Some(DhcpOption::MobileIpHomeAgent(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return the SMTP servers (option 69)
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::SmtpServer(vec![[10, 0, 0, 25].into()]));
+    /// assert_eq!(opts.smtp_servers(), Some(&[[10, 0, 0, 25].into()][..]));
+    /// assert_eq!(opts.pop3_servers(), None);
+    /// ```
+    pub fn smtp_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::SmtpServer) {
+            Some(DhcpOption::SmtpServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return the POP3 servers (option 70)
+    pub fn pop3_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::Pop3Server) {
+            Some(DhcpOption::Pop3Server(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return the NNTP servers (option 71)
+    pub fn nntp_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::NntpServer) {
+            Some(DhcpOption::NntpServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return whether the client should perform router discovery (option 31)
+    pub fn perform_router_discovery(&self) -> Option<bool> {
+        match self.get(OptionCode::PerformRouterDiscovery) {
+            Some(DhcpOption::PerformRouterDiscovery(b)) => Some(*b),
+            _ => None,
+        }
+    }
+    /// return the static routes (option 33) as (destination, router) pairs
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::StaticRoutingTable(vec![(
+    ///     [10, 1, 0, 0].into(),
+    ///     [192, 168, 0, 1].into(),
+    /// )]));
+    /// assert_eq!(
+    ///     opts.static_routes(),
+    ///     Some(&[([10, 1, 0, 0].into(), [192, 168, 0, 1].into())][..])
+    /// );
+    /// ```
+    pub fn static_routes(&self) -> Option<&[(Ipv4Addr, Ipv4Addr)]> {
+        match self.get(OptionCode::StaticRoutingTable) {
+            Some(DhcpOption::StaticRoutingTable(routes)) => Some(routes),
+            _ => None,
+        }
+    }
+    /// return the classless static routes (option 121)
+    pub fn classless_static_routes(&self) -> Option<&[classless::ClasslessRoute]> {
+        match self.get(OptionCode::ClasslessStaticRoute) {
+            Some(DhcpOption::ClasslessStaticRoute(routes)) => Some(routes),
+            _ => None,
+        }
+    }
+    /// parse the Vendor Specific Information option (43) as PXE vendor
+    /// sub-options. Returns `None` if the option isn't present
+    pub fn pxe_vendor_options(&self) -> Option<DecodeResult<pxe::PxeVendorOptions>> {
+        match self.get(OptionCode::VendorExtensions) {
+            Some(DhcpOption::VendorExtensions(bytes)) => {
+                Some(pxe::PxeVendorOptions::decode(&mut Decoder::new(bytes)))
+            }
+            _ => None,
+        }
+    }
+    /// return the domain name servers (option 6)
+    pub fn domain_name_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::DomainNameServer) {
+            Some(DhcpOption::DomainNameServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// iterate over the domain name servers (option 6) without allocating,
+    /// yields nothing if the option is not present
+    pub fn dns_iter(&self) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.domain_name_servers()
+            .unwrap_or_default()
+            .iter()
+            .copied()
+    }
+    /// return the NetBIOS name servers (option 44)
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::NetBiosNameServers(vec![[10, 0, 0, 1].into()]));
+    /// assert_eq!(opts.netbios_name_servers(), Some(&[[10, 0, 0, 1].into()][..]));
+    /// ```
+    pub fn netbios_name_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::NetBiosNameServers) {
+            Some(DhcpOption::NetBiosNameServers(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return the NetBIOS datagram distribution servers (option 45)
+    pub fn netbios_datagram_distribution_servers(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::NetBiosDatagramDistributionServer) {
+            Some(DhcpOption::NetBiosDatagramDistributionServer(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// return the NetBIOS node type (option 46)
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions, NodeType};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::NetBiosNodeType(NodeType::H));
+    /// assert_eq!(opts.netbios_node_type(), Some(NodeType::H));
+    /// ```
+    pub fn netbios_node_type(&self) -> Option<NodeType> {
+        match self.get(OptionCode::NetBiosNodeType) {
+            Some(DhcpOption::NetBiosNodeType(ntype)) => Some(*ntype),
+            _ => None,
+        }
+    }
+    /// return the NetBIOS scope (option 47)
+    pub fn netbios_scope(&self) -> Option<&str> {
+        match self.get(OptionCode::NetBiosScope) {
+            Some(DhcpOption::NetBiosScope(scope)) => Some(scope),
+            _ => None,
+        }
+    }
+    /// return the Auto-Configure setting (option 116)
+    /// ```
+    /// # use dhcproto::v4::{AutoConfig, DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::AutoConfigure(AutoConfig::DoNotAutoConfigure));
+    /// assert_eq!(opts.auto_configure(), Some(AutoConfig::DoNotAutoConfigure));
+    /// ```
+    pub fn auto_configure(&self) -> Option<AutoConfig> {
+        match self.get(OptionCode::AutoConfigure) {
+            Some(DhcpOption::AutoConfigure(auto)) => Some(*auto),
+            _ => None,
+        }
+    }
+    /// return the PXELINUX configuration file (option 209)
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::PxeLinuxConfigFile("pxelinux.cfg/default".to_owned()));
+    /// assert_eq!(opts.pxelinux_config_file(), Some("pxelinux.cfg/default"));
+    /// ```
+    pub fn pxelinux_config_file(&self) -> Option<&str> {
+        match self.get(OptionCode::PxeLinuxConfigFile) {
+            Some(DhcpOption::PxeLinuxConfigFile(file)) => Some(file),
+            _ => None,
+        }
+    }
+    /// return the PXELINUX path prefix (option 210)
+    pub fn pxelinux_path_prefix(&self) -> Option<&str> {
+        match self.get(OptionCode::PxeLinuxPathPrefix) {
+            Some(DhcpOption::PxeLinuxPathPrefix(prefix)) => Some(prefix),
+            _ => None,
+        }
+    }
+    /// return the PXELINUX reboot time in seconds (option 211)
+    pub fn pxelinux_reboot_time(&self) -> Option<u32> {
+        match self.get(OptionCode::PxeLinuxRebootTime) {
+            Some(DhcpOption::PxeLinuxRebootTime(secs)) => Some(*secs),
+            _ => None,
+        }
+    }
+    /// return the client FQDN (option 81)
+    pub fn client_fqdn(&self) -> Option<&fqdn::ClientFQDN> {
+        match self.get(OptionCode::ClientFQDN) {
+            Some(DhcpOption::ClientFQDN(fqdn)) => Some(fqdn),
+            _ => None,
+        }
+    }
+    /// return the User Class (option 77)
+    pub fn user_class(&self) -> Option<&user_class::UserClass> {
+        match self.get(OptionCode::UserClass) {
+            Some(DhcpOption::UserClass(class)) => Some(class),
+            _ => None,
+        }
+    }
+    /// return the relay agent information (option 82)
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions, relay::{RelayAgentInformation, RelayInfo}};
+    /// let mut info = RelayAgentInformation::default();
+    /// info.insert(RelayInfo::ServerIdentifierOverride([10, 0, 0, 1].into()));
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::RelayAgentInformation(info));
+    /// let server_id = opts
+    ///     .relay_agent_information()
+    ///     .and_then(|info| info.server_identifier_override());
+    /// assert_eq!(server_id, Some(Ipv4Addr::new(10, 0, 0, 1)));
+    /// ```
+    pub fn relay_agent_information(&self) -> Option<&relay::RelayAgentInformation> {
+        match self.get(OptionCode::RelayAgentInformation) {
+            Some(DhcpOption::RelayAgentInformation(info)) => Some(info),
+            _ => None,
+        }
+    }
+    /// return the client last transaction time in seconds (option 91), sent in
+    /// leasequery replies
+    pub fn client_last_transaction_time(&self) -> Option<u32> {
+        match self.get(OptionCode::ClientLastTransactionTime) {
+            Some(DhcpOption::ClientLastTransactionTime(secs)) => Some(*secs),
+            _ => None,
+        }
+    }
+    /// set the client last transaction time (option 91), the seconds since the
+    /// server last heard from the client
+    pub fn set_client_last_transaction_time(&mut self, secs: u32) -> &mut Self {
+        self.insert(DhcpOption::ClientLastTransactionTime(secs));
+        self
+    }
+    /// return the associated IPs (option 92), sent in leasequery replies
+    pub fn associated_ips(&self) -> Option<&[Ipv4Addr]> {
+        match self.get(OptionCode::AssociatedIp) {
+            Some(DhcpOption::AssociatedIp(ips)) => Some(ips),
+            _ => None,
+        }
+    }
+    /// set the associated IPs (option 92), the other addresses leased to the
+    /// client in a leasequery reply
+    pub fn set_associated_ips(&mut self, ips: Vec<Ipv4Addr>) -> &mut Self {
+        self.insert(DhcpOption::AssociatedIp(ips));
+        self
+    }
     /// clear all options
     /// ```
     /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions};
@@ -139,6 +799,17 @@ impl DhcpOptions {
         self.0.is_empty()
     }
     /// Retains only the elements specified by the predicate
+    /// ```
+    /// # use dhcproto::v4::{MessageType, DhcpOption, DhcpOptions, OptionCode};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::MessageType(MessageType::Request));
+    /// opts.insert(DhcpOption::Hostname("client".to_owned()));
+    /// opts.insert(DhcpOption::ClientIdentifier(vec![1, 2, 3]));
+    /// // keep a whitelist of options in one pass
+    /// opts.retain(|code, _| matches!(code, OptionCode::MessageType | OptionCode::ClientIdentifier));
+    /// assert_eq!(opts.len(), 2);
+    /// assert!(opts.get(OptionCode::Hostname).is_none());
+    /// ```
     pub fn retain<F>(&mut self, pred: F)
     where
         F: FnMut(&OptionCode, &mut DhcpOption) -> bool,
@@ -155,11 +826,165 @@ impl DhcpOptions {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+    /// Merge the options of `other` into `self`, resolving options present in
+    /// both according to `strategy`
+    /// ```
+    /// # use dhcproto::v4::{DhcpOption, DhcpOptions, MergeStrategy, OptionCode};
+    /// let mut defaults = DhcpOptions::new();
+    /// defaults.insert(DhcpOption::DomainNameServer(vec![[10, 0, 0, 53].into()]));
+    /// defaults.insert(DhcpOption::AddressLeaseTime(3600));
+    ///
+    /// let mut client = DhcpOptions::new();
+    /// client.insert(DhcpOption::DomainNameServer(vec![[192, 168, 0, 53].into()]));
+    /// client.insert(DhcpOption::AddressLeaseTime(600));
+    ///
+    /// defaults.merge(&client, MergeStrategy::Append);
+    /// assert_eq!(
+    ///     defaults.domain_name_servers(),
+    ///     Some(&[[10, 0, 0, 53].into(), [192, 168, 0, 53].into()][..])
+    /// );
+    /// assert_eq!(
+    ///     defaults.get(OptionCode::AddressLeaseTime),
+    ///     Some(&DhcpOption::AddressLeaseTime(600))
+    /// );
+    /// ```
+    pub fn merge(&mut self, other: &DhcpOptions, strategy: MergeStrategy) {
+        for (code, opt) in other.iter() {
+            match self.0.get_mut(code) {
+                None => {
+                    self.0.insert(*code, opt.clone());
+                }
+                Some(_) if strategy == MergeStrategy::KeepExisting => {}
+                Some(existing) if strategy == MergeStrategy::Append => {
+                    if !append_opt(existing, opt) {
+                        *existing = opt.clone();
+                    }
+                }
+                Some(existing) => *existing = opt.clone(),
+            }
+        }
+    }
+    /// number of bytes the options encode to, including `End`
+    pub fn encoded_len(&self) -> usize {
+        if self.0.is_empty() {
+            return 0;
+        }
+        self.0
+            .iter()
+            .filter(|(code, _)| !matches!(code, OptionCode::Pad | OptionCode::End))
+            .map(|(_, opt)| opt.encoded_len())
+            .sum::<usize>()
+            + 1
+    }
+    /// Encode the options with the codes in `order` written first, in that
+    /// order, followed by the remaining options in ascending order of code &
+    /// `End`. Unless it's listed in `order`, `RelayAgentInformation` is written
+    /// last. Codes in `order` that aren't present are ignored.
+    /// ```
+    /// # use dhcproto::{Encoder, v4::{DhcpOption, DhcpOptions, MessageType, OptionCode}};
+    /// let mut opts = DhcpOptions::new();
+    /// opts.insert(DhcpOption::MessageType(MessageType::Discover));
+    /// opts.insert(DhcpOption::Hostname("a".to_owned()));
+    /// opts.insert(DhcpOption::MaxMessageSize(1500));
+    ///
+    /// let mut buf = Vec::new();
+    /// opts.encode_ordered(&[OptionCode::MaxMessageSize], &mut Encoder::new(&mut buf))?;
+    /// assert_eq!(buf, vec![57, 2, 5, 220, 12, 1, b'a', 53, 1, 1, 255]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn encode_ordered(&self, order: &[OptionCode], e: &mut Encoder<'_>) -> EncodeResult<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        // `Pad`/`End` in the map are skipped so exactly one `End` is written
+        let skip = |code: &OptionCode| matches!(code, OptionCode::Pad | OptionCode::End);
+        let mut first = Vec::with_capacity(order.len());
+        for code in order {
+            if !skip(code) && !first.contains(code) {
+                first.push(*code);
+            }
+        }
+        let relay = OptionCode::RelayAgentInformation;
+        let rest = self
+            .0
+            .keys()
+            .filter(|code| !skip(code) && **code != relay && !first.contains(code))
+            .copied();
+        // agent info must be placed last before `End`
+        let last = (!first.contains(&relay)).then(|| relay);
+        first
+            .iter()
+            .copied()
+            .chain(rest)
+            .chain(last)
+            .filter_map(|code| self.0.get(&code))
+            .try_for_each(|opt| {
+                opt.encode(e)
+                    .map_err(|err| err.in_option(OptionCode::from(opt).into()))
+            })?;
+        DhcpOption::End.encode(e)
+    }
+}
+
+/// How [`DhcpOptions::merge`] resolves an option present in both sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeStrategy {
+    /// keep the existing option
+    KeepExisting,
+    /// replace the existing option
+    Overwrite,
+    /// for list options (addresses, routes, parameter request list, domain
+    /// search) append the values not already present, otherwise overwrite
+    Append,
+}
+
+/// append the values of `other` to `existing` if both are the same list option,
+/// returns false if the option isn't a list
+fn append_opt(existing: &mut DhcpOption, other: &DhcpOption) -> bool {
+    fn extend<T: PartialEq + Clone>(a: &mut Vec<T>, b: &[T]) {
+        for val in b {
+            if !a.contains(val) {
+                a.push(val.clone());
+            }
+        }
+    }
+    use DhcpOption::*;
+    match (existing, other) {
+        (Router(a), Router(b))
+        | (TimeServer(a), TimeServer(b))
+        | (NameServer(a), NameServer(b))
+        | (DomainNameServer(a), DomainNameServer(b))
+        | (LogServer(a), LogServer(b))
+        | (QuoteServer(a), QuoteServer(b))
+        | (LprServer(a), LprServer(b))
+        | (ImpressServer(a), ImpressServer(b))
+        | (ResourceLocationServer(a), ResourceLocationServer(b))
+        | (NIS(a), NIS(b))
+        | (NTPServers(a), NTPServers(b))
+        | (NetBiosNameServers(a), NetBiosNameServers(b))
+        | (NetBiosDatagramDistributionServer(a), NetBiosDatagramDistributionServer(b))
+        | (XFontServer(a), XFontServer(b))
+        | (XDisplayManager(a), XDisplayManager(b))
+        | (MobileIpHomeAgent(a), MobileIpHomeAgent(b))
+        | (SmtpServer(a), SmtpServer(b))
+        | (Pop3Server(a), Pop3Server(b))
+        | (NntpServer(a), NntpServer(b))
+        | (AssociatedIp(a), AssociatedIp(b)) => extend(a, b),
+        (StaticRoutingTable(a), StaticRoutingTable(b)) | (PolicyFilter(a), PolicyFilter(b)) => {
+            extend(a, b)
+        }
+        (ClasslessStaticRoute(a), ClasslessStaticRoute(b)) => extend(a, b),
+        (ParameterRequestList(a), ParameterRequestList(b)) => extend(a, b),
+        (PathMtuPlateauTable(a), PathMtuPlateauTable(b)) => extend(a, b),
+        (DomainSearch(a), DomainSearch(b)) => extend(a, b),
+        _ => return false,
+    }
+    true
 }
 
 impl IntoIterator for DhcpOptions {
     type Item = (OptionCode, DhcpOption);
-    type IntoIter = std::collections::hash_map::IntoIter<OptionCode, DhcpOption>;
+    type IntoIter = btree_map::IntoIter<OptionCode, DhcpOption>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
@@ -171,24 +996,167 @@ impl FromIterator<DhcpOption> for DhcpOptions {
         DhcpOptions(
             iter.into_iter()
                 .map(|opt| ((&opt).into(), opt))
-                .collect::<HashMap<OptionCode, DhcpOption>>(),
+                .collect::<BTreeMap<OptionCode, DhcpOption>>(),
         )
     }
 }
 
+impl Extend<DhcpOption> for DhcpOptions {
+    fn extend<T: IntoIterator<Item = DhcpOption>>(&mut self, iter: T) {
+        for opt in iter {
+            self.insert(opt);
+        }
+    }
+}
+
+impl Extend<(OptionCode, DhcpOption)> for DhcpOptions {
+    fn extend<T: IntoIterator<Item = (OptionCode, DhcpOption)>>(&mut self, iter: T) {
+        self.0.extend(iter)
+    }
+}
+
 impl FromIterator<(OptionCode, DhcpOption)> for DhcpOptions {
     fn from_iter<T: IntoIterator<Item = (OptionCode, DhcpOption)>>(iter: T) -> Self {
-        DhcpOptions(iter.into_iter().collect::<HashMap<_, _>>())
+        DhcpOptions(iter.into_iter().collect::<BTreeMap<_, _>>())
     }
 }
 
-impl Decodable for DhcpOptions {
-    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
-        // represented as a vector in the actual message
-        let mut opts = HashMap::new();
+impl DhcpOptions {
+    /// Decode options, also returning the original wire bytes of each option.
+    /// Useful for finding where a re-encoded message diverges from the input.
+    /// ```
+    /// # use dhcproto::{Decoder, v4::{DhcpOptions, OptionCode}};
+    /// let bytes = [1, 4, 255, 255, 255, 0, 0, 0, 255];
+    /// let (opts, raw) = DhcpOptions::decode_with_raw(&mut Decoder::new(&bytes))?;
+    /// assert_eq!(opts.len(), 1);
+    /// assert_eq!(raw.get(OptionCode::SubnetMask), Some(&bytes[..6]));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_with_raw<'a>(decoder: &mut Decoder<'a>) -> DecodeResult<(Self, RawOptions<'a>)> {
+        let start = decoder.remaining();
+        let mut raw = BTreeMap::new();
+        let opts = Self::decode_inner(
+            decoder,
+            false,
+            |code, bytes| {
+                raw.insert(code, bytes);
+            },
+            keep_unknown,
+        )?;
+        let bytes = &start[..start.len() - decoder.remaining().len()];
+        Ok((opts, RawOptions { opts: raw, bytes }))
+    }
+
+    /// Decode options, returning [`DecodeError::DuplicateOption`] if an option
+    /// that must appear at most once is repeated (see
+    /// [`OptionCode::is_singleton`]). The default, lenient, decoding keeps the
+    /// first instance of a repeated singleton & ignores the rest.
+    /// ```
+    /// # use dhcproto::{Decodable, Decoder, v4::{DhcpOptions, MessageType}};
+    /// // message type appears twice
+    /// let buf = [53, 1, 1, 12, 1, b'a', 53, 1, 3, 255];
+    /// assert!(DhcpOptions::decode_strict(&mut Decoder::new(&buf)).is_err());
+    /// let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+    /// assert_eq!(opts.msg_type(), Some(MessageType::Discover));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_strict(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Self::decode_inner(decoder, true, |_, _| {}, keep_unknown)
+    }
+
+    /// Decode options until the End option, the end of the buffer or an error,
+    /// returning the options decoded so far along with the error. On error
+    /// the decoder is left at the start of the option that failed
+    /// ```
+    /// # use dhcproto::{Decoder, v4::{DhcpOptions, MessageType}};
+    /// // message type, then a truncated hostname
+    /// let buf = [53, 1, 1, 12, 5, b'a'];
+    /// let mut decoder = Decoder::new(&buf);
+    /// let (opts, err) = DhcpOptions::decode_partial(&mut decoder);
+    /// assert_eq!(opts.msg_type(), Some(MessageType::Discover));
+    /// assert!(err.is_some());
+    /// assert_eq!(decoder.buffer(), &[12, 5, b'a']);
+    /// ```
+    pub fn decode_partial(decoder: &mut Decoder<'_>) -> (Self, Option<DecodeError>) {
+        Self::decode_until_err(decoder, false, OptionsMode::Dhcp, |_, _| {}, keep_unknown)
+    }
+
+    /// Decode options, calling `on_unknown` with the code & value of each
+    /// option that isn't modeled by [`DhcpOption`] as it's encountered. Options
+    /// it returns [`KeepOrDrop::Drop`] for are left out of the result
+    /// ```
+    /// # use dhcproto::{Decoder, v4::{DhcpOptions, KeepOrDrop, MessageType}};
+    /// let buf = [53, 1, 1, 224, 2, 0xde, 0xad, 225, 1, 0, 255];
+    /// let mut seen = Vec::new();
+    /// let opts = DhcpOptions::decode_with_unknown(&mut Decoder::new(&buf), |code, data| {
+    ///     seen.push((code, data.len()));
+    ///     if code == 224 { KeepOrDrop::Keep } else { KeepOrDrop::Drop }
+    /// })?;
+    /// assert_eq!(seen, [(224, 2), (225, 1)]);
+    /// assert_eq!(opts.len(), 2);
+    /// assert_eq!(opts.msg_type(), Some(MessageType::Discover));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_with_unknown(
+        decoder: &mut Decoder<'_>,
+        on_unknown: impl FnMut(u8, &[u8]) -> KeepOrDrop,
+    ) -> DecodeResult<Self> {
+        Self::decode_inner(decoder, false, |_, _| {}, on_unknown)
+    }
+
+    /// Decode BOOTP vendor extensions (RFC 1497), where the DHCP extensions
+    /// are read as [`DhcpOption::Unknown`], see [`OptionsMode::Bootp`]
+    pub(crate) fn decode_vendor_extensions(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Self::decode_inner_as(decoder, false, OptionsMode::Bootp, |_, _| {}, keep_unknown)
+    }
+
+    fn decode_inner<'a>(
+        decoder: &mut Decoder<'a>,
+        strict: bool,
+        on_raw: impl FnMut(OptionCode, &'a [u8]),
+        on_unknown: impl FnMut(u8, &[u8]) -> KeepOrDrop,
+    ) -> DecodeResult<Self> {
+        Self::decode_inner_as(decoder, strict, OptionsMode::Dhcp, on_raw, on_unknown)
+    }
+
+    fn decode_inner_as<'a>(
+        decoder: &mut Decoder<'a>,
+        strict: bool,
+        mode: OptionsMode,
+        on_raw: impl FnMut(OptionCode, &'a [u8]),
+        on_unknown: impl FnMut(u8, &[u8]) -> KeepOrDrop,
+    ) -> DecodeResult<Self> {
         // should we error the whole parser if we fail to parse an
-        // option or just stop parsing options? -- here we will just stop
-        while let Ok(opt) = DhcpOption::decode(decoder) {
+        // option or just stop parsing options? -- here we will just stop,
+        // unless strict decoding found a duplicate
+        match Self::decode_until_err(decoder, strict, mode, on_raw, on_unknown) {
+            (_, Some(err @ DecodeError::DuplicateOption { .. })) => Err(err),
+            (opts, _) => Ok(opts),
+        }
+    }
+
+    fn decode_until_err<'a>(
+        decoder: &mut Decoder<'a>,
+        strict: bool,
+        mode: OptionsMode,
+        mut on_raw: impl FnMut(OptionCode, &'a [u8]),
+        mut on_unknown: impl FnMut(u8, &[u8]) -> KeepOrDrop,
+    ) -> (Self, Option<DecodeError>) {
+        // represented as a vector in the actual message
+        let mut opts = BTreeMap::new();
+        let mut start = decoder.remaining();
+        // running out of bytes on an option boundary (no End) isn't an error
+        while !decoder.buffer().is_empty() {
+            let opt = match DhcpOption::decode_as(decoder, mode) {
+                Ok(opt) => opt,
+                Err(err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(remaining = start.len(), "failed to decode option: {}", err);
+                    decoder.rewind(start);
+                    let err = err.in_option(start[0], decoder.offset());
+                    return (DhcpOptions(opts), Some(err));
+                }
+            };
             // we throw away PAD bytes here
             match opt {
                 DhcpOption::End => {
@@ -196,37 +1164,129 @@ impl Decodable for DhcpOptions {
                 }
                 DhcpOption::Pad => {}
                 _ => {
-                    opts.insert(OptionCode::from(&opt), opt);
+                    let code = OptionCode::from(&opt);
+                    let end = decoder.remaining();
+                    let raw = &start[..start.len() - end.len()];
+                    // len includes the code & length bytes of every instance
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(code = u8::from(code), len = raw.len(), "decoded option");
+                    if code.is_singleton() {
+                        let repeated = opts.contains_key(&code) || instances(raw) > 1;
+                        if repeated && strict {
+                            decoder.rewind(start);
+                            return (
+                                DhcpOptions(opts),
+                                Some(DecodeError::DuplicateOption { code }),
+                            );
+                        }
+                        if opts.contains_key(&code) {
+                            // keep the first instance
+                            start = end;
+                            continue;
+                        }
+                    }
+                    if let DhcpOption::Unknown(unknown) = &opt {
+                        if on_unknown(unknown.code, unknown.data()) == KeepOrDrop::Drop {
+                            start = end;
+                            continue;
+                        }
+                    }
+                    on_raw(code, raw);
+                    opts.insert(code, opt);
                 }
             }
+            start = decoder.remaining();
         }
-        Ok(DhcpOptions(opts))
+        (DhcpOptions(opts), None)
+    }
+}
+
+impl Decodable for DhcpOptions {
+    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Self::decode_inner(decoder, false, |_, _| {}, keep_unknown)
+    }
+}
+
+/// Returned from the callback passed to [`DhcpOptions::decode_with_unknown`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum KeepOrDrop {
+    /// keep the option
+    Keep,
+    /// leave the option out of the decoded options
+    Drop,
+}
+
+fn keep_unknown(_: u8, _: &[u8]) -> KeepOrDrop {
+    KeepOrDrop::Keep
+}
+
+/// number of consecutive instances of an option in its raw span
+fn instances(raw: &[u8]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while let Some(len) = raw.get(i + 1) {
+        count += 1;
+        i += 2 + *len as usize;
+    }
+    count
+}
+
+/// The original bytes of each decoded option, borrowed from the input buffer.
+/// Each span includes the code & length bytes, and covers all consecutive
+/// instances of an option that was split according to RFC 3396.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RawOptions<'a> {
+    opts: BTreeMap<OptionCode, &'a [u8]>,
+    bytes: &'a [u8],
+}
+
+impl<'a> RawOptions<'a> {
+    /// get the raw bytes of an option
+    pub fn get(&self, code: OptionCode) -> Option<&'a [u8]> {
+        self.opts.get(&code).copied()
+    }
+    /// iterate over the raw options
+    pub fn iter(&self) -> impl Iterator<Item = (&OptionCode, &&'a [u8])> {
+        self.opts.iter()
+    }
+    /// number of options captured
+    pub fn len(&self) -> usize {
+        self.opts.len()
+    }
+    /// returns true if no options were captured
+    pub fn is_empty(&self) -> bool {
+        self.opts.is_empty()
+    }
+    /// The whole options region exactly as it appeared in the input, from the
+    /// first option up to & including `End`, with any `Pad` bytes in between.
+    /// A transparent relay can forward these verbatim rather than
+    /// re-encoding
+    /// ```
+    /// # use dhcproto::{Decoder, v4::DhcpOptions};
+    /// // trailing padding after `End` isn't part of the options
+    /// let bytes = [53, 1, 1, 0, 0, 12, 1, b'a', 255, 0, 0];
+    /// let (_, raw) = DhcpOptions::decode_with_raw(&mut Decoder::new(&bytes))?;
+    /// assert_eq!(raw.options_bytes(), &bytes[..9]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn options_bytes(&self) -> &'a [u8] {
+        self.bytes
     }
 }
 
+/// Options can be encoded on their own, without the fixed message header.
+/// The options are written followed by `End`, with `RelayAgentInformation`
+/// placed last. An empty set of options encodes to nothing.
+/// ```
+/// # use dhcproto::{Encodable, v4::{DhcpOption, DhcpOptions, MessageType}};
+/// let mut opts = DhcpOptions::new();
+/// opts.insert(DhcpOption::MessageType(MessageType::Discover));
+/// assert_eq!(opts.to_vec()?, vec![53, 1, 1, 255]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
 impl Encodable for DhcpOptions {
     fn encode(&self, e: &mut Encoder<'_>) -> EncodeResult<()> {
-        if self.0.is_empty() {
-            Ok(())
-        } else {
-            // encode all opts adding the `End` afterwards
-            // sum all bytes written
-            match self.get(OptionCode::RelayAgentInformation) {
-                // agent info must be placed last before `End`
-                Some(agent_info) => self
-                    .0
-                    .iter()
-                    .filter(|opt| *opt.0 != OptionCode::RelayAgentInformation)
-                    .chain(iter::once((&OptionCode::RelayAgentInformation, agent_info)))
-                    .chain(iter::once((&OptionCode::End, &DhcpOption::End)))
-                    .try_for_each(|(_, opt)| opt.encode(e)),
-                None => self
-                    .0
-                    .iter()
-                    .chain(iter::once((&OptionCode::End, &DhcpOption::End)))
-                    .try_for_each(|(_, opt)| opt.encode(e)),
-            }
-        }
+        self.encode_ordered(&[], e)
     }
 }
 
@@ -276,10 +1336,16 @@ pub enum OptionCode {
     IpForwarding,
     /// 20 Non-local source routing
     NonLocalSrcRouting,
+    /// 21 Policy Filter
+    PolicyFilter,
     /// 22 Max Datagram reassembly size
     MaxDatagramSize,
     /// 23 Ip TTL
     DefaultIpTtl,
+    /// 24 Path MTU Aging Timeout
+    PathMtuAgingTimeout,
+    /// 25 Path MTU Plateau Table
+    PathMtuPlateauTable,
     /// 26 Interface MTU
     InterfaceMtu,
     /// 27 All Subnets Local
@@ -350,10 +1416,20 @@ pub enum OptionCode {
     ClassIdentifier,
     /// 61 Client Identifier
     ClientIdentifier,
+    /// 68 Mobile IP Home Agent
+    MobileIpHomeAgent,
+    /// 69 SMTP Server
+    SmtpServer,
+    /// 70 POP3 Server
+    Pop3Server,
+    /// 71 NNTP Server
+    NntpServer,
+    /// 77 User Class - <https://www.rfc-editor.org/rfc/rfc3004.html>
+    UserClass,
     /// 80 Rapid Commit - <https://www.rfc-editor.org/rfc/rfc4039.html>
     RapidCommit,
     /// 81 FQDN - <https://datatracker.ietf.org/doc/html/rfc4702>
-    // ClientFQDN(),
+    ClientFQDN,
     /// 82 Relay Agent Information
     RelayAgentInformation,
     /// 91 client-last-transaction-time - <https://www.rfc-editor.org/rfc/rfc4388.html#section-6.1>
@@ -366,12 +1442,20 @@ pub enum OptionCode {
     ClientNetworkInterface,
     /// 97 Client Machine Identifier - <https://www.rfc-editor.org/rfc/rfc4578.html>
     ClientMachineIdentifier,
+    /// 100 POSIX Timezone - <https://www.rfc-editor.org/rfc/rfc4833.html>
+    PosixTimezone,
+    /// 101 TZ Database Timezone - <https://www.rfc-editor.org/rfc/rfc4833.html>
+    TzDatabaseTimezone,
     /// 114 Captive portal - <https://datatracker.ietf.org/doc/html/rfc8910>
     CaptivePortal,
+    /// 116 Auto-Configure - <https://www.rfc-editor.org/rfc/rfc2563.html>
+    AutoConfigure,
     /// 118 Subnet option - <https://datatracker.ietf.org/doc/html/rfc3011>
     SubnetSelection,
     /// 119 Domain Search - <https://www.rfc-editor.org/rfc/rfc3397.html>
     DomainSearch,
+    /// 121 Classless Static Route - <https://www.rfc-editor.org/rfc/rfc3442.html>
+    ClasslessStaticRoute,
     /// 151 status-code - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.2>
     StatusCode,
     /// 152 - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.3>
@@ -386,12 +1470,191 @@ pub enum OptionCode {
     DhcpState,
     /// 157 - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.8>
     DataSource,
+    /// 209 PXELINUX Configuration File - <https://www.rfc-editor.org/rfc/rfc5071.html#section-5>
+    PxeLinuxConfigFile,
+    /// 210 PXELINUX Path Prefix - <https://www.rfc-editor.org/rfc/rfc5071.html#section-6>
+    PxeLinuxPathPrefix,
+    /// 211 PXELINUX Reboot Time - <https://www.rfc-editor.org/rfc/rfc5071.html#section-7>
+    PxeLinuxRebootTime,
     /// Unknown option
     Unknown(u8),
     /// 255 End
     End,
 }
 
+impl OptionCode {
+    /// standardized name of the option code, as listed in the IANA
+    /// "BOOTP Vendor Extensions and DHCP Options" registry
+    pub(crate) fn name(&self) -> &'static str {
+        use OptionCode::*;
+        match self {
+            Pad => "Pad",
+            SubnetMask => "Subnet Mask",
+            TimeOffset => "Time Offset",
+            Router => "Router",
+            TimeServer => "Time Server",
+            NameServer => "Name Server",
+            DomainNameServer => "Domain Name Server",
+            LogServer => "Log Server",
+            QuoteServer => "Quote Server",
+            LprServer => "LPR Server",
+            ImpressServer => "Impress Server",
+            ResourceLocationServer => "Resource Location Server",
+            Hostname => "Host Name",
+            BootFileSize => "Boot File Size",
+            MeritDumpFile => "Merit Dump File",
+            DomainName => "Domain Name",
+            SwapServer => "Swap Server",
+            RootPath => "Root Path",
+            ExtensionsPath => "Extensions Path",
+            IpForwarding => "IP Forwarding",
+            NonLocalSrcRouting => "Non-Local Source Routing",
+            PolicyFilter => "Policy Filter",
+            MaxDatagramSize => "Max Datagram Reassembly Size",
+            DefaultIpTtl => "Default IP TTL",
+            PathMtuAgingTimeout => "Path MTU Aging Timeout",
+            PathMtuPlateauTable => "Path MTU Plateau Table",
+            InterfaceMtu => "Interface MTU",
+            AllSubnetsLocal => "All Subnets Local",
+            BroadcastAddr => "Broadcast Address",
+            PerformMaskDiscovery => "Perform Mask Discovery",
+            MaskSupplier => "Mask Supplier",
+            PerformRouterDiscovery => "Perform Router Discovery",
+            RouterSolicitationAddr => "Router Solicitation Address",
+            StaticRoutingTable => "Static Route",
+            ArpCacheTimeout => "ARP Cache Timeout",
+            EthernetEncapsulation => "Ethernet Encapsulation",
+            DefaultTcpTtl => "Default TCP TTL",
+            TcpKeepaliveInterval => "TCP Keepalive Interval",
+            TcpKeepaliveGarbage => "TCP Keepalive Garbage",
+            NISDomain => "NIS Domain",
+            NIS => "NIS Servers",
+            NTPServers => "NTP Servers",
+            VendorExtensions => "Vendor Specific Information",
+            NetBiosNameServers => "NetBIOS Name Server",
+            NetBiosDatagramDistributionServer => "NetBIOS Datagram Distribution Server",
+            NetBiosNodeType => "NetBIOS Node Type",
+            NetBiosScope => "NetBIOS Scope",
+            XFontServer => "X Window Font Server",
+            XDisplayManager => "X Window Display Manager",
+            RequestedIpAddress => "Requested IP Address",
+            AddressLeaseTime => "IP Address Lease Time",
+            OptionOverload => "Option Overload",
+            MessageType => "DHCP Message Type",
+            ServerIdentifier => "Server Identifier",
+            ParameterRequestList => "Parameter Request List",
+            Message => "Message",
+            MaxMessageSize => "Maximum DHCP Message Size",
+            Renewal => "Renewal (T1) Time",
+            Rebinding => "Rebinding (T2) Time",
+            ClassIdentifier => "Vendor Class Identifier",
+            ClientIdentifier => "Client Identifier",
+            MobileIpHomeAgent => "Mobile IP Home Agent",
+            SmtpServer => "SMTP Server",
+            Pop3Server => "POP3 Server",
+            NntpServer => "NNTP Server",
+            UserClass => "User Class",
+            RapidCommit => "Rapid Commit",
+            ClientFQDN => "Client FQDN",
+            RelayAgentInformation => "Relay Agent Information",
+            ClientLastTransactionTime => "Client Last Transaction Time",
+            AssociatedIp => "Associated IP",
+            ClientSystemArchitecture => "Client System Architecture",
+            ClientNetworkInterface => "Client Network Interface",
+            ClientMachineIdentifier => "Client Machine Identifier",
+            PosixTimezone => "POSIX Timezone",
+            TzDatabaseTimezone => "TZ Database Timezone",
+            CaptivePortal => "Captive Portal",
+            AutoConfigure => "Auto-Config",
+            SubnetSelection => "Subnet Selection",
+            DomainSearch => "Domain Search",
+            ClasslessStaticRoute => "Classless Static Route",
+            StatusCode => "Status Code",
+            BaseTime => "Base Time",
+            StartTimeOfState => "Start Time Of State",
+            QueryStartTime => "Query Start Time",
+            QueryEndTime => "Query End Time",
+            DhcpState => "DHCP State",
+            DataSource => "Data Source",
+            PxeLinuxConfigFile => "PXELINUX Configuration File",
+            PxeLinuxPathPrefix => "PXELINUX Path Prefix",
+            PxeLinuxRebootTime => "PXELINUX Reboot Time",
+            Unknown(_) => "Unknown",
+            End => "End",
+        }
+    }
+}
+
+impl OptionCode {
+    /// whether the option is one this crate decodes into a typed
+    /// [`DhcpOption`], rather than [`DhcpOption::Unknown`]
+    /// ```
+    /// # use dhcproto::v4::OptionCode;
+    /// assert!(OptionCode::Router.is_known());
+    /// assert!(!OptionCode::Unknown(224).is_known());
+    /// ```
+    pub fn is_known(&self) -> bool {
+        !matches!(self, OptionCode::Unknown(_))
+    }
+
+    /// whether the option must appear at most once in a message. These are
+    /// fixed length options, which can't be split according to RFC 3396
+    /// ```
+    /// # use dhcproto::v4::OptionCode;
+    /// assert!(OptionCode::MessageType.is_singleton());
+    /// assert!(!OptionCode::Router.is_singleton());
+    /// ```
+    pub fn is_singleton(&self) -> bool {
+        use OptionCode::*;
+        matches!(
+            self,
+            SubnetMask
+                | TimeOffset
+                | BootFileSize
+                | SwapServer
+                | IpForwarding
+                | NonLocalSrcRouting
+                | MaxDatagramSize
+                | DefaultIpTtl
+                | PathMtuAgingTimeout
+                | InterfaceMtu
+                | AllSubnetsLocal
+                | BroadcastAddr
+                | PerformMaskDiscovery
+                | MaskSupplier
+                | PerformRouterDiscovery
+                | RouterSolicitationAddr
+                | ArpCacheTimeout
+                | EthernetEncapsulation
+                | DefaultTcpTtl
+                | TcpKeepaliveInterval
+                | TcpKeepaliveGarbage
+                | NetBiosNodeType
+                | RequestedIpAddress
+                | AddressLeaseTime
+                | OptionOverload
+                | MessageType
+                | ServerIdentifier
+                | MaxMessageSize
+                | Renewal
+                | Rebinding
+                | RapidCommit
+                | ClientLastTransactionTime
+                | ClientSystemArchitecture
+                | ClientNetworkInterface
+                | AutoConfigure
+                | SubnetSelection
+                | BaseTime
+                | StartTimeOfState
+                | QueryStartTime
+                | QueryEndTime
+                | DhcpState
+                | DataSource
+                | PxeLinuxRebootTime
+        )
+    }
+}
+
 impl PartialOrd for OptionCode {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -400,7 +1663,10 @@ impl PartialOrd for OptionCode {
 
 impl Ord for OptionCode {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        u8::from(*self).cmp(&u8::from(*other))
+        // `Unknown(n)` can share a number with a known code, use it to break
+        // ties so the ordering is consistent with `Eq`
+        let key = |code: &Self| (u8::from(*code), !code.is_known());
+        key(self).cmp(&key(other))
     }
 }
 
@@ -429,8 +1695,11 @@ impl From<u8> for OptionCode {
             18 => ExtensionsPath,
             19 => IpForwarding,
             20 => NonLocalSrcRouting,
+            21 => PolicyFilter,
             22 => MaxDatagramSize,
             23 => DefaultIpTtl,
+            24 => PathMtuAgingTimeout,
+            25 => PathMtuPlateauTable,
             26 => InterfaceMtu,
             27 => AllSubnetsLocal,
             28 => BroadcastAddr,
@@ -466,16 +1735,26 @@ impl From<u8> for OptionCode {
             59 => Rebinding,
             60 => ClassIdentifier,
             61 => ClientIdentifier,
+            68 => MobileIpHomeAgent,
+            69 => SmtpServer,
+            70 => Pop3Server,
+            71 => NntpServer,
+            77 => UserClass,
             80 => RapidCommit,
+            81 => ClientFQDN,
             82 => RelayAgentInformation,
             91 => ClientLastTransactionTime,
             92 => AssociatedIp,
             93 => ClientSystemArchitecture,
             94 => ClientNetworkInterface,
             97 => ClientMachineIdentifier,
+            100 => PosixTimezone,
+            101 => TzDatabaseTimezone,
             114 => CaptivePortal,
+            116 => AutoConfigure,
             118 => SubnetSelection,
             119 => DomainSearch,
+            121 => ClasslessStaticRoute,
             151 => StatusCode,
             152 => BaseTime,
             153 => StartTimeOfState,
@@ -483,6 +1762,9 @@ impl From<u8> for OptionCode {
             155 => QueryEndTime,
             156 => DhcpState,
             157 => DataSource,
+            209 => PxeLinuxConfigFile,
+            210 => PxeLinuxPathPrefix,
+            211 => PxeLinuxRebootTime,
             255 => End,
             // TODO: implement more
             n => Unknown(n),
@@ -515,8 +1797,11 @@ impl From<OptionCode> for u8 {
             ExtensionsPath => 18,
             IpForwarding => 19,
             NonLocalSrcRouting => 20,
+            PolicyFilter => 21,
             MaxDatagramSize => 22,
             DefaultIpTtl => 23,
+            PathMtuAgingTimeout => 24,
+            PathMtuPlateauTable => 25,
             InterfaceMtu => 26,
             AllSubnetsLocal => 27,
             BroadcastAddr => 28,
@@ -552,16 +1837,26 @@ impl From<OptionCode> for u8 {
             Rebinding => 59,
             ClassIdentifier => 60,
             ClientIdentifier => 61,
+            MobileIpHomeAgent => 68,
+            SmtpServer => 69,
+            Pop3Server => 70,
+            NntpServer => 71,
+            UserClass => 77,
             RapidCommit => 80,
+            ClientFQDN => 81,
             RelayAgentInformation => 82,
             ClientLastTransactionTime => 91,
             AssociatedIp => 92,
             ClientSystemArchitecture => 93,
             ClientNetworkInterface => 94,
             ClientMachineIdentifier => 97,
+            PosixTimezone => 100,
+            TzDatabaseTimezone => 101,
             CaptivePortal => 114,
+            AutoConfigure => 116,
             SubnetSelection => 118,
             DomainSearch => 119,
+            ClasslessStaticRoute => 121,
             StatusCode => 151,
             BaseTime => 152,
             StartTimeOfState => 153,
@@ -569,6 +1864,9 @@ impl From<OptionCode> for u8 {
             QueryEndTime => 155,
             DhcpState => 156,
             DataSource => 157,
+            PxeLinuxConfigFile => 209,
+            PxeLinuxPathPrefix => 210,
+            PxeLinuxRebootTime => 211,
             End => 255,
             // TODO: implement more
             Unknown(n) => n,
@@ -578,7 +1876,7 @@ impl From<OptionCode> for u8 {
 
 /// DHCP Options
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DhcpOption {
     /// 0 Padding
     Pad,
@@ -622,15 +1920,17 @@ pub enum DhcpOption {
     IpForwarding(bool),
     /// 20 Non-local source routing
     NonLocalSrcRouting(bool),
-    // TODO: Policy filter is a varlen 8 bit ipv4 / 32-bit subnetmask
-    // need to think of a good way to represent this Vec<(Ipv4Addr, Ipv4Addr)>?
-    // can it be changed into Ipv4Net and a prefix mask field?
-    // /// 21 Policy Filter
-    // PolicyFilter(Vec<Ipv4Net>),
+    /// 21 Policy Filter, (address, mask) pairs of allowed destinations for
+    /// non-local source routes
+    PolicyFilter(Vec<(Ipv4Addr, Ipv4Addr)>),
     /// 22 Max Datagram reassembly size
     MaxDatagramSize(u16),
     /// 23 Ip TTL
     DefaultIpTtl(u8),
+    /// 24 Path MTU Aging Timeout, in seconds
+    PathMtuAgingTimeout(u32),
+    /// 25 Path MTU Plateau Table, MTU sizes ordered from smallest to largest
+    PathMtuPlateauTable(Vec<u16>),
     /// 26 Interface MTU
     InterfaceMtu(u16),
     /// 27 All Subnets Local
@@ -701,8 +2001,20 @@ pub enum DhcpOption {
     ClassIdentifier(Vec<u8>),
     /// 61 Client Identifier
     ClientIdentifier(Vec<u8>),
+    /// 68 Mobile IP Home Agent, may be empty
+    MobileIpHomeAgent(Vec<Ipv4Addr>),
+    /// 69 SMTP Server
+    SmtpServer(Vec<Ipv4Addr>),
+    /// 70 POP3 Server
+    Pop3Server(Vec<Ipv4Addr>),
+    /// 71 NNTP Server
+    NntpServer(Vec<Ipv4Addr>),
+    /// 77 User Class - <https://www.rfc-editor.org/rfc/rfc3004.html>
+    UserClass(user_class::UserClass),
     /// 80 Rapid Commit - <https://www.rfc-editor.org/rfc/rfc4039.html>
     RapidCommit,
+    /// 81 Client FQDN - <https://datatracker.ietf.org/doc/html/rfc4702>
+    ClientFQDN(fqdn::ClientFQDN),
     /// 82 Relay Agent Information - <https://datatracker.ietf.org/doc/html/rfc3046>
     RelayAgentInformation(relay::RelayAgentInformation),
     /// 91 client-last-transaction-time - <https://www.rfc-editor.org/rfc/rfc4388.html#section-6.1>
@@ -715,12 +2027,20 @@ pub enum DhcpOption {
     ClientNetworkInterface(u8, u8, u8),
     /// 97 Client Machine Identifier - <https://www.rfc-editor.org/rfc/rfc4578.html>
     ClientMachineIdentifier(Vec<u8>),
+    /// 100 POSIX Timezone, a POSIX TZ string like `EST5EDT4,M3.2.0/02:00,M11.1.0/02:00` - <https://www.rfc-editor.org/rfc/rfc4833.html>
+    PosixTimezone(String),
+    /// 101 TZ Database Timezone, a name like `Europe/Zurich` - <https://www.rfc-editor.org/rfc/rfc4833.html>
+    TzDatabaseTimezone(String),
     /// 114 Captive Portal - <https://datatracker.ietf.org/doc/html/rfc8910>
     CaptivePortal(url::Url),
+    /// 116 Auto-Configure - <https://www.rfc-editor.org/rfc/rfc2563.html>
+    AutoConfigure(AutoConfig),
     /// 118 Subnet selection - <https://datatracker.ietf.org/doc/html/rfc3011>
     SubnetSelection(Ipv4Addr),
     /// 119 Domain Search - <https://www.rfc-editor.org/rfc/rfc3397.html>
     DomainSearch(Vec<Domain>),
+    /// 121 Classless Static Route - <https://www.rfc-editor.org/rfc/rfc3442.html>
+    ClasslessStaticRoute(Vec<classless::ClasslessRoute>),
     /// 151 status-code - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.2>
     BulkLeaseQueryStatusCode(bulk_query::Code, String),
     /// 152 - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.3>
@@ -735,6 +2055,12 @@ pub enum DhcpOption {
     BulkLeaseQueryDhcpState(bulk_query::QueryState),
     /// 157 - <https://www.rfc-editor.org/rfc/rfc6926.html#section-6.2.8>
     BulkLeaseQueryDataSource(bulk_query::DataSourceFlags),
+    /// 209 PXELINUX Configuration File - <https://www.rfc-editor.org/rfc/rfc5071.html#section-5>
+    PxeLinuxConfigFile(String),
+    /// 210 PXELINUX Path Prefix - <https://www.rfc-editor.org/rfc/rfc5071.html#section-6>
+    PxeLinuxPathPrefix(String),
+    /// 211 PXELINUX Reboot Time in seconds - <https://www.rfc-editor.org/rfc/rfc5071.html#section-7>
+    PxeLinuxRebootTime(u32),
     /// Unknown option
     Unknown(UnknownOption),
     /// 255 End
@@ -819,17 +2145,18 @@ impl From<Architecture> for u16 {
     }
 }
 
-/// NetBIOS allows several different node types
+/// NetBIOS allows several different node types, used by the NetBIOS over
+/// TCP/IP Node Type option (46)
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NodeType {
-    /// Broadcast
+    /// 0x1 Broadcast
     B,
-    /// Peer-to-peer
+    /// 0x2 Peer-to-peer
     P,
-    /// Mixed (B & P)
+    /// 0x4 Mixed (B & P)
     M,
-    /// Hybrid (P & B)
+    /// 0x8 Hybrid (P & B)
     H,
     /// Unknown
     Unknown(u8),
@@ -861,6 +2188,41 @@ impl From<NodeType> for u8 {
     }
 }
 
+/// Value of the Auto-Configure option (116), whether a client without a lease
+/// may self-assign an IPv4 link-local address
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoConfig {
+    /// 0 DoNotAutoConfigure
+    DoNotAutoConfigure,
+    /// 1 AutoConfigure
+    AutoConfigure,
+    /// Unknown
+    Unknown(u8),
+}
+
+impl From<u8> for AutoConfig {
+    fn from(n: u8) -> Self {
+        use AutoConfig::*;
+        match n {
+            0 => DoNotAutoConfigure,
+            1 => AutoConfigure,
+            _ => Unknown(n),
+        }
+    }
+}
+
+impl From<AutoConfig> for u8 {
+    fn from(n: AutoConfig) -> Self {
+        use AutoConfig::*;
+        match n {
+            DoNotAutoConfigure => 0,
+            AutoConfigure => 1,
+            Unknown(n) => n,
+        }
+    }
+}
+
 #[inline]
 fn decode_inner(
     code: OptionCode,
@@ -890,8 +2252,11 @@ fn decode_inner(
         OptionCode::ExtensionsPath => ExtensionsPath(decoder.read_string(len)?),
         OptionCode::IpForwarding => IpForwarding(decoder.read_bool()?),
         OptionCode::NonLocalSrcRouting => NonLocalSrcRouting(decoder.read_bool()?),
+        OptionCode::PolicyFilter => PolicyFilter(decoder.read_pair_ipv4s(len)?),
         OptionCode::MaxDatagramSize => MaxDatagramSize(decoder.read_u16()?),
         OptionCode::DefaultIpTtl => DefaultIpTtl(decoder.read_u8()?),
+        OptionCode::PathMtuAgingTimeout => PathMtuAgingTimeout(decoder.read_u32()?),
+        OptionCode::PathMtuPlateauTable => PathMtuPlateauTable(decoder.read_u16s(len)?),
         OptionCode::InterfaceMtu => InterfaceMtu(decoder.read_u16()?),
         OptionCode::AllSubnetsLocal => AllSubnetsLocal(decoder.read_bool()?),
         OptionCode::BroadcastAddr => BroadcastAddr(decoder.read_ipv4(len)?),
@@ -902,7 +2267,7 @@ fn decode_inner(
         OptionCode::StaticRoutingTable => StaticRoutingTable(decoder.read_pair_ipv4s(len)?),
         OptionCode::ArpCacheTimeout => ArpCacheTimeout(decoder.read_u32()?),
         OptionCode::EthernetEncapsulation => EthernetEncapsulation(decoder.read_bool()?),
-        OptionCode::DefaultTcpTtl => DefaultIpTtl(decoder.read_u8()?),
+        OptionCode::DefaultTcpTtl => DefaultTcpTtl(decoder.read_u8()?),
         OptionCode::TcpKeepaliveInterval => TcpKeepaliveInterval(decoder.read_u32()?),
         OptionCode::TcpKeepaliveGarbage => TcpKeepaliveGarbage(decoder.read_bool()?),
         OptionCode::NISDomain => NISDomain(decoder.read_string(len)?),
@@ -914,6 +2279,7 @@ fn decode_inner(
             NetBiosDatagramDistributionServer(decoder.read_ipv4s(len)?)
         }
         OptionCode::NetBiosNodeType => NetBiosNodeType(decoder.read_u8()?.into()),
+        OptionCode::AutoConfigure => AutoConfigure(decoder.read_u8()?.into()),
         OptionCode::NetBiosScope => NetBiosScope(decoder.read_string(len)?),
         OptionCode::XFontServer => XFontServer(decoder.read_ipv4s(len)?),
         OptionCode::XDisplayManager => XDisplayManager(decoder.read_ipv4s(len)?),
@@ -935,12 +2301,25 @@ fn decode_inner(
         OptionCode::Rebinding => Rebinding(decoder.read_u32()?),
         OptionCode::ClassIdentifier => ClassIdentifier(decoder.read_slice(len)?.to_vec()),
         OptionCode::ClientIdentifier => ClientIdentifier(decoder.read_slice(len)?.to_vec()),
+        OptionCode::MobileIpHomeAgent => MobileIpHomeAgent(decoder.read_ipv4s(len)?),
+        OptionCode::SmtpServer => SmtpServer(decoder.read_ipv4s(len)?),
+        OptionCode::Pop3Server => Pop3Server(decoder.read_ipv4s(len)?),
+        OptionCode::NntpServer => NntpServer(decoder.read_ipv4s(len)?),
+        OptionCode::UserClass => UserClass(user_class::UserClass::decode(decoder)?),
         OptionCode::RapidCommit => {
             debug_assert!(len == 0);
             RapidCommit
         }
+        OptionCode::ClientFQDN => {
+            let value = decoder.read_slice(len)?;
+            // a malformed name is kept as is, rather than ending decoding
+            match decode_client_fqdn(value) {
+                Ok(fqdn) => ClientFQDN(fqdn),
+                Err(_) => Unknown(UnknownOption::new(code, value.to_vec())),
+            }
+        }
         OptionCode::RelayAgentInformation => {
-            let mut dec = Decoder::new(decoder.read_slice(len)?);
+            let mut dec = decoder.sub_decoder(len)?;
             RelayAgentInformation(relay::RelayAgentInformation::decode(&mut dec)?)
         }
         OptionCode::ClientLastTransactionTime => ClientLastTransactionTime(decoder.read_u32()?),
@@ -956,6 +2335,8 @@ fn decode_inner(
         OptionCode::ClientMachineIdentifier => {
             ClientMachineIdentifier(decoder.read_slice(len)?.to_vec())
         }
+        OptionCode::PosixTimezone => PosixTimezone(decoder.read_string(len)?),
+        OptionCode::TzDatabaseTimezone => TzDatabaseTimezone(decoder.read_string(len)?),
         OptionCode::CaptivePortal => CaptivePortal(decoder.read_str(len)?.parse()?),
         OptionCode::SubnetSelection => SubnetSelection(decoder.read_ipv4(len)?),
         OptionCode::DomainSearch => {
@@ -967,6 +2348,14 @@ fn decode_inner(
 
             DomainSearch(names)
         }
+        OptionCode::ClasslessStaticRoute => {
+            let value = decoder.read_slice(len)?;
+            // as for ClientFQDN, malformed routes are kept as is
+            match decode_classless_routes(value) {
+                Ok(routes) => ClasslessStaticRoute(routes),
+                Err(_) => Unknown(UnknownOption::new(code, value.to_vec())),
+            }
+        }
         OptionCode::StatusCode => {
             let code = decoder.read_u8()?.into();
             // len - 1 because code is included in length
@@ -993,6 +2382,9 @@ fn decode_inner(
         OptionCode::DataSource => {
             BulkLeaseQueryDataSource(bulk_query::DataSourceFlags::new(decoder.read_u8()?))
         }
+        OptionCode::PxeLinuxConfigFile => PxeLinuxConfigFile(decoder.read_string(len)?),
+        OptionCode::PxeLinuxPathPrefix => PxeLinuxPathPrefix(decoder.read_string(len)?),
+        OptionCode::PxeLinuxRebootTime => PxeLinuxRebootTime(decoder.read_u32()?),
         OptionCode::End => End,
         // not yet implemented
         OptionCode::Unknown(code) => {
@@ -1002,9 +2394,48 @@ fn decode_inner(
     })
 }
 
+/// decode the value of the Client FQDN option (81): flags, the 2 rcodes &
+/// the name
+fn decode_client_fqdn(value: &[u8]) -> DecodeResult<fqdn::ClientFQDN> {
+    let (flags, r1, r2, name) = match value {
+        [flags, r1, r2, name @ ..] => (fqdn::FqdnFlags::new(*flags), *r1, *r2, name),
+        _ => return Err(DecodeError::NotEnoughBytes),
+    };
+    let name = if name.is_empty() {
+        Name::new()
+    } else if flags.e() {
+        Name::read(&mut BinDecoder::new(name))?
+    } else {
+        // deprecated ASCII encoding
+        std::str::from_utf8(name)?.parse()?
+    };
+    let mut fqdn = fqdn::ClientFQDN::new(flags, Domain(name));
+    fqdn.set_r1(r1).set_r2(r2);
+    Ok(fqdn)
+}
+
+/// decode the value of the Classless Static Route option (121)
+fn decode_classless_routes(value: &[u8]) -> DecodeResult<Vec<classless::ClasslessRoute>> {
+    let mut decoder = Decoder::new(value);
+    let mut routes = Vec::new();
+    while !decoder.buffer().is_empty() {
+        routes.push(classless::ClasslessRoute::decode(&mut decoder)?);
+    }
+    Ok(routes)
+}
+
 impl Decodable for DhcpOption {
     #[inline]
     fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        Self::decode_as(decoder, OptionsMode::Dhcp)
+    }
+}
+
+impl DhcpOption {
+    /// decode an option, reading the codes `mode` gives no meaning to as
+    /// [`DhcpOption::Unknown`] without interpreting their value
+    #[inline]
+    fn decode_as(decoder: &mut Decoder<'_>, mode: OptionsMode) -> DecodeResult<Self> {
         #[derive(Debug)]
         struct Opt<'a> {
             code: u8,
@@ -1014,12 +2445,16 @@ impl Decodable for DhcpOption {
 
         impl<'a> Opt<'a> {
             #[inline]
-            fn as_option(&self) -> DecodeResult<DhcpOption> {
+            fn as_option(&self, mode: OptionsMode) -> DecodeResult<DhcpOption> {
                 let mut opt_decoder = Decoder::new(&self.buf);
-                let code = opt_decoder.read_u8()?.into();
+                let code = opt_decoder.read_u8()?;
                 let _len = opt_decoder.read_u8()?; // throw out potentially invalid len
 
-                decode_inner(code, opt_decoder.buffer().len(), &mut opt_decoder)
+                if !mode.is_typed(code) {
+                    let data = opt_decoder.buffer().to_vec();
+                    return Ok(DhcpOption::Unknown(UnknownOption { code, data }));
+                }
+                decode_inner(code.into(), opt_decoder.buffer().len(), &mut opt_decoder)
             }
             // can't implement Decodable b/c of lifetime issues
             fn decode(dec: &mut Decoder<'a>) -> DecodeResult<Self> {
@@ -1038,7 +2473,7 @@ impl Decodable for DhcpOption {
             match code.into() {
                 OptionCode::End => {
                     return match last {
-                        Some(prev) => prev.as_option(),
+                        Some(prev) => prev.as_option(mode),
                         None => {
                             decoder.read_u8()?;
                             Ok(End)
@@ -1047,7 +2482,7 @@ impl Decodable for DhcpOption {
                 }
                 OptionCode::Pad => {
                     return match last {
-                        Some(prev) => prev.as_option(),
+                        Some(prev) => prev.as_option(mode),
                         None => {
                             decoder.read_u8()?;
                             Ok(Pad)
@@ -1067,14 +2502,14 @@ impl Decodable for DhcpOption {
                         Some(prev) => {
                             // got different option, decode the one we've got
                             // need to stop here so we don't consume the next option's buffer
-                            return prev.as_option();
+                            return prev.as_option(mode);
                         }
                     });
                 }
             }
         }
         last.ok_or(crate::error::DecodeError::NotEnoughBytes)?
-            .as_option()
+            .as_option(mode)
     }
 }
 
@@ -1130,29 +2565,213 @@ pub fn encode_long_opt_bytes(
 /// x.push(68);
 /// x.extend(iter::repeat(Ipv4Addr::from([1,2,3,4])).map(|ip| u32::from(ip).to_be_bytes()).flatten().take(68));
 ///
-/// assert_eq!(buf, x);
-/// ```
-#[inline]
-pub fn encode_long_opt_chunks<'a, T, F>(
-    code: OptionCode,
-    factor: usize,
-    data: &[T],
-    f: F,
-    e: &mut Encoder<'a>,
-) -> EncodeResult<()>
-where
-    F: Fn(&T, &mut Encoder<'a>) -> EncodeResult<()>,
-{
-    // TODO: consider using `mem::size_of::<T>()` so we don't need factor
-    // although, we would need to make OptionCode repr(u8)
-    for chunk in data.chunks(u8::MAX as usize / factor) {
-        e.write_u8(code.into())?;
-        e.write_u8((chunk.len() * factor) as u8)?;
-        for thing in chunk {
-            f(thing, e)?;
+/// assert_eq!(buf, x);
+/// ```
+#[inline]
+pub fn encode_long_opt_chunks<'a, T, F>(
+    code: OptionCode,
+    factor: usize,
+    data: &[T],
+    f: F,
+    e: &mut Encoder<'a>,
+) -> EncodeResult<()>
+where
+    F: Fn(&T, &mut Encoder<'a>) -> EncodeResult<()>,
+{
+    // TODO: consider using `mem::size_of::<T>()` so we don't need factor
+    // although, we would need to make OptionCode repr(u8)
+    for chunk in data.chunks(u8::MAX as usize / factor) {
+        e.write_u8(code.into())?;
+        e.write_u8((chunk.len() * factor) as u8)?;
+        for thing in chunk {
+            f(thing, e)?;
+        }
+    }
+    Ok(())
+}
+
+impl DhcpOption {
+    /// number of bytes the option encodes to, including the code & length of
+    /// each instance when a long option is split
+    /// ```
+    /// # use dhcproto::{Encodable, v4::{DhcpOption, MessageType}};
+    /// let opt = DhcpOption::MessageType(MessageType::Discover);
+    /// assert_eq!(opt.encoded_len(), opt.to_vec()?.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        use DhcpOption::*;
+        match self {
+            Pad | End => 1,
+            RapidCommit => 2,
+            MobileIpHomeAgent(ips) if ips.is_empty() => 2,
+            SubnetMask(_)
+            | SwapServer(_)
+            | BroadcastAddr(_)
+            | RouterSolicitationAddr(_)
+            | RequestedIpAddress(_)
+            | ServerIdentifier(_)
+            | SubnetSelection(_)
+            | TimeOffset(_)
+            | PathMtuAgingTimeout(_)
+            | ArpCacheTimeout(_)
+            | TcpKeepaliveInterval(_)
+            | AddressLeaseTime(_)
+            | Renewal(_)
+            | Rebinding(_)
+            | ClientLastTransactionTime(_)
+            | BulkLeaseQueryBaseTime(_)
+            | BulkLeasQueryStartTimeOfState(_)
+            | BulkLeaseQueryQueryStartTime(_)
+            | BulkLeaseQueryQueryEndTime(_)
+            | PxeLinuxRebootTime(_) => 6,
+            TimeServer(ips)
+            | NameServer(ips)
+            | Router(ips)
+            | DomainNameServer(ips)
+            | LogServer(ips)
+            | QuoteServer(ips)
+            | LprServer(ips)
+            | ImpressServer(ips)
+            | ResourceLocationServer(ips)
+            | XFontServer(ips)
+            | XDisplayManager(ips)
+            | NIS(ips)
+            | NTPServers(ips)
+            | NetBiosNameServers(ips)
+            | NetBiosDatagramDistributionServer(ips)
+            | MobileIpHomeAgent(ips)
+            | SmtpServer(ips)
+            | Pop3Server(ips)
+            | NntpServer(ips)
+            | AssociatedIp(ips) => long_opt_chunks_len(4, ips.len()),
+            Hostname(s)
+            | MeritDumpFile(s)
+            | DomainName(s)
+            | ExtensionsPath(s)
+            | NISDomain(s)
+            | RootPath(s)
+            | NetBiosScope(s)
+            | Message(s)
+            | PxeLinuxConfigFile(s)
+            | PosixTimezone(s)
+            | TzDatabaseTimezone(s)
+            | PxeLinuxPathPrefix(s) => long_opt_bytes_len(s.len()),
+            BootFileSize(_)
+            | MaxDatagramSize(_)
+            | InterfaceMtu(_)
+            | MaxMessageSize(_)
+            | ClientSystemArchitecture(_) => 4,
+            IpForwarding(_)
+            | NonLocalSrcRouting(_)
+            | AllSubnetsLocal(_)
+            | PerformMaskDiscovery(_)
+            | MaskSupplier(_)
+            | PerformRouterDiscovery(_)
+            | EthernetEncapsulation(_)
+            | TcpKeepaliveGarbage(_)
+            | DefaultIpTtl(_)
+            | DefaultTcpTtl(_)
+            | OptionOverload(_)
+            | NetBiosNodeType(_)
+            | AutoConfigure(_)
+            | MessageType(_)
+            | BulkLeaseQueryDhcpState(_)
+            | BulkLeaseQueryDataSource(_) => 3,
+            ClientNetworkInterface(..) => 5,
+            StaticRoutingTable(pairs) | PolicyFilter(pairs) => long_opt_chunks_len(8, pairs.len()),
+            PathMtuPlateauTable(mtus) => long_opt_chunks_len(2, mtus.len()),
+            VendorExtensions(bytes)
+            | ClassIdentifier(bytes)
+            | ClientIdentifier(bytes)
+            | ClientMachineIdentifier(bytes) => long_opt_bytes_len(bytes.len()),
+            UserClass(class) => long_opt_bytes_len(class.raw().len()),
+            ParameterRequestList(codes) => long_opt_chunks_len(1, codes.len()),
+            RelayAgentInformation(relay) => long_opt_bytes_len(relay.encoded_len()),
+            CaptivePortal(url) => long_opt_bytes_len(url.as_str().len()),
+            BulkLeaseQueryStatusCode(_, msg) => long_opt_bytes_len(1 + msg.len()),
+            ClientFQDN(fqdn) => {
+                let name = &fqdn.domain().0;
+                let name_len = if name.num_labels() == 0 {
+                    0
+                } else if fqdn.flags().e() {
+                    name_wire_len(name)
+                } else {
+                    // counts the text form without allocating it
+                    let mut len = FmtLen(0);
+                    let _ = fmt::write(&mut len, format_args!("{}", name));
+                    len.0
+                };
+                long_opt_bytes_len(3 + name_len)
+            }
+            DomainSearch(names) => long_opt_bytes_len(domain_search_len(names)),
+            ClasslessStaticRoute(routes) => {
+                long_opt_bytes_len(routes.iter().map(|r| r.encoded_len()).sum())
+            }
+            Unknown(opt) => long_opt_bytes_len(opt.data.len()),
+        }
+    }
+}
+
+/// uncompressed wire length of `name`: a length byte per label plus the
+/// root label. `Name` guarantees labels fit in 63 bytes, so emitting it
+/// can't fail on length
+fn name_wire_len(name: &Name) -> usize {
+    name.iter().map(|label| 1 + label.len()).sum::<usize>() + 1
+}
+
+/// length of `names` emitted back to back through one `BinEncoder`.
+///
+/// Mirrors its name compression: every label suffix written while the
+/// encoder is below offset 0x3FFF is remembered, and the first label whose
+/// suffix was seen before is replaced with a 2 byte pointer, ending the name
+fn domain_search_len(names: &[Domain]) -> usize {
+    // (index into `names`, label index) of each remembered suffix
+    let mut seen: Vec<(usize, usize)> = Vec::new();
+    let mut len = 0;
+    for (n, name) in names.iter().enumerate() {
+        let name = &name.0;
+        let end = len + name_wire_len(name) - 1;
+        let mut offset = len;
+        len = end + 1;
+        for (i, label) in name.iter().enumerate() {
+            let suffix = name.iter().skip(i);
+            if seen
+                .iter()
+                .any(|&(sn, si)| names[sn].0.iter().skip(si).eq(suffix.clone()))
+            {
+                len = offset + 2;
+                break;
+            }
+            if end < 0x3FFF {
+                seen.push((n, i));
+            }
+            offset += 1 + label.len();
         }
     }
-    Ok(())
+    len
+}
+
+/// counts the bytes written through `fmt::Write`
+struct FmtLen(usize);
+
+impl fmt::Write for FmtLen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// encoded length of `len` bytes written with [`encode_long_opt_bytes`]
+fn long_opt_bytes_len(len: usize) -> usize {
+    long_opt_chunks_len(1, len)
+}
+
+/// encoded length of `n` items of `factor` bytes written with
+/// [`encode_long_opt_chunks`]
+fn long_opt_chunks_len(factor: usize, n: usize) -> usize {
+    let per_chunk = u8::MAX as usize / factor;
+    n * factor + 2 * ((n + per_chunk - 1) / per_chunk)
 }
 
 impl Encodable for DhcpOption {
@@ -1172,6 +2791,11 @@ impl Encodable for DhcpOption {
                 e.write_u8(code.into())?;
                 e.write_u8(0)?;
             }
+            // zero length is legal, indicating no home agents are available
+            MobileIpHomeAgent(ips) if ips.is_empty() => {
+                e.write_u8(code.into())?;
+                e.write_u8(0)?;
+            }
             SubnetMask(addr)
             | SwapServer(addr)
             | BroadcastAddr(addr)
@@ -1203,6 +2827,10 @@ impl Encodable for DhcpOption {
             | NTPServers(ips)
             | NetBiosNameServers(ips)
             | NetBiosDatagramDistributionServer(ips)
+            | MobileIpHomeAgent(ips)
+            | SmtpServer(ips)
+            | Pop3Server(ips)
+            | NntpServer(ips)
             | AssociatedIp(ips) => {
                 // let bytes = ips.iter().flat_map(|a| u32::from(*a).to_be_bytes()).collect::<Vec<_>>();
                 encode_long_opt_chunks(code, 4, ips, |ip, e| e.write_u32((*ip).into()), e)?;
@@ -1212,8 +2840,18 @@ impl Encodable for DhcpOption {
                 //     e.write_u32((*ip).into())?;
                 // }
             }
-            Hostname(s) | MeritDumpFile(s) | DomainName(s) | ExtensionsPath(s) | NISDomain(s)
-            | RootPath(s) | NetBiosScope(s) | Message(s) => {
+            Hostname(s)
+            | MeritDumpFile(s)
+            | DomainName(s)
+            | ExtensionsPath(s)
+            | NISDomain(s)
+            | RootPath(s)
+            | NetBiosScope(s)
+            | Message(s)
+            | PxeLinuxConfigFile(s)
+            | PosixTimezone(s)
+            | TzDatabaseTimezone(s)
+            | PxeLinuxPathPrefix(s) => {
                 encode_long_opt_bytes(code, s.as_bytes(), e)?;
             }
             BootFileSize(num) | MaxDatagramSize(num) | InterfaceMtu(num) | MaxMessageSize(num) => {
@@ -1238,7 +2876,7 @@ impl Encodable for DhcpOption {
                 e.write_u8(1)?;
                 e.write_u8(*byte)?
             }
-            StaticRoutingTable(pair_ips) => {
+            StaticRoutingTable(pair_ips) | PolicyFilter(pair_ips) => {
                 //     let bytes = pair_ips.iter().flat_map(|(a, b)| u32::from(*a).to_be_bytes().into_iter().chain(u32::from(*b).to_be_bytes())).collect::<Vec<_>>();
                 //     encode_chunk_bytes(code, &bytes, e)?;
                 encode_long_opt_chunks(
@@ -1252,7 +2890,11 @@ impl Encodable for DhcpOption {
                     e,
                 )?;
             }
-            ArpCacheTimeout(num)
+            PathMtuPlateauTable(mtus) => {
+                encode_long_opt_chunks(code, 2, mtus, |mtu, e| e.write_u16(*mtu), e)?;
+            }
+            PathMtuAgingTimeout(num)
+            | ArpCacheTimeout(num)
             | TcpKeepaliveInterval(num)
             | AddressLeaseTime(num)
             | Renewal(num)
@@ -1261,7 +2903,8 @@ impl Encodable for DhcpOption {
             | BulkLeaseQueryBaseTime(num)
             | BulkLeasQueryStartTimeOfState(num)
             | BulkLeaseQueryQueryStartTime(num)
-            | BulkLeaseQueryQueryEndTime(num) => {
+            | BulkLeaseQueryQueryEndTime(num)
+            | PxeLinuxRebootTime(num) => {
                 e.write_u8(code.into())?;
                 e.write_u8(4)?;
                 e.write_u32(*num)?;
@@ -1272,6 +2915,7 @@ impl Encodable for DhcpOption {
             | ClientMachineIdentifier(bytes) => {
                 encode_long_opt_bytes(code, bytes, e)?;
             }
+            UserClass(class) => encode_long_opt_bytes(code, class.raw(), e)?,
             ParameterRequestList(codes) => {
                 encode_long_opt_chunks(code, 1, codes, |code, e| e.write_u8((*code).into()), e)?;
             }
@@ -1280,6 +2924,11 @@ impl Encodable for DhcpOption {
                 e.write_u8(1)?;
                 e.write_u8((*ntype).into())?;
             }
+            AutoConfigure(auto) => {
+                e.write_u8(code.into())?;
+                e.write_u8(1)?;
+                e.write_u8((*auto).into())?;
+            }
             MessageType(mtype) => {
                 e.write_u8(code.into())?;
                 e.write_u8(1)?;
@@ -1309,11 +2958,9 @@ impl Encodable for DhcpOption {
                 encode_long_opt_bytes(code, url.as_bytes(), e)?;
             }
             BulkLeaseQueryStatusCode(status_code, msg) => {
-                e.write_u8(code.into())?;
-                let msg = msg.as_bytes();
-                e.write_u8(msg.len() as u8 + 1)?;
-                e.write_u8((*status_code).into())?;
-                e.write_slice(msg)?
+                let mut buf = vec![(*status_code).into()];
+                buf.extend_from_slice(msg.as_bytes());
+                encode_long_opt_bytes(code, &buf, e)?;
             }
             BulkLeaseQueryDhcpState(state) => {
                 e.write_u8(code.into())?;
@@ -1325,6 +2972,20 @@ impl Encodable for DhcpOption {
                 e.write_u8(1)?;
                 e.write_u8((*src).into())?
             }
+            ClientFQDN(fqdn) => {
+                let mut buf = vec![fqdn.flags().into(), fqdn.r1(), fqdn.r2()];
+                let name = &fqdn.domain().0;
+                if name.num_labels() > 0 {
+                    if fqdn.flags().e() {
+                        let mut name_buf = Vec::new();
+                        name.emit(&mut BinEncoder::new(&mut name_buf))?;
+                        buf.extend(name_buf);
+                    } else {
+                        buf.extend(name.to_string().as_bytes());
+                    }
+                }
+                encode_long_opt_bytes(code, &buf, e)?;
+            }
             DomainSearch(names) => {
                 let mut buf = Vec::new();
                 let mut name_encoder = BinEncoder::new(&mut buf);
@@ -1333,6 +2994,14 @@ impl Encodable for DhcpOption {
                 }
                 encode_long_opt_bytes(code, &buf, e)?;
             }
+            ClasslessStaticRoute(routes) => {
+                let mut buf = Vec::new();
+                let mut route_encoder = Encoder::new(&mut buf);
+                for route in routes {
+                    route.encode(&mut route_encoder)?;
+                }
+                encode_long_opt_bytes(code, &buf, e)?;
+            }
             // not yet implemented
             Unknown(opt) => {
                 encode_long_opt_bytes(code, &opt.data, e)?;
@@ -1367,8 +3036,11 @@ impl From<&DhcpOption> for OptionCode {
             ExtensionsPath(_) => OptionCode::ExtensionsPath,
             IpForwarding(_) => OptionCode::IpForwarding,
             NonLocalSrcRouting(_) => OptionCode::NonLocalSrcRouting,
+            PolicyFilter(_) => OptionCode::PolicyFilter,
             MaxDatagramSize(_) => OptionCode::MaxDatagramSize,
             DefaultIpTtl(_) => OptionCode::DefaultIpTtl,
+            PathMtuAgingTimeout(_) => OptionCode::PathMtuAgingTimeout,
+            PathMtuPlateauTable(_) => OptionCode::PathMtuPlateauTable,
             InterfaceMtu(_) => OptionCode::InterfaceMtu,
             AllSubnetsLocal(_) => OptionCode::AllSubnetsLocal,
             BroadcastAddr(_) => OptionCode::BroadcastAddr,
@@ -1404,16 +3076,26 @@ impl From<&DhcpOption> for OptionCode {
             Rebinding(_) => OptionCode::Rebinding,
             ClassIdentifier(_) => OptionCode::ClassIdentifier,
             ClientIdentifier(_) => OptionCode::ClientIdentifier,
+            MobileIpHomeAgent(_) => OptionCode::MobileIpHomeAgent,
+            SmtpServer(_) => OptionCode::SmtpServer,
+            Pop3Server(_) => OptionCode::Pop3Server,
+            NntpServer(_) => OptionCode::NntpServer,
+            UserClass(_) => OptionCode::UserClass,
             RapidCommit => OptionCode::RapidCommit,
+            ClientFQDN(_) => OptionCode::ClientFQDN,
             RelayAgentInformation(_) => OptionCode::RelayAgentInformation,
             ClientLastTransactionTime(_) => OptionCode::ClientLastTransactionTime,
             AssociatedIp(_) => OptionCode::AssociatedIp,
             ClientSystemArchitecture(_) => OptionCode::ClientSystemArchitecture,
             ClientNetworkInterface(_, _, _) => OptionCode::ClientNetworkInterface,
             ClientMachineIdentifier(_) => OptionCode::ClientMachineIdentifier,
+            PosixTimezone(_) => OptionCode::PosixTimezone,
+            TzDatabaseTimezone(_) => OptionCode::TzDatabaseTimezone,
             CaptivePortal(_) => OptionCode::CaptivePortal,
+            AutoConfigure(_) => OptionCode::AutoConfigure,
             SubnetSelection(_) => OptionCode::SubnetSelection,
             DomainSearch(_) => OptionCode::DomainSearch,
+            ClasslessStaticRoute(_) => OptionCode::ClasslessStaticRoute,
             BulkLeaseQueryStatusCode(_, _) => OptionCode::StatusCode,
             BulkLeaseQueryBaseTime(_) => OptionCode::BaseTime,
             BulkLeasQueryStartTimeOfState(_) => OptionCode::StartTimeOfState,
@@ -1421,6 +3103,9 @@ impl From<&DhcpOption> for OptionCode {
             BulkLeaseQueryQueryEndTime(_) => OptionCode::QueryEndTime,
             BulkLeaseQueryDhcpState(_) => OptionCode::DhcpState,
             BulkLeaseQueryDataSource(_) => OptionCode::DataSource,
+            PxeLinuxConfigFile(_) => OptionCode::PxeLinuxConfigFile,
+            PxeLinuxPathPrefix(_) => OptionCode::PxeLinuxPathPrefix,
+            PxeLinuxRebootTime(_) => OptionCode::PxeLinuxRebootTime,
             End => OptionCode::End,
             // TODO: implement more
             Unknown(n) => OptionCode::Unknown(n.code),
@@ -1455,6 +3140,23 @@ impl UnknownOption {
     pub fn into_parts(self) -> (OptionCode, Vec<u8>) {
         (self.code.into(), self.data)
     }
+    /// decode the data for this option with `f`, for options this crate
+    /// doesn't know the type of
+    /// ```
+    /// # use std::net::SocketAddrV4;
+    /// # use dhcproto::{Decoder, v4::{OptionCode, UnknownOption}};
+    /// // vendor option carrying a controller endpoint
+    /// let opt = UnknownOption::new(OptionCode::Unknown(224), vec![10, 0, 0, 1, 0x1f, 0x90]);
+    /// let addr = opt.decode_data(Decoder::read_socketaddr_v4)?;
+    /// assert_eq!(addr, "10.0.0.1:8080".parse::<SocketAddrV4>()?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn decode_data<'a, T>(
+        &'a self,
+        f: impl FnOnce(&mut Decoder<'a>) -> DecodeResult<T>,
+    ) -> DecodeResult<T> {
+        f(&mut Decoder::new(&self.data))
+    }
 }
 
 impl Decodable for UnknownOption {
@@ -1468,10 +3170,8 @@ impl Decodable for UnknownOption {
 
 impl Encodable for UnknownOption {
     fn encode(&self, e: &mut Encoder<'_>) -> EncodeResult<()> {
-        // TODO: account for >255 len
-        e.write_u8(self.code)?;
-        e.write_u8(self.data.len() as u8)?;
-        e.write_slice(&self.data)?;
+        // values over 255 bytes are split according to RFC 3396
+        encode_long_opt_bytes(self.code.into(), &self.data, e)?;
         Ok(())
     }
 }
@@ -1521,6 +3221,91 @@ pub enum MessageType {
     Unknown(u8),
 }
 
+impl MessageType {
+    /// the opcode a message of this type is sent with, `BootRequest` for
+    /// messages to the server & `BootReply` for messages from it. Returns
+    /// `None` for types that may be sent either way, or are unknown
+    /// ```
+    /// # use dhcproto::v4::{MessageType, Opcode};
+    /// assert_eq!(MessageType::Discover.opcode(), Some(Opcode::BootRequest));
+    /// assert_eq!(MessageType::Offer.opcode(), Some(Opcode::BootReply));
+    /// ```
+    pub fn opcode(&self) -> Option<super::Opcode> {
+        use super::Opcode;
+        use MessageType::*;
+        match self {
+            Discover | Request | Decline | Release | Inform | LeaseQuery | BulkLeaseQuery
+            | ActiveLeaseQuery => Some(Opcode::BootRequest),
+            Offer | Ack | Nak | ForceRenew | LeaseUnassigned | LeaseUnknown | LeaseActive
+            | LeaseQueryDone | LeaseQueryStatus => Some(Opcode::BootReply),
+            Tls | Unknown(_) => None,
+        }
+    }
+}
+
+impl MessageType {
+    /// canonical name of the message type, without the "DHCP" prefix
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        use MessageType::*;
+        Some(match self {
+            Discover => "DISCOVER",
+            Offer => "OFFER",
+            Request => "REQUEST",
+            Decline => "DECLINE",
+            Ack => "ACK",
+            Nak => "NAK",
+            Release => "RELEASE",
+            Inform => "INFORM",
+            ForceRenew => "FORCERENEW",
+            LeaseQuery => "LEASEQUERY",
+            LeaseUnassigned => "LEASEUNASSIGNED",
+            LeaseUnknown => "LEASEUNKNOWN",
+            LeaseActive => "LEASEACTIVE",
+            BulkLeaseQuery => "BULKLEASEQUERY",
+            LeaseQueryDone => "LEASEQUERYDONE",
+            ActiveLeaseQuery => "ACTIVELEASEQUERY",
+            LeaseQueryStatus => "LEASEQUERYSTATUS",
+            Tls => "TLS",
+            Unknown(_) => return None,
+        })
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "UNKNOWN({})", u8::from(*self)),
+        }
+    }
+}
+
+/// Parses the canonical name, ignoring case & an optional "DHCP" prefix,
+/// `UNKNOWN(n)` or a number
+/// ```
+/// # use dhcproto::v4::MessageType;
+/// assert_eq!("DISCOVER".parse(), Ok(MessageType::Discover));
+/// assert_eq!("DhcpOffer".parse(), Ok(MessageType::Offer));
+/// assert_eq!("UNKNOWN(200)".parse(), Ok(MessageType::Unknown(200)));
+/// assert_eq!(MessageType::Ack.to_string(), "ACK");
+/// assert!("BOGUS".parse::<MessageType>().is_err());
+/// ```
+impl FromStr for MessageType {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let name = trimmed
+            .get(..4)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("DHCP"))
+            .map_or(trimmed, |_| &trimmed[4..]);
+        super::parse_name("message type", name, Self::name).map_err(|mut e| {
+            e.name = trimmed.to_owned();
+            e
+        })
+    }
+}
+
 impl From<u8> for MessageType {
     fn from(n: u8) -> Self {
         match n {
@@ -1691,6 +3476,648 @@ mod tests {
 
         Ok(())
     }
+    #[test]
+    fn test_decode_with_raw() -> Result<()> {
+        // domain name split over two consecutive options, then PAD & END
+        let bytes = [
+            15, 3, b'f', b'o', b'o', 15, 4, b'.', b'c', b'o', b'm', 0, 1, 4, 255, 255, 0, 0, 255,
+        ];
+        let (opts, raw) = DhcpOptions::decode_with_raw(&mut Decoder::new(&bytes))?;
+        assert_eq!(
+            opts.get(OptionCode::DomainName),
+            Some(&DhcpOption::DomainName("foo.com".to_owned()))
+        );
+        assert_eq!(raw.len(), 2);
+        assert_eq!(raw.get(OptionCode::DomainName), Some(&bytes[..11]));
+        assert_eq!(raw.get(OptionCode::SubnetMask), Some(&bytes[12..18]));
+        Ok(())
+    }
+    #[test]
+    fn test_leasequery_opts() -> Result<()> {
+        test_opt(
+            DhcpOption::ClientLastTransactionTime(3600),
+            vec![91, 4, 0, 0, 14, 16],
+        )?;
+        test_opt(
+            DhcpOption::AssociatedIp(vec![[192, 168, 0, 1].into(), [192, 168, 0, 2].into()]),
+            vec![92, 8, 192, 168, 0, 1, 192, 168, 0, 2],
+        )?;
+
+        let mut opts = DhcpOptions::new();
+        opts.insert(DhcpOption::MessageType(MessageType::LeaseActive));
+        opts.insert(DhcpOption::ClientLastTransactionTime(60));
+        opts.insert(DhcpOption::AssociatedIp(vec![[10, 0, 0, 1].into()]));
+        let opts = DhcpOptions::decode(&mut Decoder::new(&opts.to_vec()?))?;
+        assert_eq!(opts.msg_type(), Some(MessageType::LeaseActive));
+        assert_eq!(opts.client_last_transaction_time(), Some(60));
+        assert_eq!(opts.associated_ips(), Some(&[[10, 0, 0, 1].into()][..]));
+        Ok(())
+    }
+    #[test]
+    fn test_encode_options_only() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        assert!(opts.to_vec()?.is_empty());
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::AgentCircuitId(vec![1, 2]));
+        opts.insert(DhcpOption::RelayAgentInformation(info));
+        opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+        let bytes = opts.to_vec()?;
+        // agent info is last, before END
+        assert_eq!(bytes, vec![1, 4, 255, 255, 255, 0, 82, 4, 1, 2, 1, 2, 255]);
+        assert_eq!(DhcpOptions::decode(&mut Decoder::new(&bytes))?, opts);
+        Ok(())
+    }
+    #[test]
+    fn test_router_dns_iter() -> Result<()> {
+        let (input, _) = binput();
+        let opts = DhcpOptions::decode(&mut Decoder::new(&input))?;
+        assert_eq!(
+            opts.routers_iter().collect::<Vec<_>>(),
+            opts.routers().unwrap()
+        );
+        assert_eq!(
+            opts.dns_iter().collect::<Vec<_>>(),
+            opts.domain_name_servers().unwrap()
+        );
+        assert!(opts.dns_iter().count() > 0);
+        Ok(())
+    }
+    #[test]
+    fn test_client_fqdn() -> Result<()> {
+        // canonical wire format
+        let flags = fqdn::FqdnFlags::default().set_s(true).set_e(true);
+        let name = Domain("host.example.com.".parse::<Name>()?);
+        test_opt(
+            DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(flags, name)),
+            vec![
+                81, 21, 0x05, 0, 0, 4, b'h', b'o', b's', b't', 7, b'e', b'x', b'a', b'm', b'p',
+                b'l', b'e', 3, b'c', b'o', b'm', 0,
+            ],
+        )?;
+        // deprecated ascii encoding
+        let bytes = [81, 7, 0x01, 255, 255, b'h', b'o', b's', b't'];
+        let opt = DhcpOption::decode(&mut Decoder::new(&bytes))?;
+        match &opt {
+            DhcpOption::ClientFQDN(fqdn) => {
+                assert!(fqdn.flags().s() && !fqdn.flags().e());
+                assert_eq!((fqdn.r1(), fqdn.r2()), (255, 255));
+                assert_eq!(fqdn.domain().0, "host".parse::<Name>()?);
+            }
+            _ => panic!("expected ClientFQDN"),
+        }
+        // empty name
+        test_opt(
+            DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(flags, Domain(Name::new()))),
+            vec![81, 3, 0x05, 0, 0],
+        )?;
+        // malformed names & values are kept as unknown, without losing the
+        // options after them
+        for fqdn in [&[81, 5, 0, 0, 0, 0xff, 0xfe][..], &[81, 2, 0, 0]] {
+            let buf = [&[53, 1, 1], fqdn, &[12, 2, b'h', b'i', 255]].concat();
+            let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+            assert_eq!(opts.len(), 3);
+            assert_eq!(opts.get(OptionCode::ClientFQDN), None);
+            assert!(matches!(
+                opts.get(OptionCode::Unknown(81)),
+                Some(DhcpOption::Unknown(opt)) if opt.data() == &fqdn[2..]
+            ));
+            assert_eq!(
+                opts.get(OptionCode::Hostname),
+                Some(&DhcpOption::Hostname("hi".into()))
+            );
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_ordered() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.insert(DhcpOption::Unknown(UnknownOption::new(
+            OptionCode::Unknown(53),
+            vec![9],
+        )));
+        opts.insert(DhcpOption::InterfaceMtu(1500));
+        opts.insert(DhcpOption::MessageType(MessageType::Offer));
+        opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+        let codes = opts.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            [
+                OptionCode::SubnetMask,
+                OptionCode::InterfaceMtu,
+                OptionCode::MessageType,
+                OptionCode::Unknown(53),
+            ]
+        );
+        // `Unknown(53)` & `MessageType` are distinct keys
+        assert_eq!(opts.len(), 4);
+        assert_eq!(
+            opts.to_vec()?,
+            [1, 4, 255, 255, 255, 0, 26, 2, 5, 220, 53, 1, 2, 53, 1, 9, 255]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_ordered() -> Result<()> {
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::AgentCircuitId(vec![1]));
+        let mut opts = DhcpOptions::new();
+        opts.insert(DhcpOption::RelayAgentInformation(info));
+        opts.insert(DhcpOption::MessageType(MessageType::Request));
+        opts.insert(DhcpOption::SubnetMask([255, 0, 0, 0].into()));
+        opts.insert(DhcpOption::InterfaceMtu(1500));
+
+        let encode = |order: &[OptionCode]| -> Result<Vec<u8>> {
+            let mut buf = Vec::new();
+            opts.encode_ordered(order, &mut Encoder::new(&mut buf))?;
+            Ok(buf)
+        };
+        assert_eq!(encode(&[])?, opts.to_vec()?);
+        assert_eq!(
+            encode(&[
+                OptionCode::MessageType,
+                OptionCode::Hostname,
+                OptionCode::MessageType,
+                OptionCode::End,
+            ])?,
+            [53, 1, 3, 1, 4, 255, 0, 0, 0, 26, 2, 5, 220, 82, 3, 1, 1, 1, 255]
+        );
+        // relay info can be placed explicitly
+        assert_eq!(
+            encode(&[OptionCode::RelayAgentInformation])?,
+            [82, 3, 1, 1, 1, 1, 4, 255, 0, 0, 0, 26, 2, 5, 220, 53, 1, 3, 255]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_len() -> Result<()> {
+        let decoded = DhcpOptions::decode(&mut Decoder::new(&binput().0))?;
+        assert_eq!(decoded.encoded_len(), decoded.to_vec()?.len());
+
+        let mut info = relay::RelayAgentInformation::default();
+        info.insert(relay::RelayInfo::AgentCircuitId(vec![1, 2, 3]));
+        info.insert(relay::RelayInfo::LinkSelection([10, 0, 0, 1].into()));
+        let opts = [
+            DhcpOption::Router(vec![[10, 0, 0, 1].into(); 100]),
+            DhcpOption::Hostname("h".repeat(600)),
+            DhcpOption::ParameterRequestList(vec![OptionCode::Router; 255]),
+            DhcpOption::StaticRoutingTable(vec![([0, 0, 0, 0].into(), [0, 0, 0, 0].into()); 40]),
+            DhcpOption::BulkLeaseQueryStatusCode(bulk_query::Code::Success, "x".repeat(254)),
+            DhcpOption::MobileIpHomeAgent(vec![]),
+            DhcpOption::ClientNetworkInterface(1, 2, 1),
+            DhcpOption::RelayAgentInformation(info),
+            DhcpOption::DomainSearch(vec![
+                Domain(Name::from_str("eng.apple.com.").unwrap()),
+                Domain(Name::from_str("marketing.apple.com.").unwrap()),
+            ]),
+            // compressed names, split across instances
+            DhcpOption::DomainSearch(
+                (0..60)
+                    .map(|i| format!("host{}.example.com.", i % 40))
+                    .chain([".".to_owned()])
+                    .map(|n| Domain(Name::from_str(&n).unwrap()))
+                    .collect(),
+            ),
+            DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(
+                fqdn::FqdnFlags::default().set_e(true),
+                Domain(Name::from_str("host.example.com.").unwrap()),
+            )),
+            DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(
+                fqdn::FqdnFlags::default(),
+                Domain(Name::from_str("host.example.com.").unwrap()),
+            )),
+            DhcpOption::ClientFQDN(fqdn::ClientFQDN::new(
+                fqdn::FqdnFlags::default().set_e(true),
+                Domain(Name::root()),
+            )),
+            DhcpOption::Unknown(UnknownOption::new(OptionCode::Unknown(240), vec![])),
+            DhcpOption::ClasslessStaticRoute(vec![
+                classless::ClasslessRoute::new(
+                    "10.0.0.0/8".parse().unwrap(),
+                    [10, 0, 0, 1].into()
+                );
+                60
+            ]),
+        ];
+        for opt in &opts {
+            assert_eq!(opt.encoded_len(), opt.to_vec()?.len(), "{opt:?}");
+        }
+        let opts = opts.into_iter().collect::<DhcpOptions>();
+        assert_eq!(opts.encoded_len(), opts.to_vec()?.len());
+        assert_eq!(DhcpOptions::new().encoded_len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_classless_static_route() -> Result<()> {
+        let buf = [121, 13, 0, 192, 168, 0, 1, 24, 10, 1, 2, 10, 0, 0, 254, 255];
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        let routes = opts.classless_static_routes().unwrap();
+        assert_eq!(
+            routes[0].to_route_entry(),
+            ("0.0.0.0/0".parse()?, [192, 168, 0, 1].into())
+        );
+        assert_eq!(
+            routes[1].to_route_entry(),
+            ("10.1.2.0/24".parse()?, [10, 0, 0, 254].into())
+        );
+        assert_eq!(opts.to_vec()?, buf);
+        // a truncated route is kept as unknown, along with the options after it
+        let buf = [121, 3, 24, 10, 1, 12, 2, b'h', b'i', 255];
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(opts.classless_static_routes(), None);
+        assert!(matches!(
+            opts.get(OptionCode::Unknown(121)),
+            Some(DhcpOption::Unknown(opt)) if opt.data() == [24, 10, 1]
+        ));
+        assert_eq!(
+            opts.get(OptionCode::Hostname),
+            Some(&DhcpOption::Hostname("hi".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_singletons() -> Result<()> {
+        // non-consecutive duplicate server id, first is kept
+        let buf = [
+            54, 4, 10, 0, 0, 1, 3, 4, 10, 0, 0, 9, 54, 4, 10, 0, 0, 2, 255,
+        ];
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(
+            opts.get(OptionCode::ServerIdentifier),
+            Some(&DhcpOption::ServerIdentifier([10, 0, 0, 1].into()))
+        );
+        assert!(matches!(
+            DhcpOptions::decode_strict(&mut Decoder::new(&buf)),
+            Err(DecodeError::DuplicateOption {
+                code: OptionCode::ServerIdentifier
+            })
+        ));
+        // consecutive instances
+        let buf = [51, 4, 0, 0, 0, 1, 51, 4, 0, 0, 0, 2, 255];
+        assert!(DhcpOptions::decode_strict(&mut Decoder::new(&buf)).is_err());
+        // repeated list options are concatenated, not duplicates
+        let buf = [3, 4, 10, 0, 0, 1, 3, 4, 10, 0, 0, 2, 53, 1, 1, 255];
+        let opts = DhcpOptions::decode_strict(&mut Decoder::new(&buf))?;
+        assert_eq!(opts.routers().map(|r| r.len()), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = DhcpOptions::new();
+        base.insert(DhcpOption::Router(vec![[10, 0, 0, 1].into()]));
+        base.insert(DhcpOption::DomainName("example.com".to_owned()));
+        let mut other = DhcpOptions::new();
+        other.insert(DhcpOption::Router(vec![
+            [10, 0, 0, 1].into(),
+            [10, 0, 0, 2].into(),
+        ]));
+        other.insert(DhcpOption::DomainName("example.org".to_owned()));
+        other.insert(DhcpOption::InterfaceMtu(1500));
+
+        let mut keep = base.clone();
+        keep.merge(&other, MergeStrategy::KeepExisting);
+        assert_eq!(keep.len(), 3);
+        assert_eq!(keep.get(OptionCode::Router), base.get(OptionCode::Router));
+        assert_eq!(
+            keep.get(OptionCode::DomainName),
+            base.get(OptionCode::DomainName)
+        );
+
+        let mut overwrite = base.clone();
+        overwrite.merge(&other, MergeStrategy::Overwrite);
+        assert_eq!(overwrite, other);
+
+        let mut append = base;
+        append.merge(&other, MergeStrategy::Append);
+        assert_eq!(append.routers(), other.routers());
+        assert_eq!(
+            append.get(OptionCode::DomainName),
+            other.get(OptionCode::DomainName)
+        );
+        assert_eq!(append.len(), 3);
+    }
+
+    #[test]
+    fn test_auto_configure() -> Result<()> {
+        for (auto, n) in [
+            (AutoConfig::DoNotAutoConfigure, 0),
+            (AutoConfig::AutoConfigure, 1),
+            (AutoConfig::Unknown(7), 7),
+        ] {
+            assert_eq!(AutoConfig::from(n), auto);
+            test_opt(DhcpOption::AutoConfigure(auto), vec![116, 1, n])?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pxelinux() -> Result<()> {
+        test_opt(
+            DhcpOption::PxeLinuxConfigFile("pxelinux.cfg".to_owned()),
+            vec![
+                209, 12, b'p', b'x', b'e', b'l', b'i', b'n', b'u', b'x', b'.', b'c', b'f', b'g',
+            ],
+        )?;
+        test_opt(
+            DhcpOption::PxeLinuxPathPrefix("/tftp/".to_owned()),
+            vec![210, 6, b'/', b't', b'f', b't', b'p', b'/'],
+        )?;
+        test_opt(
+            DhcpOption::PxeLinuxRebootTime(300),
+            vec![211, 4, 0, 0, 1, 44],
+        )?;
+        Ok(())
+    }
+    #[test]
+    fn test_trailing_after_end() -> Result<()> {
+        // multiple END & junk after the first END are ignored
+        let bytes = [53, 1, 1, 255, 255, 0, 0, 1, 4, 255, 255, 255, 0, 0xde, 0xad];
+        let mut opts = DhcpOptions::decode(&mut Decoder::new(&bytes))?;
+        assert_eq!(opts.len(), 1);
+        assert_eq!(opts.msg_type(), Some(MessageType::Discover));
+        // END & PAD in the map don't produce extra bytes
+        opts.insert(DhcpOption::End);
+        opts.insert(DhcpOption::Pad);
+        assert_eq!(opts.to_vec()?, vec![53, 1, 1, 255]);
+        Ok(())
+    }
+    #[test]
+    fn test_pad_end_bytes_in_values() -> Result<()> {
+        // 0x00/0xFF in a length or value are data, not PAD/END
+        let mut bytes = vec![
+            1, 4, 255, 255, 255, 0, // subnet mask
+            43, 3, 255, 0, 255, // vendor extensions
+            53, 1, 2, // message type
+            80, 0, // rapid commit, zero length
+            250, 255,
+        ];
+        bytes.extend([0xff; 255]);
+        bytes.push(255);
+        let opts = DhcpOptions::decode(&mut Decoder::new(&bytes))?;
+        assert_eq!(opts.len(), 5);
+        assert_eq!(
+            opts.get(OptionCode::SubnetMask),
+            Some(&DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0)))
+        );
+        assert_eq!(
+            opts.get(OptionCode::VendorExtensions),
+            Some(&DhcpOption::VendorExtensions(vec![255, 0, 255]))
+        );
+        assert_eq!(
+            opts.get(OptionCode::RapidCommit),
+            Some(&DhcpOption::RapidCommit)
+        );
+        assert_eq!(
+            opts.get(OptionCode::Unknown(250)),
+            Some(&DhcpOption::Unknown(UnknownOption::new(
+                OptionCode::Unknown(250),
+                vec![0xff; 255]
+            )))
+        );
+        assert_eq!(opts.msg_type(), Some(MessageType::Offer));
+        assert_eq!(opts.to_vec()?, bytes);
+        Ok(())
+    }
+    #[test]
+    fn test_time_log_servers() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.set_time_servers(vec![[10, 0, 0, 1].into(), [10, 0, 0, 2].into()])
+            .set_log_servers(vec![[10, 0, 0, 3].into()]);
+        let opts = DhcpOptions::decode(&mut Decoder::new(&opts.to_vec()?))?;
+        assert_eq!(
+            opts.time_servers(),
+            Some(&[[10, 0, 0, 1].into(), [10, 0, 0, 2].into()][..])
+        );
+        assert_eq!(opts.log_servers(), Some(&[[10, 0, 0, 3].into()][..]));
+        Ok(())
+    }
+    #[test]
+    fn test_swap_server_root_path() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.set_swap_server([10, 0, 0, 5].into())
+            .set_root_path("/srv/nfsroot");
+        let buf = opts.to_vec()?;
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(opts.swap_server(), Some([10, 0, 0, 5].into()));
+        assert_eq!(opts.root_path(), Some("/srv/nfsroot"));
+        test_opt(
+            DhcpOption::RootPath("/srv".to_owned()),
+            vec![17, 4, b'/', b's', b'r', b'v'],
+        )?;
+        Ok(())
+    }
+    #[test]
+    fn test_boot_file_size_merit_dump() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.set_boot_file_size(0x0102).set_merit_dump_file("/c");
+        let buf = opts.to_vec()?;
+        assert_eq!(buf, [13, 2, 1, 2, 14, 2, b'/', b'c', 255]);
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(opts.boot_file_size(), Some(0x0102));
+        assert_eq!(opts.merit_dump_file(), Some("/c"));
+        Ok(())
+    }
+    #[test]
+    fn test_ip_layer_params() -> Result<()> {
+        test_opt(DhcpOption::IpForwarding(true), vec![19, 1, 1])?;
+        test_opt(DhcpOption::NonLocalSrcRouting(false), vec![20, 1, 0])?;
+        test_opt(
+            DhcpOption::PolicyFilter(vec![
+                ([10, 0, 0, 0].into(), [255, 0, 0, 0].into()),
+                ([192, 168, 1, 0].into(), [255, 255, 255, 0].into()),
+            ]),
+            vec![
+                21, 16, 10, 0, 0, 0, 255, 0, 0, 0, 192, 168, 1, 0, 255, 255, 255, 0,
+            ],
+        )?;
+        // not a multiple of 8
+        assert!(DhcpOption::decode(&mut Decoder::new(&[21, 4, 10, 0, 0, 0])).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_timezone() -> Result<()> {
+        test_opt(
+            DhcpOption::PosixTimezone("UTC0".to_owned()),
+            vec![100, 4, b'U', b'T', b'C', b'0'],
+        )?;
+        test_opt(
+            DhcpOption::TzDatabaseTimezone("Etc/UTC".to_owned()),
+            vec![101, 7, b'E', b't', b'c', b'/', b'U', b'T', b'C'],
+        )?;
+        // not UTF-8
+        assert!(DhcpOption::decode(&mut Decoder::new(&[101, 1, 0xff])).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_user_class() -> Result<()> {
+        let class = user_class::UserClass::new(vec![b"a".to_vec(), b"bc".to_vec()])?;
+        test_opt(
+            DhcpOption::UserClass(class),
+            vec![77, 5, 1, b'a', 2, b'b', b'c'],
+        )?;
+        test_opt(
+            DhcpOption::UserClass(user_class::UserClass::from_raw(b"iPXE".to_vec())),
+            vec![77, 4, b'i', b'P', b'X', b'E'],
+        )?;
+        let opts = DhcpOptions::decode(&mut Decoder::new(&[77, 2, 1, b'x', 255]))?;
+        assert_eq!(
+            opts.user_class().map(|c| c.interpret()),
+            Some(user_class::UserClassValue::Rfc3004(vec![&b"x"[..]]))
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_get_raw() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.insert(DhcpOption::Router(vec![
+            [10, 0, 0, 1].into(),
+            [10, 0, 0, 2].into(),
+        ]));
+        opts.insert(DhcpOption::Unknown(UnknownOption::new(
+            OptionCode::Unknown(240),
+            vec![0xde, 0xad],
+        )));
+        // split into two instances on the wire
+        let ips = vec![Ipv4Addr::new(1, 2, 3, 4); 70];
+        opts.insert(DhcpOption::NIS(ips));
+        assert_eq!(
+            &*opts.get_raw(OptionCode::Router).unwrap()?,
+            [10, 0, 0, 1, 10, 0, 0, 2]
+        );
+        assert!(matches!(
+            opts.get_raw(OptionCode::Unknown(240)),
+            Some(Ok(Cow::Borrowed(&[0xde, 0xad])))
+        ));
+        let nis = opts.get_raw(OptionCode::NIS).unwrap()?;
+        assert_eq!(nis.len(), 280);
+        assert!(nis.chunks(4).all(|ip| ip == [1, 2, 3, 4]));
+        assert!(opts.get_raw(OptionCode::SubnetMask).is_none());
+        Ok(())
+    }
+    #[test]
+    fn test_host_params() -> Result<()> {
+        test_opt(DhcpOption::DefaultTcpTtl(64), vec![37, 1, 64])?;
+        test_opt(
+            DhcpOption::TcpKeepaliveInterval(7200),
+            vec![38, 4, 0, 0, 28, 32],
+        )?;
+        test_opt(DhcpOption::TcpKeepaliveGarbage(true), vec![39, 1, 1])?;
+
+        let mut opts = DhcpOptions::new();
+        opts.set_default_ip_ttl(128).set_tcp_keepalive_interval(0);
+        let opts = DhcpOptions::decode(&mut Decoder::new(&opts.to_vec()?))?;
+        assert_eq!(opts.default_ip_ttl(), Some(128));
+        assert_eq!(opts.tcp_keepalive_interval(), Some(0));
+        assert_eq!(opts.tcp_keepalive_garbage(), None);
+        Ok(())
+    }
+    #[test]
+    fn test_path_mtu() -> Result<()> {
+        test_opt(
+            DhcpOption::PathMtuAgingTimeout(600),
+            vec![24, 4, 0, 0, 2, 88],
+        )?;
+        test_opt(
+            DhcpOption::PathMtuPlateauTable(vec![576, 1500]),
+            vec![25, 4, 2, 64, 5, 220],
+        )?;
+        // not a multiple of 2
+        assert!(DhcpOption::decode(&mut Decoder::new(&[25, 3, 2, 64, 5])).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_nis() -> Result<()> {
+        let mut opts = DhcpOptions::new();
+        opts.set_nis_domain("yp")
+            .set_nis_servers(vec![[10, 0, 0, 2].into(), [10, 0, 0, 3].into()]);
+        let buf = opts.to_vec()?;
+        assert_eq!(
+            buf,
+            [40, 2, b'y', b'p', 41, 8, 10, 0, 0, 2, 10, 0, 0, 3, 255]
+        );
+        let opts = DhcpOptions::decode(&mut Decoder::new(&buf))?;
+        assert_eq!(opts.nis_domain(), Some("yp"));
+        assert_eq!(opts.nis_servers().map(|ips| ips.len()), Some(2));
+        Ok(())
+    }
+    #[test]
+    fn test_router_discovery_static_routes() -> Result<()> {
+        test_opt(DhcpOption::PerformRouterDiscovery(true), vec![31, 1, 1])?;
+        test_opt(
+            DhcpOption::StaticRoutingTable(vec![
+                ([10, 1, 0, 0].into(), [192, 168, 0, 1].into()),
+                ([10, 2, 0, 0].into(), [192, 168, 0, 2].into()),
+            ]),
+            vec![
+                33, 16, 10, 1, 0, 0, 192, 168, 0, 1, 10, 2, 0, 0, 192, 168, 0, 2,
+            ],
+        )?;
+        let opts = DhcpOptions::decode(&mut Decoder::new(&[
+            31, 1, 0, 33, 8, 10, 1, 0, 0, 192, 168, 0, 1, 255,
+        ]))?;
+        assert_eq!(opts.perform_router_discovery(), Some(false));
+        assert_eq!(opts.static_routes().map(|r| r.len()), Some(1));
+        // length must be a multiple of 8
+        assert!(DhcpOption::decode(&mut Decoder::new(&[33, 4, 10, 1, 0, 0])).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_app_servers() -> Result<()> {
+        test_opt(DhcpOption::MobileIpHomeAgent(vec![]), vec![68, 0])?;
+        test_opt(
+            DhcpOption::MobileIpHomeAgent(vec![[10, 0, 0, 1].into()]),
+            vec![68, 4, 10, 0, 0, 1],
+        )?;
+        test_opt(
+            DhcpOption::SmtpServer(vec![[10, 0, 0, 25].into()]),
+            vec![69, 4, 10, 0, 0, 25],
+        )?;
+        test_opt(
+            DhcpOption::Pop3Server(vec![[10, 0, 0, 110].into()]),
+            vec![70, 4, 10, 0, 0, 110],
+        )?;
+        test_opt(
+            DhcpOption::NntpServer(vec![[10, 0, 0, 119].into(), [10, 0, 0, 120].into()]),
+            vec![71, 8, 10, 0, 0, 119, 10, 0, 0, 120],
+        )?;
+        let opts = DhcpOptions::decode(&mut Decoder::new(&[68, 0, 255]))?;
+        assert_eq!(opts.mobile_ip_home_agents(), Some(&[][..]));
+        Ok(())
+    }
+    #[test]
+    fn test_netbios() -> Result<()> {
+        test_opt(
+            DhcpOption::NetBiosNameServers(vec!["10.0.0.1".parse::<Ipv4Addr>().unwrap()]),
+            vec![44, 4, 10, 0, 0, 1],
+        )?;
+        test_opt(
+            DhcpOption::NetBiosDatagramDistributionServer(vec!["10.0.0.2"
+                .parse::<Ipv4Addr>()
+                .unwrap()]),
+            vec![45, 4, 10, 0, 0, 2],
+        )?;
+        test_opt(
+            DhcpOption::NetBiosScope("corp".to_string()),
+            vec![47, 4, b'c', b'o', b'r', b'p'],
+        )?;
+        for (node, n) in [
+            (NodeType::B, 1),
+            (NodeType::P, 2),
+            (NodeType::M, 4),
+            (NodeType::H, 8),
+            (NodeType::Unknown(3), 3),
+        ] {
+            assert_eq!(NodeType::from(n), node);
+            assert_eq!(u8::from(node), n);
+            test_opt(DhcpOption::NetBiosNodeType(node), vec![46, 1, n])?;
+        }
+
+        Ok(())
+    }
 
     #[test]
     fn test_pair_ips() -> Result<()> {
@@ -1786,6 +4213,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_long_values_split() -> Result<()> {
+        // values over 255 bytes are split, not truncated
+        let data = (0..300).map(|n| n as u8).collect::<Vec<_>>();
+        let opt = DhcpOption::Unknown(UnknownOption::new(OptionCode::Unknown(240), data.clone()));
+        let mut expected = vec![240, 255];
+        expected.extend(&data[..255]);
+        expected.extend([240, 45]);
+        expected.extend(&data[255..]);
+        test_opt(opt, expected.clone())?;
+        let opt = UnknownOption::new(OptionCode::Unknown(240), data);
+        assert_eq!(opt.to_vec()?, expected);
+
+        let msg = "x".repeat(300);
+        let opt = DhcpOption::BulkLeaseQueryStatusCode(bulk_query::Code::Success, msg);
+        let buf = opt.to_vec()?;
+        assert_eq!(buf.len(), 2 + 255 + 2 + 46);
+        assert_eq!(DhcpOption::decode(&mut Decoder::new(&buf))?, opt);
+        Ok(())
+    }
+
     fn binput() -> (Vec<u8>, usize) {
         (
             vec![
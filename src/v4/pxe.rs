@@ -0,0 +1,624 @@
+//! PXE vendor options, encapsulated in the Vendor Specific Information option
+//! (43) - [PXE 2.1 specification](http://www.pix.net/software/pxeboot/archive/pxespec.pdf)
+//! section 2.4
+//!
+//! ```rust
+//! use dhcproto::{Encodable, v4::{self, pxe::{BootMenuItem, MenuPrompt, PxeOption, PxeVendorOptions}}};
+//!
+//! let mut pxe = PxeVendorOptions::default();
+//! pxe.insert(PxeOption::BootMenu(vec![BootMenuItem::new(0x8000, "Install")]));
+//! pxe.insert(PxeOption::MenuPrompt(MenuPrompt::new(10, "Press F8 for menu")));
+//!
+//! let mut msg = v4::Message::default();
+//! msg.opts_mut()
+//!     .insert(v4::DhcpOption::VendorExtensions(pxe.to_vec()?));
+//! let decoded = msg.opts().pxe_vendor_options().unwrap()?;
+//! assert_eq!(decoded, pxe);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::{cmp::Ordering, collections::BTreeMap, fmt, net::Ipv4Addr};
+
+use crate::{Decodable, Decoder, Encodable, Encoder};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Collection of PXE vendor sub-options, kept ordered by code
+///
+/// You can create/modify it, then encode it into a
+/// [`DhcpOption::VendorExtensions`]. Encoding writes the sub-options followed
+/// by the PXE End sub-option (255)
+///
+/// [`DhcpOption::VendorExtensions`]: crate::v4::DhcpOption::VendorExtensions
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PxeVendorOptions(BTreeMap<PxeCode, PxeOption>);
+
+impl PxeVendorOptions {
+    /// Get the data for a particular [`PxeCode`]
+    pub fn get(&self, code: PxeCode) -> Option<&PxeOption> {
+        self.0.get(&code)
+    }
+    /// Get the mutable data for a particular [`PxeCode`]
+    pub fn get_mut(&mut self, code: PxeCode) -> Option<&mut PxeOption> {
+        self.0.get_mut(&code)
+    }
+    /// remove sub option
+    pub fn remove(&mut self, code: PxeCode) -> Option<PxeOption> {
+        self.0.remove(&code)
+    }
+    /// insert a new [`PxeOption`]
+    pub fn insert(&mut self, opt: PxeOption) -> Option<PxeOption> {
+        self.0.insert((&opt).into(), opt)
+    }
+    /// iterate over entries, in order of code
+    pub fn iter(&self) -> impl Iterator<Item = (&PxeCode, &PxeOption)> {
+        self.0.iter()
+    }
+    /// Returns `true` if there are no options
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// return the discovery control flags (sub-option 6)
+    pub fn discovery_control(&self) -> Option<DiscoveryControl> {
+        match self.get(PxeCode::DiscoveryControl) {
+            Some(PxeOption::DiscoveryControl(flags)) => Some(*flags),
+            _ => None,
+        }
+    }
+    /// return the boot servers (sub-option 8)
+    pub fn boot_servers(&self) -> Option<&[BootServer]> {
+        match self.get(PxeCode::BootServers) {
+            Some(PxeOption::BootServers(servers)) => Some(servers),
+            _ => None,
+        }
+    }
+    /// return the boot menu (sub-option 9)
+    pub fn boot_menu(&self) -> Option<&[BootMenuItem]> {
+        match self.get(PxeCode::BootMenu) {
+            Some(PxeOption::BootMenu(items)) => Some(items),
+            _ => None,
+        }
+    }
+    /// return the menu prompt (sub-option 10)
+    pub fn menu_prompt(&self) -> Option<&MenuPrompt> {
+        match self.get(PxeCode::MenuPrompt) {
+            Some(PxeOption::MenuPrompt(prompt)) => Some(prompt),
+            _ => None,
+        }
+    }
+}
+
+impl Decodable for PxeVendorOptions {
+    fn decode(d: &mut Decoder<'_>) -> super::DecodeResult<Self> {
+        let mut opts = BTreeMap::new();
+        loop {
+            match d.peek_u8() {
+                // end of the buffer or the End sub-option
+                Err(_) | Ok(END) => break,
+                Ok(PAD) => {
+                    d.read_u8()?;
+                }
+                Ok(_) => {
+                    let opt = PxeOption::decode(d)?;
+                    opts.insert(PxeCode::from(&opt), opt);
+                }
+            }
+        }
+        Ok(PxeVendorOptions(opts))
+    }
+}
+
+impl Encodable for PxeVendorOptions {
+    fn encode(&self, e: &mut Encoder<'_>) -> super::EncodeResult<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        self.0.values().try_for_each(|opt| opt.encode(e))?;
+        e.write_u8(END)
+    }
+}
+
+const PAD: u8 = 0;
+const END: u8 = 255;
+
+/// PXE vendor sub-option
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PxeOption {
+    /// 6 PXE_DISCOVERY_CONTROL
+    DiscoveryControl(DiscoveryControl),
+    /// 7 DISCOVERY_MCAST_ADDR, multicast address used for boot server discovery
+    MulticastAddr(Ipv4Addr),
+    /// 8 PXE_BOOT_SERVERS
+    BootServers(Vec<BootServer>),
+    /// 9 PXE_BOOT_MENU
+    BootMenu(Vec<BootMenuItem>),
+    /// 10 PXE_MENU_PROMPT
+    MenuPrompt(MenuPrompt),
+    /// 71 PXE_BOOT_ITEM, the boot server type & layer being requested
+    BootItem {
+        /// boot server type
+        ty: u16,
+        /// boot layer
+        layer: u16,
+    },
+    /// unknown or not yet implemented sub-option
+    Unknown(UnknownPxeOption),
+}
+
+impl Decodable for PxeOption {
+    fn decode(d: &mut Decoder<'_>) -> super::DecodeResult<Self> {
+        use PxeOption::*;
+        let code = d.read_u8()?.into();
+        let len = d.read_u8()? as usize;
+        let mut d = d.sub_decoder(len)?;
+        Ok(match code {
+            PxeCode::DiscoveryControl => DiscoveryControl(d.read_u8()?.into()),
+            PxeCode::MulticastAddr => MulticastAddr(d.read_ipv4(len)?),
+            PxeCode::BootServers => {
+                let mut servers = Vec::new();
+                while !d.buffer().is_empty() {
+                    let ty = d.read_u16()?;
+                    let count = d.read_u8()? as usize;
+                    servers.push(BootServer {
+                        ty,
+                        addrs: d.read_ipv4s(count * 4)?,
+                    });
+                }
+                BootServers(servers)
+            }
+            PxeCode::BootMenu => {
+                let mut items = Vec::new();
+                while !d.buffer().is_empty() {
+                    let ty = d.read_u16()?;
+                    let len = d.read_u8()? as usize;
+                    items.push(BootMenuItem {
+                        ty,
+                        description: d.read_string(len)?,
+                    });
+                }
+                BootMenu(items)
+            }
+            PxeCode::MenuPrompt => {
+                let timeout = d.read_u8()?;
+                let prompt = d.read_string(d.buffer().len())?;
+                MenuPrompt(self::MenuPrompt { timeout, prompt })
+            }
+            PxeCode::BootItem => BootItem {
+                ty: d.read_u16()?,
+                layer: d.read_u16()?,
+            },
+            PxeCode::Unknown(code) => Unknown(UnknownPxeOption {
+                code,
+                data: d.read_slice(len)?.to_vec(),
+            }),
+        })
+    }
+}
+
+impl Encodable for PxeOption {
+    fn encode(&self, e: &mut Encoder<'_>) -> super::EncodeResult<()> {
+        use PxeOption::*;
+        let code: PxeCode = self.into();
+        let mut buf = Vec::new();
+        let mut data = Encoder::new(&mut buf);
+        match self {
+            DiscoveryControl(flags) => data.write_u8((*flags).into())?,
+            MulticastAddr(addr) => data.write_u32((*addr).into())?,
+            BootServers(servers) => {
+                for server in servers {
+                    data.write_u16(server.ty)?;
+                    data.write_u8(list_len(code, server.addrs.len())?)?;
+                    for addr in &server.addrs {
+                        data.write_u32((*addr).into())?;
+                    }
+                }
+            }
+            BootMenu(items) => {
+                for item in items {
+                    data.write_u16(item.ty)?;
+                    data.write_u8(list_len(code, item.description.len())?)?;
+                    data.write_slice(item.description.as_bytes())?;
+                }
+            }
+            MenuPrompt(prompt) => {
+                data.write_u8(prompt.timeout)?;
+                data.write_slice(prompt.prompt.as_bytes())?;
+            }
+            BootItem { ty, layer } => {
+                data.write_u16(*ty)?;
+                data.write_u16(*layer)?;
+            }
+            Unknown(opt) => data.write_slice(&opt.data)?,
+        }
+        e.write_u8(code.into())?;
+        // sub-options can't be split, the value must fit in a length byte
+        e.write_u8(list_len(code, buf.len())?)?;
+        e.write_slice(&buf)
+    }
+}
+
+/// length of a value within a sub-option, which must fit in a single byte
+fn list_len(code: PxeCode, len: usize) -> super::EncodeResult<u8> {
+    u8::try_from(len).map_err(|_| super::EncodeError::OptionValueTooLong {
+        code: code.into(),
+        len,
+    })
+}
+
+/// PXE discovery control flags (sub-option 6)
+///
+/// ```text
+///  0 1 2 3 4 5 6 7
+/// +-+-+-+-+-+-+-+-+
+/// |  MBZ  |D|S|M|B|
+/// +-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Default, Clone, PartialEq, Eq, Hash)]
+pub struct DiscoveryControl(u8);
+
+impl fmt::Debug for DiscoveryControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscoveryControl")
+            .field("disable_broadcast", &self.disable_broadcast())
+            .field("disable_multicast", &self.disable_multicast())
+            .field("servers_only", &self.servers_only())
+            .field("skip_menu", &self.skip_menu())
+            .finish()
+    }
+}
+
+impl fmt::Display for DiscoveryControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl DiscoveryControl {
+    /// Create new DiscoveryControl from u8
+    pub fn new(n: u8) -> Self {
+        Self(n)
+    }
+    /// get the status of the B flag, broadcast discovery is disabled
+    pub fn disable_broadcast(&self) -> bool {
+        (self.0 & 0x01) != 0
+    }
+    /// set the B bit, returns a new DiscoveryControl
+    pub fn set_disable_broadcast(mut self, bit: bool) -> Self {
+        self.set_bit(0x01, bit);
+        self
+    }
+    /// get the status of the M flag, multicast discovery is disabled
+    pub fn disable_multicast(&self) -> bool {
+        (self.0 & 0x02) != 0
+    }
+    /// set the M bit, returns a new DiscoveryControl
+    pub fn set_disable_multicast(mut self, bit: bool) -> Self {
+        self.set_bit(0x02, bit);
+        self
+    }
+    /// get the status of the S flag, only accept replies from servers in the
+    /// boot servers list (sub-option 8)
+    pub fn servers_only(&self) -> bool {
+        (self.0 & 0x04) != 0
+    }
+    /// set the S bit, returns a new DiscoveryControl
+    pub fn set_servers_only(mut self, bit: bool) -> Self {
+        self.set_bit(0x04, bit);
+        self
+    }
+    /// get the status of the D flag, download the boot file immediately
+    /// without prompting or showing the menu
+    pub fn skip_menu(&self) -> bool {
+        (self.0 & 0x08) != 0
+    }
+    /// set the D bit, returns a new DiscoveryControl
+    pub fn set_skip_menu(mut self, bit: bool) -> Self {
+        self.set_bit(0x08, bit);
+        self
+    }
+    fn set_bit(&mut self, mask: u8, bit: bool) {
+        if bit {
+            self.0 |= mask;
+        } else {
+            self.0 &= !mask;
+        }
+    }
+}
+
+impl From<u8> for DiscoveryControl {
+    fn from(n: u8) -> Self {
+        Self(n)
+    }
+}
+impl From<DiscoveryControl> for u8 {
+    fn from(f: DiscoveryControl) -> Self {
+        f.0
+    }
+}
+
+/// A boot server type & its addresses, from the boot servers sub-option (8)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BootServer {
+    ty: u16,
+    addrs: Vec<Ipv4Addr>,
+}
+
+impl BootServer {
+    /// Create a new BootServer of type `ty`, `0` is the PXE bootstrap server
+    pub fn new(ty: u16, addrs: Vec<Ipv4Addr>) -> Self {
+        Self { ty, addrs }
+    }
+    /// get the boot server type
+    pub fn ty(&self) -> u16 {
+        self.ty
+    }
+    /// get the addresses of the servers
+    pub fn addrs(&self) -> &[Ipv4Addr] {
+        &self.addrs
+    }
+}
+
+/// An item of the boot menu sub-option (9)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BootMenuItem {
+    ty: u16,
+    description: String,
+}
+
+impl BootMenuItem {
+    /// Create a new BootMenuItem, selecting it discovers boot servers of type
+    /// `ty`
+    pub fn new<S: Into<String>>(ty: u16, description: S) -> Self {
+        Self {
+            ty,
+            description: description.into(),
+        }
+    }
+    /// get the boot server type
+    pub fn ty(&self) -> u16 {
+        self.ty
+    }
+    /// get the description shown in the menu
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// The menu prompt sub-option (10)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuPrompt {
+    timeout: u8,
+    prompt: String,
+}
+
+impl MenuPrompt {
+    /// Create a new MenuPrompt. `timeout` is the number of seconds to wait
+    /// for a key press, `0` boots the first menu item immediately & `255`
+    /// shows the menu without a timeout
+    pub fn new<S: Into<String>>(timeout: u8, prompt: S) -> Self {
+        Self {
+            timeout,
+            prompt: prompt.into(),
+        }
+    }
+    /// get the timeout in seconds
+    pub fn timeout(&self) -> u8 {
+        self.timeout
+    }
+    /// get the prompt
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+}
+
+/// An as-of-yet unimplemented PXE sub-option
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnknownPxeOption {
+    code: u8,
+    data: Vec<u8>,
+}
+
+impl UnknownPxeOption {
+    /// create a new unknown sub-option
+    pub fn new(code: PxeCode, data: Vec<u8>) -> Self {
+        Self {
+            code: code.into(),
+            data,
+        }
+    }
+    /// return the sub-option code
+    pub fn code(&self) -> PxeCode {
+        self.code.into()
+    }
+    /// return the data for this code
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// PXE sub-option code, represented as a u8
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PxeCode {
+    /// 6
+    DiscoveryControl,
+    /// 7
+    MulticastAddr,
+    /// 8
+    BootServers,
+    /// 9
+    BootMenu,
+    /// 10
+    MenuPrompt,
+    /// 71
+    BootItem,
+    /// unknown/unimplemented sub-option
+    Unknown(u8),
+}
+
+impl PartialOrd for PxeCode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PxeCode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `Unknown(n)` can share a number with a known code, use it to break ties
+        let key = |code: &Self| (u8::from(*code), matches!(code, PxeCode::Unknown(_)));
+        key(self).cmp(&key(other))
+    }
+}
+
+impl From<u8> for PxeCode {
+    fn from(n: u8) -> Self {
+        use PxeCode::*;
+        match n {
+            6 => DiscoveryControl,
+            7 => MulticastAddr,
+            8 => BootServers,
+            9 => BootMenu,
+            10 => MenuPrompt,
+            71 => BootItem,
+            _ => Unknown(n),
+        }
+    }
+}
+impl From<PxeCode> for u8 {
+    fn from(code: PxeCode) -> Self {
+        use PxeCode::*;
+        match code {
+            DiscoveryControl => 6,
+            MulticastAddr => 7,
+            BootServers => 8,
+            BootMenu => 9,
+            MenuPrompt => 10,
+            BootItem => 71,
+            Unknown(n) => n,
+        }
+    }
+}
+
+impl From<&PxeOption> for PxeCode {
+    fn from(opt: &PxeOption) -> Self {
+        use PxeOption::*;
+        match opt {
+            DiscoveryControl(_) => PxeCode::DiscoveryControl,
+            MulticastAddr(_) => PxeCode::MulticastAddr,
+            BootServers(_) => PxeCode::BootServers,
+            BootMenu(_) => PxeCode::BootMenu,
+            MenuPrompt(_) => PxeCode::MenuPrompt,
+            BootItem { .. } => PxeCode::BootItem,
+            Unknown(opt) => PxeCode::Unknown(opt.code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn test_opt(opt: PxeOption, actual: Vec<u8>) -> Result<()> {
+        let out = opt.to_vec()?;
+        assert_eq!(out, actual);
+        assert_eq!(PxeOption::decode(&mut Decoder::new(&out))?, opt);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sub_options() -> Result<()> {
+        test_opt(
+            PxeOption::DiscoveryControl(
+                DiscoveryControl::default()
+                    .set_disable_multicast(true)
+                    .set_skip_menu(true),
+            ),
+            vec![6, 1, 0x0a],
+        )?;
+        test_opt(
+            PxeOption::MulticastAddr([224, 0, 1, 2].into()),
+            vec![7, 4, 224, 0, 1, 2],
+        )?;
+        test_opt(
+            PxeOption::BootServers(vec![
+                BootServer::new(0, vec![[10, 0, 0, 1].into()]),
+                BootServer::new(0x8000, vec![[10, 0, 0, 2].into(), [10, 0, 0, 3].into()]),
+            ]),
+            vec![
+                8, 18, 0, 0, 1, 10, 0, 0, 1, 0x80, 0, 2, 10, 0, 0, 2, 10, 0, 0, 3,
+            ],
+        )?;
+        test_opt(
+            PxeOption::BootMenu(vec![
+                BootMenuItem::new(0, "Local"),
+                BootMenuItem::new(0x8000, "Net"),
+            ]),
+            vec![
+                9, 14, 0, 0, 5, b'L', b'o', b'c', b'a', b'l', 0x80, 0, 3, b'N', b'e', b't',
+            ],
+        )?;
+        test_opt(
+            PxeOption::MenuPrompt(MenuPrompt::new(5, "F8")),
+            vec![10, 3, 5, b'F', b'8'],
+        )?;
+        test_opt(
+            PxeOption::BootItem {
+                ty: 0x8000,
+                layer: 0,
+            },
+            vec![71, 4, 0x80, 0, 0, 0],
+        )?;
+        test_opt(
+            PxeOption::Unknown(UnknownPxeOption::new(
+                PxeCode::Unknown(1),
+                vec![10, 0, 0, 1],
+            )),
+            vec![1, 4, 10, 0, 0, 1],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_vendor_options() -> Result<()> {
+        let mut pxe = PxeVendorOptions::default();
+        assert!(pxe.to_vec()?.is_empty());
+        pxe.insert(PxeOption::MenuPrompt(MenuPrompt::new(0, "")));
+        pxe.insert(PxeOption::DiscoveryControl(DiscoveryControl::new(0x08)));
+        // ordered by code, followed by End
+        let buf = pxe.to_vec()?;
+        assert_eq!(buf, vec![6, 1, 0x08, 10, 1, 0, 255]);
+        assert_eq!(PxeVendorOptions::decode(&mut Decoder::new(&buf))?, pxe);
+        assert!(pxe.discovery_control().unwrap().skip_menu());
+        assert_eq!(pxe.menu_prompt().map(|p| p.timeout()), Some(0));
+        assert_eq!(pxe.boot_menu(), None);
+
+        // pad is skipped, nothing after End is read
+        let buf = [0, 6, 1, 0x01, 255, 9, 0];
+        let pxe = PxeVendorOptions::decode(&mut Decoder::new(&buf))?;
+        assert!(pxe.discovery_control().unwrap().disable_broadcast());
+        assert_eq!(pxe.iter().count(), 1);
+
+        // truncated boot server list
+        let buf = [8, 4, 0, 0, 2, 10];
+        assert!(PxeVendorOptions::decode(&mut Decoder::new(&buf)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_too_long() {
+        let opt = PxeOption::BootMenu(vec![BootMenuItem::new(0, "a".repeat(100)); 3]);
+        assert!(matches!(
+            opt.to_vec(),
+            Err(crate::error::EncodeError::OptionValueTooLong { code: 9, .. })
+        ));
+    }
+}
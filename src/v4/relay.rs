@@ -1,5 +1,10 @@
 //!
-use std::{collections::HashMap, fmt, net::Ipv4Addr};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    net::Ipv4Addr,
+};
 
 use crate::{Decodable, Encodable};
 
@@ -26,6 +31,20 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct RelayAgentInformation(HashMap<RelayCode, RelayInfo>);
 
+// `HashMap` isn't `Hash`, so hash the entries in order of their code, giving
+// equal relay info the same hash regardless of insertion order
+#[allow(clippy::derive_hash_xor_eq)] // consistent with the derived `PartialEq`
+impl Hash for RelayAgentInformation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut info = self.0.iter().collect::<Vec<_>>();
+        // `Unknown(n)` can share a number with a known code, use it to break ties
+        info.sort_unstable_by_key(|(code, _)| {
+            (u8::from(**code), matches!(code, RelayCode::Unknown(_)))
+        });
+        info.hash(state);
+    }
+}
+
 impl RelayAgentInformation {
     /// Get the data for a particular [`RelayCode`]
     ///
@@ -72,6 +91,64 @@ impl RelayAgentInformation {
     {
         self.0.retain(pred)
     }
+    /// return the agent circuit id (sub-option 1)
+    pub fn circuit_id(&self) -> Option<&[u8]> {
+        match self.get(RelayCode::AgentCircuitId) {
+            Some(RelayInfo::AgentCircuitId(id)) => Some(id),
+            _ => None,
+        }
+    }
+    /// return the agent remote id (sub-option 2)
+    pub fn remote_id(&self) -> Option<&[u8]> {
+        match self.get(RelayCode::AgentRemoteId) {
+            Some(RelayInfo::AgentRemoteId(id)) => Some(id),
+            _ => None,
+        }
+    }
+    /// return the link selection address (sub-option 5)
+    pub fn link_selection(&self) -> Option<Ipv4Addr> {
+        match self.get(RelayCode::LinkSelection) {
+            Some(RelayInfo::LinkSelection(addr)) => Some(*addr),
+            _ => None,
+        }
+    }
+    /// return the subscriber id (sub-option 6), an opaque
+    /// value chosen by the access provider
+    /// ```
+    /// # use dhcproto::v4::relay::{RelayAgentInformation, RelayFlags, RelayInfo};
+    /// let mut info = RelayAgentInformation::default();
+    /// info.insert(RelayInfo::SubscriberId(b"cust-1234".to_vec()));
+    /// info.insert(RelayInfo::RelayAgentFlags(RelayFlags::default().set_unicast()));
+    /// assert_eq!(info.subscriber_id(), Some(&b"cust-1234"[..]));
+    /// assert!(info.flags().unwrap().unicast());
+    /// assert_eq!(info.link_selection(), None);
+    /// ```
+    pub fn subscriber_id(&self) -> Option<&[u8]> {
+        match self.get(RelayCode::SubscriberId) {
+            Some(RelayInfo::SubscriberId(id)) => Some(id),
+            _ => None,
+        }
+    }
+    /// return the relay agent flags (sub-option 10)
+    pub fn flags(&self) -> Option<RelayFlags> {
+        match self.get(RelayCode::RelayAgentFlags) {
+            Some(RelayInfo::RelayAgentFlags(flags)) => Some(*flags),
+            _ => None,
+        }
+    }
+    /// return the server identifier override (sub-option 11). A server should
+    /// send this address as the Server Identifier (54) of its replies, so the
+    /// client unicasts renewals to the relay rather than the server
+    pub fn server_identifier_override(&self) -> Option<Ipv4Addr> {
+        match self.get(RelayCode::ServerIdentifierOverride) {
+            Some(RelayInfo::ServerIdentifierOverride(addr)) => Some(*addr),
+            _ => None,
+        }
+    }
+    /// number of bytes the sub-options encode to
+    pub fn encoded_len(&self) -> usize {
+        self.0.values().map(RelayInfo::encoded_len).sum()
+    }
 }
 
 impl Decodable for RelayAgentInformation {
@@ -91,7 +168,7 @@ impl Encodable for RelayAgentInformation {
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RelayInfo {
     /// 1 - <https://datatracker.ietf.org/doc/html/rfc3046>
     AgentCircuitId(Vec<u8>),
@@ -134,7 +211,7 @@ impl Decodable for RelayInfo {
             RelayCode::AgentRemoteId => {
                 let len = d.read_u8()? as usize;
                 let data = d.read_slice(len)?.to_vec();
-                AgentCircuitId(data)
+                AgentRemoteId(data)
             }
             RelayCode::DocsisDeviceClass => {
                 let _ = d.read_u8()?;
@@ -151,9 +228,10 @@ impl Decodable for RelayInfo {
                 SubscriberId(data)
             }
             RelayCode::RelayAgentFlags => {
-                let _len = d.read_u8()?;
-                let flags = d.read_u8()?;
-                RelayAgentFlags(flags.into())
+                // flags may be extended, keep the first byte & skip the rest
+                let len = d.read_u8()? as usize;
+                let flags = d.read_slice(len)?;
+                RelayAgentFlags((*flags.first().ok_or(super::DecodeError::NotEnoughBytes)?).into())
             }
             RelayCode::ServerIdentifierOverride => {
                 let len = d.read_u8()? as usize;
@@ -182,6 +260,19 @@ impl Decodable for RelayInfo {
     }
 }
 
+impl RelayInfo {
+    /// number of bytes the sub-option encodes to, including code & length
+    pub fn encoded_len(&self) -> usize {
+        use RelayInfo::*;
+        2 + match self {
+            AgentCircuitId(id) | AgentRemoteId(id) | SubscriberId(id) => id.len(),
+            DocsisDeviceClass(_) | LinkSelection(_) | ServerIdentifierOverride(_) => 4,
+            RelayAgentFlags(_) => 1,
+            Unknown(opt) => opt.data.len(),
+        }
+    }
+}
+
 impl Encodable for RelayInfo {
     fn encode(&self, e: &mut crate::Encoder<'_>) -> super::EncodeResult<()> {
         use RelayInfo::*;
@@ -190,7 +281,7 @@ impl Encodable for RelayInfo {
         match self {
             AgentCircuitId(id) | AgentRemoteId(id) | SubscriberId(id) => {
                 // length of bytes stored in Vec
-                e.write_u8(id.len() as u8)?;
+                e.write_u8(sub_opt_len(code, id)?)?;
                 e.write_slice(id)?
             }
             DocsisDeviceClass(n) => {
@@ -208,7 +299,7 @@ impl Encodable for RelayInfo {
             // not yet implemented
             Unknown(opt) => {
                 // length of bytes stored in Vec
-                e.write_u8(opt.data.len() as u8)?;
+                e.write_u8(sub_opt_len(code, &opt.data)?)?;
                 e.write_slice(&opt.data)?
             }
         };
@@ -216,8 +307,17 @@ impl Encodable for RelayInfo {
     }
 }
 
+/// sub-option lengths are a single byte, unlike options they can't be split
+fn sub_opt_len(code: RelayCode, data: &[u8]) -> super::EncodeResult<u8> {
+    u8::try_from(data.len()).map_err(|_| super::EncodeError::OptionValueTooLong {
+        code: code.into(),
+        len: data.len(),
+    })
+}
+
+/// Relay Agent Flags (sub-option 10)
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Copy, Default, Clone, PartialEq, Eq)]
+#[derive(Copy, Default, Clone, PartialEq, Eq, Hash)]
 pub struct RelayFlags(u8);
 
 impl fmt::Debug for RelayFlags {
@@ -261,6 +361,85 @@ impl From<RelayFlags> for u8 {
     }
 }
 
+/// Agent Remote ID (sub-option 2). The contents are chosen by the relay, this
+/// keeps the raw bytes & offers the common interpretations
+/// ```rust
+/// use dhcproto::v4::relay::{RelayAgentInformation, RemoteId};
+///
+/// let mut info = RelayAgentInformation::default();
+/// info.insert(RemoteId::from_mac([0, 0x1b, 0x21, 0xaa, 0xbb, 0xcc]).into());
+/// let id = RemoteId::from(info.remote_id().unwrap());
+/// assert_eq!(id.as_mac(), Some([0, 0x1b, 0x21, 0xaa, 0xbb, 0xcc]));
+/// assert_eq!(id.as_str(), None);
+///
+/// let id = RemoteId::from(&b"00:1b:21:aa:bb:cc"[..]);
+/// assert_eq!(id.as_mac(), Some([0, 0x1b, 0x21, 0xaa, 0xbb, 0xcc]));
+/// assert_eq!(id.as_str(), Some("00:1b:21:aa:bb:cc"));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteId {
+    data: Vec<u8>,
+}
+
+impl RemoteId {
+    /// Create a Remote ID from raw bytes
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+    /// Create a Remote ID holding a MAC address as 6 raw bytes
+    pub fn from_mac(mac: [u8; 6]) -> Self {
+        Self::new(mac.to_vec())
+    }
+    /// the raw bytes
+    pub fn raw(&self) -> &[u8] {
+        &self.data
+    }
+    /// consume the Remote ID, returning the raw bytes
+    pub fn into_raw(self) -> Vec<u8> {
+        self.data
+    }
+    /// interpret as a MAC address, either text of 6 hex pairs separated by
+    /// `:` or `-`, or 6 raw bytes. Values that are text (see
+    /// [`RemoteId::as_str`]) are only parsed as text, so a 6 character
+    /// descriptor like `port-7` isn't taken as a MAC
+    pub fn as_mac(&self) -> Option<[u8; 6]> {
+        let text = match self.as_str() {
+            Some(text) => text,
+            None => return <[u8; 6]>::try_from(&self.data[..]).ok(),
+        };
+        let mut mac = [0; 6];
+        let mut pairs = text.split(|c| c == ':' || c == '-');
+        for byte in mac.iter_mut() {
+            let pair = pairs
+                .next()
+                .filter(|pair| pair.len() == 2 && pair.bytes().all(|b| b.is_ascii_hexdigit()))?;
+            *byte = u8::from_str_radix(pair, 16).ok()?;
+        }
+        pairs.next().is_none().then(|| mac)
+    }
+    /// interpret as text, if the bytes are printable ASCII, e.g. an interface
+    /// descriptor like `eth0/1/2:100`
+    pub fn as_str(&self) -> Option<&str> {
+        if self.data.is_empty() || !self.data.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            return None;
+        }
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+impl From<&[u8]> for RemoteId {
+    fn from(data: &[u8]) -> Self {
+        Self::new(data.to_vec())
+    }
+}
+
+impl From<RemoteId> for RelayInfo {
+    fn from(id: RemoteId) -> Self {
+        RelayInfo::AgentRemoteId(id.data)
+    }
+}
+
 /// An as-of-yet unimplemented relay info
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -411,6 +590,7 @@ mod tests {
             RelayInfo::AgentCircuitId(vec![0, 1, 2, 3, 4]),
             vec![1, 5, 0, 1, 2, 3, 4],
         )?;
+        test_opt(RelayInfo::AgentRemoteId(vec![0, 1, 2]), vec![2, 3, 0, 1, 2])?;
 
         Ok(())
     }
@@ -420,6 +600,10 @@ mod tests {
             RelayInfo::RelayAgentFlags(RelayFlags::default().set_unicast()),
             vec![10, 1, 0x80],
         )?;
+        // only the first byte is defined
+        let opt = RelayInfo::decode(&mut crate::Decoder::new(&[10, 2, 0x80, 0xff, 1]))?;
+        assert_eq!(opt, RelayInfo::RelayAgentFlags(RelayFlags::new(0x80)));
+        assert!(RelayInfo::decode(&mut crate::Decoder::new(&[10, 0, 1, 1])).is_err());
 
         Ok(())
     }
@@ -432,4 +616,62 @@ mod tests {
 
         Ok(())
     }
+    #[test]
+    fn test_accessors() -> Result<()> {
+        let mut info = RelayAgentInformation::default();
+        info.insert(RelayInfo::AgentCircuitId(vec![1]));
+        info.insert(RelayInfo::AgentRemoteId(vec![2]));
+        info.insert(RelayInfo::LinkSelection([10, 0, 0, 1].into()));
+        info.insert(RelayInfo::SubscriberId(b"sub".to_vec()));
+        info.insert(RelayInfo::RelayAgentFlags(RelayFlags::new(0x80)));
+        info.insert(RelayInfo::ServerIdentifierOverride([10, 0, 0, 2].into()));
+        let info = RelayAgentInformation::decode(&mut crate::Decoder::new(&info.to_vec()?))?;
+        assert_eq!(info.circuit_id(), Some(&[1][..]));
+        assert_eq!(info.remote_id(), Some(&[2][..]));
+        assert_eq!(info.link_selection(), Some([10, 0, 0, 1].into()));
+        assert_eq!(info.subscriber_id(), Some(&b"sub"[..]));
+        assert!(info.flags().unwrap().unicast());
+        assert_eq!(
+            info.server_identifier_override(),
+            Some([10, 0, 0, 2].into())
+        );
+        assert_eq!(RelayAgentInformation::default().subscriber_id(), None);
+        Ok(())
+    }
+    #[test]
+    fn test_remote_id() {
+        let mac = [0xde, 0xad, 0xbe, 0xef, 0, 1];
+        assert_eq!(
+            RemoteId::from(&b"DE-AD-BE-EF-00-01"[..]).as_mac(),
+            Some(mac)
+        );
+        for bad in [
+            &b"de:ad:be:ef:00"[..],
+            b"de:ad:be:ef:00:01:02",
+            b"de:ad:be:ef:0:001",
+            b"zz:ad:be:ef:00:01",
+            b"+e:ad:be:ef:00:01",
+            b"port-7",
+        ] {
+            assert_eq!(RemoteId::from(bad).as_mac(), None, "{:?}", bad);
+        }
+        let id = RemoteId::from(&b"eth0/1/2:100"[..]);
+        assert_eq!((id.as_str(), id.as_mac()), (Some("eth0/1/2:100"), None));
+        assert_eq!(RemoteId::from(&b"a\tb"[..]).as_str(), None);
+        assert_eq!(RemoteId::default().as_str(), None);
+        assert_eq!(
+            RelayInfo::from(RemoteId::new(vec![1, 2])),
+            RelayInfo::AgentRemoteId(vec![1, 2])
+        );
+        assert_eq!(RemoteId::from_mac(mac).into_raw(), mac);
+    }
+
+    #[test]
+    fn test_too_long() {
+        let opt = RelayInfo::AgentCircuitId(vec![0; 256]);
+        assert!(matches!(
+            opt.to_vec(),
+            Err(crate::error::EncodeError::OptionValueTooLong { code: 1, len: 256 })
+        ));
+    }
 }
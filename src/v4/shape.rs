@@ -0,0 +1,169 @@
+//! Decoding option values by a caller supplied shape
+//!
+//! Vendor & site-specific options aren't modeled by [`DhcpOption`] and decode
+//! as raw bytes. If the width of their fields is known out of band, a
+//! [`Schema`] mapping codes to a [`ValueShape`] can decode them with
+//! [`DhcpOptions::get_as`] or [`DhcpOptions::get_with_schema`].
+//! ```rust
+//! use std::net::Ipv4Addr;
+//! use dhcproto::v4::{
+//!     shape::{Schema, ShapedValue, ValueShape},
+//!     DhcpOption, DhcpOptions, OptionCode, UnknownOption,
+//! };
+//!
+//! let mut opts = DhcpOptions::new();
+//! opts.insert(DhcpOption::Unknown(UnknownOption::new(
+//!     OptionCode::Unknown(224),
+//!     vec![10, 0, 0, 1, 10, 0, 0, 2],
+//! )));
+//!
+//! let mut schema = Schema::new();
+//! schema.insert(OptionCode::Unknown(224), ValueShape::Ipv4List);
+//! assert_eq!(
+//!     opts.get_with_schema(OptionCode::Unknown(224), &schema).unwrap()?,
+//!     ShapedValue::Ipv4List(vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)])
+//! );
+//! // the same bytes, read another way
+//! assert!(opts.get_as(OptionCode::Unknown(224), ValueShape::U32).unwrap().is_err());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`DhcpOption`]: crate::v4::DhcpOption
+//! [`DhcpOptions::get_as`]: crate::v4::DhcpOptions::get_as
+//! [`DhcpOptions::get_with_schema`]: crate::v4::DhcpOptions::get_with_schema
+use std::{collections::HashMap, net::Ipv4Addr};
+
+use crate::{
+    decoder::Decoder,
+    error::{DecodeError, DecodeResult},
+    v4::OptionCode,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Shapes to decode option codes by
+pub type Schema = HashMap<OptionCode, ValueShape>;
+
+/// How to interpret the bytes of an option value. Fixed width shapes must
+/// match the value's length exactly, list shapes a multiple of their width
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValueShape {
+    /// a single byte, 1 is true
+    Bool,
+    /// an 8 bit integer
+    U8,
+    /// a 16 bit big-endian integer
+    U16,
+    /// a 32 bit big-endian integer
+    U32,
+    /// an IPv4 address
+    Ipv4,
+    /// a list of 16 bit big-endian integers
+    U16List,
+    /// a list of IPv4 addresses
+    Ipv4List,
+    /// a UTF-8 string
+    String,
+    /// opaque bytes
+    Bytes,
+}
+
+impl ValueShape {
+    /// Decode `value`, the bytes of an option without its code & length
+    pub fn decode(&self, value: &[u8]) -> DecodeResult<ShapedValue> {
+        let len = value.len();
+        let exact = |n: usize| {
+            if len == n {
+                Ok(())
+            } else {
+                Err(DecodeError::NotEnoughBytes)
+            }
+        };
+        let mut d = Decoder::new(value);
+        Ok(match self {
+            ValueShape::Bool => {
+                exact(1)?;
+                ShapedValue::Bool(d.read_bool()?)
+            }
+            ValueShape::U8 => {
+                exact(1)?;
+                ShapedValue::U8(d.read_u8()?)
+            }
+            ValueShape::U16 => {
+                exact(2)?;
+                ShapedValue::U16(d.read_u16()?)
+            }
+            ValueShape::U32 => {
+                exact(4)?;
+                ShapedValue::U32(d.read_u32()?)
+            }
+            ValueShape::Ipv4 => ShapedValue::Ipv4(d.read_ipv4(len)?),
+            ValueShape::U16List => ShapedValue::U16List(d.read_u16s(len)?),
+            ValueShape::Ipv4List => ShapedValue::Ipv4List(d.read_ipv4s(len)?),
+            ValueShape::String => ShapedValue::String(d.read_string(len)?),
+            ValueShape::Bytes => ShapedValue::Bytes(value.to_vec()),
+        })
+    }
+}
+
+/// An option value decoded by a [`ValueShape`]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShapedValue {
+    /// see [`ValueShape::Bool`]
+    Bool(bool),
+    /// see [`ValueShape::U8`]
+    U8(u8),
+    /// see [`ValueShape::U16`]
+    U16(u16),
+    /// see [`ValueShape::U32`]
+    U32(u32),
+    /// see [`ValueShape::Ipv4`]
+    Ipv4(Ipv4Addr),
+    /// see [`ValueShape::U16List`]
+    U16List(Vec<u16>),
+    /// see [`ValueShape::Ipv4List`]
+    Ipv4List(Vec<Ipv4Addr>),
+    /// see [`ValueShape::String`]
+    String(String),
+    /// see [`ValueShape::Bytes`]
+    Bytes(Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        let ok = |shape: ValueShape, value: &[u8]| shape.decode(value).unwrap();
+        assert_eq!(ok(ValueShape::Bool, &[1]), ShapedValue::Bool(true));
+        assert_eq!(ok(ValueShape::U8, &[7]), ShapedValue::U8(7));
+        assert_eq!(ok(ValueShape::U16, &[1, 0]), ShapedValue::U16(256));
+        assert_eq!(ok(ValueShape::U32, &[0, 0, 1, 0]), ShapedValue::U32(256));
+        assert_eq!(
+            ok(ValueShape::Ipv4, &[10, 0, 0, 1]),
+            ShapedValue::Ipv4([10, 0, 0, 1].into())
+        );
+        assert_eq!(
+            ok(ValueShape::U16List, &[0, 1, 0, 2]),
+            ShapedValue::U16List(vec![1, 2])
+        );
+        assert_eq!(
+            ok(ValueShape::String, b"ab"),
+            ShapedValue::String("ab".into())
+        );
+        assert_eq!(ok(ValueShape::Bytes, &[]), ShapedValue::Bytes(vec![]));
+        assert_eq!(ok(ValueShape::Ipv4List, &[]), ShapedValue::Ipv4List(vec![]));
+
+        // widths must match
+        assert!(ValueShape::U16.decode(&[1, 2, 3]).is_err());
+        assert!(ValueShape::U32.decode(&[1, 2]).is_err());
+        assert!(ValueShape::Bool.decode(&[]).is_err());
+        assert!(ValueShape::U16List.decode(&[1]).is_err());
+        assert!(ValueShape::Ipv4List.decode(&[1, 2, 3, 4, 5]).is_err());
+        assert!(ValueShape::String.decode(&[0xff]).is_err());
+    }
+}
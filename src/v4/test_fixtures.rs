@@ -0,0 +1,234 @@
+//! Known-good v4 packets & the messages they decode to, for use in downstream
+//! test suites. Requires the `test-util` feature.
+//!
+//! ```rust
+//! use dhcproto::{Decodable, Decoder, v4::{Message, test_fixtures}};
+//!
+//! let msg = Message::decode(&mut Decoder::new(&test_fixtures::offer()))?;
+//! assert_eq!(msg, test_fixtures::offer_message());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::net::Ipv4Addr;
+
+use crate::v4::{DhcpOption, Flags, HType, Message, MessageType, Opcode, OptionCode};
+
+/// an Offer for 192.168.0.3 from 192.168.0.1, followed by trailing pad
+pub fn offer() -> Vec<u8> {
+    vec![
+        0x02, 0x01, 0x06, 0x00, 0x00, 0x00, 0x15, 0x5c, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0xc0, 0xa8, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xcc, 0x00,
+        0x0a, 0xc4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82, 0x53, 0x63,
+        0x35, 0x01, 0x02, 0x36, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x33, 0x04, 0x00, 0x00, 0x00, 0x3c,
+        0x3a, 0x04, 0x00, 0x00, 0x00, 0x1e, 0x3b, 0x04, 0x00, 0x00, 0x00, 0x34, 0x01, 0x04, 0xff,
+        0xff, 0xff, 0x00, 0x03, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x06, 0x08, 0xc0, 0xa8, 0x00, 0x01,
+        0xc0, 0xa8, 0x01, 0x01, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ]
+}
+
+/// the message [`offer`] decodes to
+pub fn offer_message() -> Message {
+    let mut msg = Message::empty(0x155c);
+    msg.set_opcode(Opcode::BootReply)
+        .set_flags(Flags::default().set_broadcast())
+        .set_yiaddr([192, 168, 0, 3])
+        .set_chaddr_from_mac([0xcc, 0x00, 0x0a, 0xc4, 0x00, 0x00]);
+    let opts = msg.opts_mut();
+    opts.insert(DhcpOption::MessageType(MessageType::Offer));
+    opts.insert(DhcpOption::ServerIdentifier([192, 168, 0, 1].into()));
+    opts.insert(DhcpOption::AddressLeaseTime(60));
+    opts.insert(DhcpOption::Renewal(30));
+    opts.insert(DhcpOption::Rebinding(52));
+    opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+    opts.insert(DhcpOption::Router(vec![[192, 168, 0, 1].into()]));
+    opts.insert(DhcpOption::DomainNameServer(vec![
+        [192, 168, 0, 1].into(),
+        [192, 168, 1, 1].into(),
+    ]));
+    msg
+}
+
+/// another Offer, for 192.168.0.149 in reply to [`discover`]
+pub fn other_offer() -> Vec<u8> {
+    vec![
+        0x02, 0x01, 0x06, 0x00, 0xa6, 0x80, 0x56, 0x74, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0xc0, 0xa8, 0x00, 0x95, 0xc0, 0xa8, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xde, 0xad,
+        0xc0, 0xde, 0xca, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82, 0x53, 0x63,
+        0x35, 0x01, 0x02, 0x36, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x33, 0x04, 0x00, 0x00, 0x00, 0x78,
+        0x3a, 0x04, 0x00, 0x00, 0x00, 0x3c, 0x3b, 0x04, 0x00, 0x00, 0x00, 0x69, 0x01, 0x04, 0xff,
+        0xff, 0xff, 0x00, 0x1c, 0x04, 0xc0, 0xa8, 0x00, 0xff, 0x06, 0x04, 0xc0, 0xa8, 0x00, 0x01,
+        0x03, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ]
+}
+
+/// the message [`other_offer`] decodes to
+pub fn other_offer_message() -> Message {
+    let mut msg = Message::empty(0xa680_5674);
+    msg.set_opcode(Opcode::BootReply)
+        .set_flags(Flags::default().set_broadcast())
+        .set_yiaddr([192, 168, 0, 149])
+        .set_siaddr([192, 168, 0, 1])
+        .set_chaddr_from_mac([0xde, 0xad, 0xc0, 0xde, 0xca, 0xfe]);
+    let opts = msg.opts_mut();
+    opts.insert(DhcpOption::MessageType(MessageType::Offer));
+    opts.insert(DhcpOption::ServerIdentifier([192, 168, 0, 1].into()));
+    opts.insert(DhcpOption::AddressLeaseTime(120));
+    opts.insert(DhcpOption::Renewal(60));
+    opts.insert(DhcpOption::Rebinding(105));
+    opts.insert(DhcpOption::SubnetMask([255, 255, 255, 0].into()));
+    opts.insert(DhcpOption::BroadcastAddr([192, 168, 0, 255].into()));
+    opts.insert(DhcpOption::DomainNameServer(vec![[192, 168, 0, 1].into()]));
+    opts.insert(DhcpOption::Router(vec![[192, 168, 0, 1].into()]));
+    msg
+}
+
+/// a Discover requesting a long parameter list
+pub fn discover() -> Vec<u8> {
+    vec![
+        0x01, 0x01, 0x06, 0x00, 0xa6, 0x80, 0x56, 0x74, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xde, 0xad,
+        0xc0, 0xde, 0xca, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x63, 0x82, 0x53, 0x63,
+        0x35, 0x01, 0x01, 0x37, 0x40, 0xfc, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
+        0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+        0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+        0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36,
+        0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x43, 0x42, 0x33, 0x04, 0x00, 0x00, 0x00, 0x01,
+        0xff,
+    ]
+}
+
+/// the message [`discover`] decodes to
+pub fn discover_message() -> Message {
+    let mut msg = Message::empty(0xa680_5674);
+    msg.set_flags(Flags::default().set_broadcast())
+        .set_chaddr_from_mac([0xde, 0xad, 0xc0, 0xde, 0xca, 0xfe]);
+    let params = [0xfc]
+        .into_iter()
+        .chain(0x01..=0x3d)
+        .chain([0x43, 0x42])
+        .map(OptionCode::from)
+        .collect();
+    let opts = msg.opts_mut();
+    opts.insert(DhcpOption::MessageType(MessageType::Discover));
+    opts.insert(DhcpOption::ParameterRequestList(params));
+    opts.insert(DhcpOption::AddressLeaseTime(1));
+    msg
+}
+
+/// a BOOTP request with every header field set & no options
+pub fn bootreq() -> Vec<u8> {
+    vec![
+        1u8, // op
+        2,   // htype
+        3,   // hlen
+        4,   // ops
+        5, 6, 7, 8, // xid
+        9, 10, // secs
+        11, 12, // flags
+        13, 14, 15, 16, // ciaddr
+        17, 18, 19, 20, // yiaddr
+        21, 22, 23, 24, // siaddr
+        25, 26, 27, 28, // giaddr
+        29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, // chaddr
+        45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67,
+        68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90,
+        91, 92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107,
+        0, // sname: "-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijk",
+        109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109,
+        110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 110,
+        111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 110, 111,
+        112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 110, 111, 112,
+        113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 110, 111, 112, 113,
+        114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 110, 111, 112, 113, 114,
+        115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 109, 0, 0, 0, 0, 0, 0, 0,
+        0, // file: "mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}mnopqrstuvwxyz{|}m",
+        99, 130, 83, 99, // magic cookie
+    ]
+}
+
+/// the message [`bootreq`] decodes to
+pub fn bootreq_message() -> Message {
+    let mut msg = Message::empty(0x0506_0708);
+    // sname & file keep their NUL terminator when decoded
+    let mut sname = (45..=107).collect::<Vec<u8>>();
+    sname.push(0);
+    let mut fname = (109..=125).cycle().take(17 * 7).collect::<Vec<u8>>();
+    fname.extend([109, 0]);
+    msg.set_htype(HType::from(2))
+        .set_hops(4)
+        .set_secs(0x090a)
+        .set_flags(Flags::new(0x0b0c))
+        .set_ciaddr([13, 14, 15, 16])
+        .set_yiaddr([17, 18, 19, 20])
+        .set_siaddr([21, 22, 23, 24])
+        .set_giaddr(Ipv4Addr::new(25, 26, 27, 28))
+        .set_chaddr(&(29..=44).collect::<Vec<u8>>())
+        .set_sname(&sname)
+        .set_fname(&fname);
+    // the full 16 bytes of chaddr are kept, only 3 are in use
+    msg.hlen = 3;
+    msg
+}
+
+/// every fixture as `(name, bytes, expected message)`
+pub fn all() -> Vec<(&'static str, Vec<u8>, Message)> {
+    vec![
+        ("offer", offer(), offer_message()),
+        ("other_offer", other_offer(), other_offer_message()),
+        ("discover", discover(), discover_message()),
+        ("bootreq", bootreq(), bootreq_message()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Decodable, Decoder};
+
+    #[test]
+    fn test_fixtures_decode() -> Result<(), Box<dyn std::error::Error>> {
+        for (name, bytes, expected) in all() {
+            let msg = Message::decode(&mut Decoder::new(&bytes))?;
+            assert_eq!(msg, expected, "fixture {}", name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! User Class option (77) - <https://www.rfc-editor.org/rfc/rfc3004.html>
+//!
+//! RFC 3004 defines the value as a list of length-prefixed classes, but many
+//! clients predate it & send a single opaque string instead. The raw bytes are
+//! kept as received, [`UserClass::interpret`] guesses which form was used.
+//! ```rust
+//! use dhcproto::v4::user_class::{UserClass, UserClassValue};
+//!
+//! let rfc = UserClass::new(vec![b"ipxe".to_vec(), b"lab".to_vec()])?;
+//! assert_eq!(rfc.raw(), b"\x04ipxe\x03lab");
+//! assert_eq!(rfc.interpret(), UserClassValue::Rfc3004(vec![&b"ipxe"[..], &b"lab"[..]]));
+//!
+//! let legacy = UserClass::from_raw(b"iPXE".to_vec());
+//! assert_eq!(legacy.interpret(), UserClassValue::Legacy(&b"iPXE"[..]));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use crate::{
+    decoder::{Decodable, Decoder},
+    error::{DecodeResult, EncodeError, EncodeResult},
+    v4::OptionCode,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// User Class option data, the raw value bytes as received
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct UserClass {
+    data: Vec<u8>,
+}
+
+impl UserClass {
+    /// Create a User Class in the RFC 3004 form, from a list of classes
+    ///
+    /// Returns
+    ///    Err - [`EncodeError::EmptyOptionValue`] if a class is empty, or
+    ///    [`EncodeError::OptionValueTooLong`] if one is longer than 255 bytes
+    pub fn new(classes: Vec<Vec<u8>>) -> EncodeResult<Self> {
+        let code = OptionCode::UserClass.into();
+        let mut data = Vec::with_capacity(classes.iter().map(|c| c.len() + 1).sum());
+        for class in classes {
+            let len = class.len();
+            match u8::try_from(len) {
+                Ok(0) => return Err(EncodeError::EmptyOptionValue { code }),
+                Ok(n) => data.push(n),
+                Err(_) => return Err(EncodeError::OptionValueTooLong { code, len }),
+            }
+            data.extend(class);
+        }
+        Ok(Self { data })
+    }
+    /// Create a User Class from raw value bytes, in either form
+    pub fn from_raw(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+    /// the raw value bytes
+    pub fn raw(&self) -> &[u8] {
+        &self.data
+    }
+    /// consume the User Class, returning the raw value bytes
+    pub fn into_raw(self) -> Vec<u8> {
+        self.data
+    }
+    /// guess the form of the value. If the length bytes exactly cover the
+    /// value without any empty class it's taken to be RFC 3004, otherwise
+    /// it's a legacy opaque string
+    pub fn interpret(&self) -> UserClassValue<'_> {
+        let mut classes = Vec::new();
+        let mut rest = &self.data[..];
+        while let [len, tail @ ..] = rest {
+            let len = *len as usize;
+            if len == 0 || len > tail.len() {
+                return UserClassValue::Legacy(&self.data);
+            }
+            let (class, tail) = tail.split_at(len);
+            classes.push(class);
+            rest = tail;
+        }
+        if classes.is_empty() {
+            UserClassValue::Legacy(&self.data)
+        } else {
+            UserClassValue::Rfc3004(classes)
+        }
+    }
+}
+
+impl Decodable for UserClass {
+    /// reads the remainder of the decoder as the value
+    fn decode(decoder: &mut Decoder<'_>) -> DecodeResult<Self> {
+        let len = decoder.buffer().len();
+        Ok(Self::from_raw(decoder.read_slice(len)?.to_vec()))
+    }
+}
+
+/// A guess at how a [`UserClass`] value was encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserClassValue<'a> {
+    /// RFC 3004 list of classes
+    Rfc3004(Vec<&'a [u8]>),
+    /// a single opaque class, as sent by pre-RFC 3004 clients
+    Legacy(&'a [u8]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpret() {
+        let class = |b: &[u8]| UserClass::from_raw(b.to_vec());
+        assert_eq!(
+            class(b"\x03abc").interpret(),
+            UserClassValue::Rfc3004(vec![&b"abc"[..]])
+        );
+        // length bytes don't cover the value exactly
+        assert_eq!(
+            class(b"\x03ab").interpret(),
+            UserClassValue::Legacy(&b"\x03ab"[..])
+        );
+        assert_eq!(
+            class(b"\x01a\x00").interpret(),
+            UserClassValue::Legacy(&b"\x01a\x00"[..])
+        );
+        assert_eq!(
+            class(b"MSFT 5.0").interpret(),
+            UserClassValue::Legacy(&b"MSFT 5.0"[..])
+        );
+        assert_eq!(class(b"").interpret(), UserClassValue::Legacy(&[]));
+
+        assert!(matches!(
+            UserClass::new(vec![b"a".to_vec(), vec![]]),
+            Err(EncodeError::EmptyOptionValue { code: 77 })
+        ));
+        assert!(matches!(
+            UserClass::new(vec![vec![0; 256]]),
+            Err(EncodeError::OptionValueTooLong { code: 77, len: 256 })
+        ));
+    }
+}
@@ -59,6 +59,8 @@ use serde::{Deserialize, Serialize};
 
 use std::{convert::TryInto, fmt, net::Ipv6Addr};
 
+use rand::RngCore;
+
 // re-export submodules from proto::msg
 pub use self::options::*;
 
@@ -147,12 +149,27 @@ impl Message {
         }
     }
 
+    /// returns a new `Message` with an xid drawn from `rng` and empty opt
+    /// section, so a seeded rng gives reproducible messages
+    /// ```
+    /// # use dhcproto::v6::{Message, MessageType};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// let a = Message::new_with_rng(MessageType::Solicit, &mut StdRng::seed_from_u64(1));
+    /// let b = Message::new_with_rng(MessageType::Solicit, &mut StdRng::seed_from_u64(1));
+    /// assert_eq!(a.xid(), b.xid());
+    /// ```
+    pub fn new_with_rng(msg_type: MessageType, rng: &mut impl RngCore) -> Self {
+        let mut xid = [0; 3];
+        rng.fill_bytes(&mut xid);
+        Self::new_with_id(msg_type, xid)
+    }
+
     /// returns a new `Message` with a given xid and message type and empty opt section
     pub fn new_with_id(msg_type: MessageType, xid: [u8; 3]) -> Self {
         Self {
             msg_type,
             xid,
-            ..Self::default()
+            opts: DhcpOptions::new(),
         }
     }
 
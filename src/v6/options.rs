@@ -603,29 +603,29 @@ impl Decodable for DhcpOption {
             OptionCode::ClientId => DhcpOption::ClientId(decoder.read_slice(len)?.to_vec()),
             OptionCode::ServerId => DhcpOption::ServerId(decoder.read_slice(len)?.to_vec()),
             OptionCode::IANA => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::IANA(IANA::decode(&mut dec)?)
             }
             OptionCode::IATA => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::IATA(IATA::decode(&mut dec)?)
             }
             OptionCode::IAAddr => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::IAAddr(IAAddr::decode(&mut dec)?)
             }
             OptionCode::ORO => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::ORO(ORO::decode(&mut dec)?)
             }
             OptionCode::Preference => DhcpOption::Preference(decoder.read_u8()?),
             OptionCode::ElapsedTime => DhcpOption::ElapsedTime(decoder.read_u16()?),
             OptionCode::RelayMsg => {
-                let mut relay_dec = Decoder::new(decoder.read_slice(len)?);
+                let mut relay_dec = decoder.sub_decoder(len)?;
                 DhcpOption::RelayMsg(RelayMessage::decode(&mut relay_dec)?)
             }
             OptionCode::Authentication => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::Authentication(Authentication::decode(&mut dec)?)
             }
             OptionCode::ServerUnicast => DhcpOption::ServerUnicast(decoder.read::<16>()?.into()),
@@ -651,7 +651,7 @@ impl Decodable for DhcpOption {
             OptionCode::VendorOpts => DhcpOption::VendorOpts(VendorOpts {
                 num: decoder.read_u32()?,
                 opts: {
-                    let mut opt_decoder = Decoder::new(decoder.read_slice(len - 4)?);
+                    let mut opt_decoder = decoder.sub_decoder(len - 4)?;
                     DhcpOptions::decode(&mut opt_decoder)?
                 },
             }),
@@ -660,11 +660,11 @@ impl Decodable for DhcpOption {
             OptionCode::ReconfAccept => DhcpOption::ReconfAccept,
             OptionCode::DNSNameServer => DhcpOption::DNSNameServer(decoder.read_ipv6s(len)?),
             OptionCode::IAPD => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::IAPD(IAPD::decode(&mut dec)?)
             }
             OptionCode::IAPDPrefix => {
-                let mut dec = Decoder::new(decoder.read_slice(len)?);
+                let mut dec = decoder.sub_decoder(len)?;
                 DhcpOption::IAPDPrefix(IAPDPrefix::decode(&mut dec)?)
             }
             OptionCode::DomainSearchList => {